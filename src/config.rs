@@ -2,31 +2,113 @@ use anyhow::{Result, anyhow};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, channel};
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Deserialize, Clone)]
+use crate::overlay::{MonitorSelector, PlaybackMode, check_fps};
+
+/// Keys a preset table may contain, for flagging typos. Keep in sync with
+/// the fields of [`PresetConfig`], plus `extends`, which is resolved before
+/// deserializing.
+const PRESET_KEYS: &[&str] = &[
+    "extends",
+    "path",
+    "fps",
+    "compress",
+    "click_through",
+    "nearest",
+    "opacity",
+    "position",
+    "range",
+    "sheet",
+    "playback",
+    "monitor",
+    "always_on_top",
+    "rotate",
+    "flip_h",
+    "flip_v",
+];
+
+#[derive(Debug, Default, Deserialize, Clone)]
 pub struct PresetConfig {
     pub path: String,
-    pub fps: Option<u64>,
+    /// Frames per second, fractional values such as 7.5 allowed
+    pub fps: Option<f64>,
     pub compress: Option<bool>,
+    /// Let mouse input pass through the window to whatever is below
+    pub click_through: Option<bool>,
+    /// Scale with nearest-neighbor sampling, for pixel art
+    pub nearest: Option<bool>,
+    /// Overlay opacity from 0.0 to 1.0
+    pub opacity: Option<f32>,
+    /// Window position, `x,y` or an anchor such as `top-right`
+    pub position: Option<String>,
+    /// Frames to play, `START:END` with either side optional
+    pub range: Option<String>,
+    /// Play `path` as a sprite sheet, `COLS,ROWS` or `COLS,ROWS,FRAMES`
+    pub sheet: Option<String>,
+    /// `forward`, `reverse` or `ping-pong`
+    pub playback: Option<PlaybackMode>,
+    /// Monitor to show the window on, by index from 0 or by name
+    pub monitor: Option<MonitorSelector>,
+    /// Keep the window above other windows
+    pub always_on_top: Option<bool>,
+    /// Clockwise rotation in degrees, 90, 180 or 270
+    pub rotate: Option<u32>,
+    /// Mirror the animation left to right
+    pub flip_h: Option<bool>,
+    /// Mirror the animation top to bottom
+    pub flip_v: Option<bool>,
 }
 
 impl PresetConfig {
     pub fn use_compression(&self) -> bool {
         self.compress.unwrap_or(false)
     }
+
+    pub fn click_through(&self) -> bool {
+        self.click_through.unwrap_or(false)
+    }
+
+    pub fn nearest(&self) -> bool {
+        self.nearest.unwrap_or(false)
+    }
+
+    pub fn always_on_top(&self) -> bool {
+        self.always_on_top.unwrap_or(false)
+    }
+
+    pub fn flip_h(&self) -> bool {
+        self.flip_h.unwrap_or(false)
+    }
+
+    pub fn flip_v(&self) -> bool {
+        self.flip_v.unwrap_or(false)
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug)]
 pub struct Config {
     pub default: Option<PresetConfig>,
-    #[serde(flatten)]
+    /// Key names mapped to presets played once when the key is pressed
+    pub reactions: HashMap<String, String>,
+    /// Every other top-level table, by name
     pub presets: HashMap<String, PresetConfig>,
 }
 
 impl Config {
-    pub fn load() -> Result<Option<Self>> {
-        let config_path = get_config_path()?;
+    /// Load the config from `path`, or from the default location (see
+    /// [`get_config_path`]) when none is given. A missing default config is not
+    /// an error; a missing explicit one is.
+    pub fn load(path: Option<&Path>) -> Result<Option<Self>> {
+        let config_path = match path {
+            Some(path) if !path.exists() => {
+                return Err(anyhow!("Config file {} does not exist", path.display()));
+            }
+            Some(path) => path.to_path_buf(),
+            None => get_config_path()?,
+        };
 
         if !config_path.exists() {
             log::debug!("Config file not found at {}", config_path.display());
@@ -38,14 +120,72 @@ impl Config {
         let config_content = fs::read_to_string(&config_path)
             .map_err(|e| anyhow!("Failed to read config file: {}", e))?;
 
-        let config: Config = toml::from_str(&config_content)
-            .map_err(|e| anyhow!("Failed to parse config file: {}", e))?;
+        let config = Self::parse(&config_content).map_err(|e| {
+            anyhow!(
+                "Failed to parse config file {}: {}",
+                config_path.display(),
+                e
+            )
+        })?;
 
         log::debug!("Loaded config with {} presets", config.presets.len());
 
         Ok(Some(config))
     }
 
+    /// Parse config file contents, checking each preset as it goes.
+    ///
+    /// Presets are the top-level tables other than `[reactions]`, so the TOML
+    /// is read as plain values first to report problems by preset name.
+    /// Malformed values and empty paths are errors; unknown keys, a likely
+    /// misspelling of `[default]` and paths that don't exist are warnings.
+    /// Presets with `extends` are merged over their base first, so inherited
+    /// settings are checked too.
+    pub fn parse(content: &str) -> Result<Self> {
+        let table: toml::Table = content.parse()?;
+
+        let mut config = Config {
+            default: None,
+            reactions: HashMap::new(),
+            presets: HashMap::new(),
+        };
+        let mut tables = HashMap::new();
+        for (name, value) in table {
+            if name == "reactions" {
+                config.reactions = value.try_into().map_err(|e| {
+                    anyhow!("[reactions] must map key names to preset names: {}", e)
+                })?;
+                continue;
+            }
+
+            let toml::Value::Table(preset) = value else {
+                return Err(anyhow!(
+                    "Top-level key '{}' is not a preset table; preset settings go under a [name] header",
+                    name
+                ));
+            };
+            warn_unknown_keys(&name, &preset);
+            if name != "default" && edit_distance(&name, "default") <= 2 {
+                log::warn!(
+                    "Preset '{}' looks like a misspelling of [default], it won't be used as the default",
+                    name
+                );
+            }
+            tables.insert(name, preset);
+        }
+
+        for name in tables.keys() {
+            let preset = parse_preset(name, resolve_extends(name, &tables)?)?;
+            if name == "default" {
+                config.default = Some(preset);
+            } else {
+                config.presets.insert(name.clone(), preset);
+            }
+        }
+
+        Ok(config)
+    }
+
     pub fn get_preset(&self, name: &str) -> Option<&PresetConfig> {
         // Special case for "default"
         if name == "default" {
@@ -59,6 +199,16 @@ impl Config {
         self.default.as_ref()
     }
 
+    /// The preset a command-line argument plays: the default preset when there
+    /// is no argument, nothing when it's a path
+    pub fn selected_preset(&self, path_or_preset: Option<&str>) -> Option<&PresetConfig> {
+        match path_or_preset {
+            None => self.get_default(),
+            Some(arg) if is_likely_path(arg) => None,
+            Some(name) => self.get_preset(name),
+        }
+    }
+
     pub fn list_presets(&self) -> Vec<String> {
         let mut presets = Vec::new();
 
@@ -74,25 +224,248 @@ impl Config {
     }
 }
 
-fn get_config_path() -> Result<PathBuf> {
-    let home_dir = dirs::home_dir()
-        .or_else(|| std::env::var("HOME").ok().map(PathBuf::from))
-        .ok_or_else(|| anyhow!("Could not determine home directory"))?;
+/// Warn about keys in the preset table `name` that no setting uses
+fn warn_unknown_keys(name: &str, table: &toml::Table) {
+    for key in table.keys() {
+        if PRESET_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        match PRESET_KEYS
+            .iter()
+            .find(|known| edit_distance(key, known) <= 2)
+        {
+            Some(known) => log::warn!(
+                "Preset '{}' has unknown key '{}', did you mean '{}'?",
+                name,
+                key,
+                known
+            ),
+            None => log::warn!("Preset '{}' has unknown key '{}', ignoring it", name, key),
+        }
+    }
+}
+
+/// The table of preset `name` with everything it inherits through `extends`
+/// filled in, nearer presets taking precedence
+fn resolve_extends(name: &str, tables: &HashMap<String, toml::Table>) -> Result<toml::Table> {
+    let mut chain = vec![name];
+    let mut current = name;
+    while let Some(base) = tables[current].get("extends") {
+        let base = base
+            .as_str()
+            .ok_or_else(|| anyhow!("Preset '{}': extends must be a preset name", current))?;
+        if !tables.contains_key(base) {
+            return Err(anyhow!(
+                "Preset '{}' extends unknown preset '{}'",
+                current,
+                base
+            ));
+        }
+        if chain.contains(&base) {
+            chain.push(base);
+            return Err(anyhow!(
+                "Preset '{}' extends itself through {}",
+                name,
+                chain.join(" -> ")
+            ));
+        }
+        chain.push(base);
+        current = base;
+    }
+
+    let mut merged = toml::Table::new();
+    for preset in chain.iter().rev() {
+        merged.extend(tables[*preset].clone());
+    }
+    Ok(merged)
+}
+
+/// Deserialize the preset table `name` and check its path
+fn parse_preset(name: &str, table: toml::Table) -> Result<PresetConfig> {
+    let preset: PresetConfig = toml::Value::Table(table)
+        .try_into()
+        .map_err(|e| anyhow!("Preset '{}': {}", name, e))?;
+
+    if preset.path.trim().is_empty() {
+        return Err(anyhow!("Preset '{}' has an empty path", name));
+    }
+    if let Some(fps) = preset.fps {
+        check_fps(fps).map_err(|e| anyhow!("Preset '{}': fps {}", name, e))?;
+    }
+    if !Path::new(&preset.path).exists() {
+        log::warn!(
+            "Preset '{}' points to '{}', which doesn't exist",
+            name,
+            preset.path
+        );
+    }
+
+    Ok(preset)
+}
+
+/// Set `key` to the string `value` in preset `name` of the config file at
+/// `path`, keeping the rest of the file, comments included, as it was
+pub fn save_preset_value(path: &Path, name: &str, key: &str, value: &str) -> Result<()> {
+    let content =
+        fs::read_to_string(path).map_err(|e| anyhow!("Failed to read config file: {}", e))?;
+    let updated = set_preset_value(&content, name, key, value)?;
+    Config::parse(&updated)?;
+    fs::write(path, updated).map_err(|e| anyhow!("Failed to write config file: {}", e))
+}
+
+/// Set `key` in the `[name]` table of `content`, keeping the comment after
+/// an existing value, or add it at the end of the table when the preset
+/// doesn't set it yet
+fn set_preset_value(content: &str, name: &str, key: &str, value: &str) -> Result<String> {
+    let mut document: toml_edit::DocumentMut = content
+        .parse()
+        .map_err(|e| anyhow!("Failed to parse config file: {}", e))?;
+    let table = document
+        .get_mut(name)
+        .and_then(|item| item.as_table_like_mut())
+        .ok_or_else(|| anyhow!("No [{}] table in the config", name))?;
+
+    let mut updated = toml_edit::Value::from(value);
+    if let Some(existing) = table.get(key).and_then(|item| item.as_value()) {
+        *updated.decor_mut() = existing.decor().clone();
+    }
+    table.insert(key, toml_edit::Item::Value(updated));
+
+    Ok(document.to_string())
+}
+
+/// Levenshtein distance between two strings, for suggesting fixes to typos
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// How long the config has to stay unchanged before it's reloaded, since
+/// editors often save a file in several writes
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Watches the config file for `--watch`
+pub struct ConfigWatcher {
+    _watcher: notify::RecommendedWatcher,
+    receiver: Receiver<()>,
+    path: PathBuf,
+    last_change: Option<Instant>,
+}
+
+impl ConfigWatcher {
+    /// Watch the directory holding `path` rather than the file itself, so
+    /// editors that save by replacing the file are still noticed
+    pub fn new(path: &Path) -> Result<Self> {
+        use notify::{EventKind, RecursiveMode, Watcher};
+
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| anyhow!("{} is not a file", path.display()))?
+            .to_os_string();
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        let (sender, receiver) = channel();
+        let mut watcher =
+            notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+                if let Ok(event) = result
+                    && matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                    && event
+                        .paths
+                        .iter()
+                        .any(|path| path.file_name() == Some(file_name.as_os_str()))
+                {
+                    let _ = sender.send(());
+                }
+            })?;
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+        log::info!("Watching {} for config changes", path.display());
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver,
+            path: path.to_path_buf(),
+            last_change: None,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the config changed and has since been left alone for
+    /// [`RELOAD_DEBOUNCE`]. Reports each burst of writes once.
+    pub fn poll(&mut self, now: Instant) -> bool {
+        if self.receiver.try_iter().count() > 0 {
+            self.last_change = Some(now);
+        }
+        settled(&mut self.last_change, now)
+    }
+}
+
+fn settled(last_change: &mut Option<Instant>, now: Instant) -> bool {
+    match *last_change {
+        Some(changed) if now.duration_since(changed) >= RELOAD_DEBOUNCE => {
+            *last_change = None;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Default location of the config file: `anibuddy/config.toml` under
+/// `$XDG_CONFIG_HOME`, or under `~/.config` when that isn't set
+pub fn get_config_path() -> Result<PathBuf> {
+    config_path_in(
+        std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from),
+        dirs::home_dir().or_else(|| std::env::var("HOME").ok().map(PathBuf::from)),
+    )
+}
+
+fn config_path_in(config_home: Option<PathBuf>, home_dir: Option<PathBuf>) -> Result<PathBuf> {
+    // The XDG spec says to ignore relative paths here
+    let config_home = match config_home.filter(|dir| dir.is_absolute()) {
+        Some(dir) => dir,
+        None => home_dir
+            .ok_or_else(|| anyhow!("Could not determine home directory"))?
+            .join(".config"),
+    };
 
-    Ok(home_dir
-        .join(".config")
-        .join("anibuddy")
-        .join("config.toml"))
+    Ok(config_home.join("anibuddy").join("config.toml"))
 }
 
+/// Extensions of files anibuddy plays, which mark a dotted argument as a path
+const MEDIA_EXTENSIONS: &[&str] = &["gif", "png", "webp", "jpg", "jpeg", "bmp", "tif", "tiff"];
+
+/// Whether a command-line argument looks like a file path rather than a preset
+/// name. A dot alone doesn't decide it, so presets such as `v1.2` still resolve;
+/// dotted names without a media extension are looked up as presets first and
+/// only then tried as paths.
 pub fn is_likely_path(input: &str) -> bool {
-    // Check if the input looks like a file path rather than a preset name
     input.contains('/')
         || input.contains('\\')
-        || input.starts_with("./")
-        || input.starts_with("../")
-        || input.starts_with("~/")
-        || input.contains('.') && input.len() > 1 // Has extension or is relative path like "./dir"
+        || input.starts_with('~')
+        || input == "."
+        || input == ".."
+        || Path::new(input)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                MEDIA_EXTENSIONS
+                    .iter()
+                    .any(|known| ext.eq_ignore_ascii_case(known))
+            })
 }
 
 #[cfg(test)]
@@ -110,6 +483,8 @@ mod tests {
         assert!(is_likely_path("animation.gif"));
         assert!(is_likely_path("frames.png"));
         assert!(is_likely_path("C:\\frames"));
+        assert!(is_likely_path("Wave.GIF"));
+        assert!(is_likely_path("."));
 
         // These should be treated as preset names
         assert!(!is_likely_path("frames"));
@@ -117,29 +492,309 @@ mod tests {
         assert!(!is_likely_path("1"));
         assert!(!is_likely_path("dancer"));
         assert!(!is_likely_path("default"));
+
+        // Dots without a media extension are allowed in preset names
+        assert!(!is_likely_path("v1.2"));
+        assert!(!is_likely_path("konata.left"));
+        assert!(!is_likely_path("wave.gif.bak"));
+
+        let config = Config::parse("[\"konata.left\"]\npath = \"/konata\"\n").unwrap();
+        assert_eq!(
+            config
+                .selected_preset(Some("konata.left"))
+                .map(|preset| preset.path.as_str()),
+            Some("/konata")
+        );
+    }
+
+    #[test]
+    fn test_load_explicit_path() {
+        let dir = std::env::temp_dir().join(format!("anibuddy-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("custom.toml");
+        std::fs::write(&path, "[wave]\npath = \"/wave.gif\"\nfps = 12\n").unwrap();
+
+        let config = Config::load(Some(&path)).unwrap().unwrap();
+        assert_eq!(config.get_preset("wave").unwrap().fps, Some(12.0));
+
+        // Fractional rates parse alongside the integer ones
+        let config = Config::parse("[slides]\npath = \"/slides\"\nfps = 0.5\n").unwrap();
+        assert_eq!(config.get_preset("slides").unwrap().fps, Some(0.5));
+        assert!(Config::parse("[slides]\npath = \"/slides\"\nfps = 1e-20\n").is_err());
+
+        assert!(Config::load(Some(&dir.join("missing.toml"))).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_config_path_without_home() {
+        assert!(config_path_in(None, None).is_err());
+        assert_eq!(
+            config_path_in(None, Some(PathBuf::from("/home/user"))).unwrap(),
+            PathBuf::from("/home/user/.config/anibuddy/config.toml")
+        );
+    }
+
+    #[test]
+    fn test_config_path_prefers_xdg_config_home() {
+        let home = Some(PathBuf::from("/home/user"));
+        assert_eq!(
+            config_path_in(Some(PathBuf::from("/xdg")), home.clone()).unwrap(),
+            PathBuf::from("/xdg/anibuddy/config.toml")
+        );
+        assert_eq!(
+            config_path_in(Some(PathBuf::from("/xdg")), None).unwrap(),
+            PathBuf::from("/xdg/anibuddy/config.toml")
+        );
+        assert_eq!(
+            config_path_in(Some(PathBuf::from("relative")), home).unwrap(),
+            PathBuf::from("/home/user/.config/anibuddy/config.toml")
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_preset() {
+        let error = |content: &str| Config::parse(content).unwrap_err().to_string();
+
+        assert!(error("fps = 30\n[wave]\npath = \"/wave.gif\"").contains("'fps'"));
+        assert!(error("[wave]\npath = \"  \"").contains("'wave' has an empty path"));
+        assert!(error("[wave]\nfps = 30").contains("Preset 'wave': missing field `path`"));
+
+        let message = error("[wave]\npath = \"/wave.gif\"\nfps = \"fast\"");
+        assert!(message.contains("Preset 'wave'"), "{}", message);
+        assert!(message.contains("fast"), "{}", message);
+
+        assert!(error("[reactions]\nw = 3").contains("[reactions]"));
+        assert!(Config::parse("[wave\npath = 1").is_err());
+    }
+
+    #[test]
+    fn test_parse_tolerates_typos_with_warnings() {
+        let config = Config::parse(
+            r#"
+            [defualt]
+            path = "/idle"
+            fsp = 12
+            "#,
+        )
+        .unwrap();
+        assert!(config.default.is_none());
+        assert_eq!(config.get_preset("defualt").unwrap().fps, None);
+    }
+
+    #[test]
+    fn test_extends_chain() {
+        let config = Config::parse(
+            r#"
+            [base]
+            path = "/base.gif"
+            fps = 12
+            opacity = 0.5
+            position = "top-left"
+
+            [middle]
+            extends = "base"
+            fps = 24
+            playback = "reverse"
+
+            [leaf]
+            extends = "middle"
+            path = "/leaf.gif"
+            opacity = 0.8
+            "#,
+        )
+        .unwrap();
+
+        let leaf = config.get_preset("leaf").unwrap();
+        assert_eq!(leaf.path, "/leaf.gif");
+        assert_eq!(leaf.fps, Some(24.0));
+        assert_eq!(leaf.opacity, Some(0.8));
+        assert_eq!(leaf.position.as_deref(), Some("top-left"));
+        assert_eq!(leaf.playback, Some(PlaybackMode::Reverse));
+
+        let middle = config.get_preset("middle").unwrap();
+        assert_eq!(middle.path, "/base.gif");
+        assert_eq!(middle.opacity, Some(0.5));
+    }
+
+    #[test]
+    fn test_extends_errors() {
+        let error = Config::parse(
+            r#"
+            [a]
+            extends = "b"
+            path = "/a.gif"
+
+            [b]
+            extends = "a"
+            "#,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("extends itself"), "{}", error);
+
+        let error = Config::parse("[a]\nextends = \"a\"\npath = \"/a.gif\"")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("a -> a"), "{}", error);
+
+        let error = Config::parse("[a]\nextends = \"missing\"\npath = \"/a.gif\"")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("unknown preset 'missing'"), "{}", error);
+    }
+
+    #[test]
+    fn test_reload_debounce() {
+        let start = Instant::now();
+        let mut last_change = None;
+        assert!(!settled(&mut last_change, start));
+
+        // A later write restarts the wait
+        last_change = Some(start);
+        assert!(!settled(&mut last_change, start + RELOAD_DEBOUNCE / 2));
+        last_change = Some(start + RELOAD_DEBOUNCE / 2);
+        assert!(!settled(&mut last_change, start + RELOAD_DEBOUNCE));
+
+        assert!(settled(&mut last_change, start + RELOAD_DEBOUNCE * 2));
+        assert!(!settled(&mut last_change, start + RELOAD_DEBOUNCE * 3));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("default", "default"), 0);
+        assert_eq!(edit_distance("defualt", "default"), 2);
+        assert_eq!(edit_distance("defaults", "default"), 1);
+        assert_eq!(edit_distance("fsp", "fps"), 2);
+        assert_eq!(edit_distance("", "fps"), 3);
+    }
+
+    #[test]
+    fn test_reactions_section() {
+        let config = Config::parse(
+            r#"
+            [default]
+            path = "/idle"
+
+            [wave]
+            path = "/wave.gif"
+            fps = 24
+
+            [reactions]
+            w = "wave"
+            Space = "wave"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.reactions.len(), 2);
+        assert_eq!(config.reactions["w"], "wave");
+        assert_eq!(config.list_presets(), vec!["default", "wave"]);
+    }
+
+    #[test]
+    fn test_selected_preset() {
+        let config = Config::parse(
+            r#"
+            [default]
+            path = "/idle"
+
+            [wave]
+            path = "/wave.gif"
+            click_through = true
+            nearest = true
+            playback = "ping-pong"
+            monitor = 1
+            always_on_top = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.selected_preset(None).unwrap().path, "/idle");
+        let wave = config.selected_preset(Some("wave")).unwrap();
+        assert!(wave.click_through());
+        assert!(wave.nearest());
+        assert_eq!(wave.playback, Some(PlaybackMode::PingPong));
+        assert_eq!(wave.monitor, Some(MonitorSelector::Index(1)));
+        assert!(wave.always_on_top());
+        assert!(!config.selected_preset(None).unwrap().click_through());
+        assert!(config.selected_preset(Some("./wave.gif")).is_none());
+        assert!(config.selected_preset(Some("missing")).is_none());
     }
 
     #[test]
     fn test_compression_default() {
         let preset_with_compress = PresetConfig {
             path: "/test".to_string(),
-            fps: Some(30),
+            fps: Some(30.0),
             compress: Some(true),
+            ..Default::default()
         };
         assert!(preset_with_compress.use_compression());
 
         let preset_without_compress = PresetConfig {
             path: "/test".to_string(),
-            fps: Some(30),
+            fps: Some(30.0),
             compress: None,
+            ..Default::default()
         };
         assert!(!preset_without_compress.use_compression());
 
         let preset_with_false_compress = PresetConfig {
             path: "/test".to_string(),
-            fps: Some(30),
+            fps: Some(30.0),
             compress: Some(false),
+            ..Default::default()
         };
         assert!(!preset_with_false_compress.use_compression());
     }
+
+    #[test]
+    fn test_set_preset_value() {
+        let content = "# my presets\n[konata]\npath = \"./konata\"\nposition = \"1,2\" # old\n\n[dancing]\npath = \"./dancing\"\n";
+
+        let moved = set_preset_value(content, "konata", "position", "300,40").unwrap();
+        assert_eq!(
+            moved,
+            "# my presets\n[konata]\npath = \"./konata\"\nposition = \"300,40\" # old\n\n[dancing]\npath = \"./dancing\"\n"
+        );
+
+        // Added to the preset's table when it doesn't set it, leaving other presets alone
+        let added = set_preset_value(content, "dancing", "position", "-5,7").unwrap();
+        assert!(added.contains("[dancing]\npath = \"./dancing\"\nposition = \"-5,7\"\n"));
+        assert!(added.contains("position = \"1,2\" # old"));
+        assert_eq!(
+            Config::parse(&added).unwrap().presets["dancing"]
+                .position
+                .as_deref(),
+            Some("-5,7")
+        );
+
+        assert!(set_preset_value(content, "missing", "position", "0,0").is_err());
+    }
+
+    #[test]
+    fn test_set_preset_value_quoted_name() {
+        let content = "[\"cat.v2\"]\npath = \"./cat\"\n\n[cat]\npath = \"./old-cat\"\n";
+
+        let updated = set_preset_value(content, "cat.v2", "position", "10,20").unwrap();
+        let config = Config::parse(&updated).unwrap();
+        assert_eq!(config.presets["cat.v2"].position.as_deref(), Some("10,20"));
+        assert_eq!(config.presets["cat"].position, None);
+
+        // A header that only starts with the name is a different preset
+        assert!(set_preset_value("[catalog]\npath = \"./c\"\n", "cat", "position", "0,0").is_err());
+    }
+
+    #[test]
+    fn test_set_preset_value_multiline_array() {
+        let content = "[konata]\npath = \"./konata\"\nextra = [\n  [1, 2],\n  [3, 4],\n]\nposition = \"1,2\"\n";
+
+        let updated = set_preset_value(content, "konata", "position", "5,6").unwrap();
+        assert_eq!(
+            updated,
+            "[konata]\npath = \"./konata\"\nextra = [\n  [1, 2],\n  [3, 4],\n]\nposition = \"5,6\"\n"
+        );
+    }
 }
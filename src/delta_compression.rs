@@ -1,87 +1,342 @@
-use anyhow::Result;
+//! GPU delta compression for frame sequences.
+//!
+//! Deltas are always computed on straight (non-premultiplied) RGBA, which is
+//! what the loaders produce. Every channel, alpha included, is differenced and
+//! clamped independently, so reconstruction never depends on the alpha value of
+//! a pixel and soft edges come back exactly as they went in. Premultiplication
+//! happens only at display time, in the overlay pipeline's blend state.
+//! Callers must not hand premultiplied frames to [`DeltaCompressor`].
+//!
+//! Each delta is taken against the frame playback will have reconstructed
+//! rather than the original, so encoder and decoder never drift apart.
+//!
+//! Deltas store each channel difference times a scale, [`DEFAULT_DELTA_SCALE`]
+//! unless [`CompressionOptions`] picks a coarser one. The default is lossless.
+//!
+//! [`compress_sequence_cpu`] and [`reconstruct_frame_cpu`] do the same math on
+//! the CPU, bit for bit, for adapters that can't run the compute pipelines.
+
+use anyhow::{Result, anyhow};
+use bytemuck::{Pod, Zeroable};
 use image::RgbaImage;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::ops::{Bound, Range};
 use std::sync::Arc;
+use wgpu::util::DeviceExt;
 
-const DELTA_CALCULATE_SHADER: &str = r#"
-@group(0) @binding(0)
-var current_frame: texture_2d<f32>;
-@group(0) @binding(1)
-var previous_frame: texture_2d<f32>;
-@group(0) @binding(2)
-var delta_output: texture_storage_2d<rgba16sint, write>;
-
-@compute @workgroup_size(8, 8)
-fn cs_main(@builtin(global_invocation_id) global_id: vec3<u32>) {
-    let dims = textureDimensions(current_frame);
-    let coords = vec2<i32>(i32(global_id.x), i32(global_id.y));
-    
-    if (coords.x >= i32(dims.x) || coords.y >= i32(dims.y)) {
-        return;
-    }
-    
-    let current_pixel = textureLoad(current_frame, coords, 0);
-    let previous_pixel = textureLoad(previous_frame, coords, 0);
-    
-    // Calculate delta as signed difference
-    let delta = current_pixel - previous_pixel;
-    
-    // Convert to signed 16-bit integer format [-32767, 32767] range for much higher precision
-    let delta_int = vec4<i32>(
-        i32(clamp(delta.r * 32767.0, -32767.0, 32767.0)),
-        i32(clamp(delta.g * 32767.0, -32767.0, 32767.0)),
-        i32(clamp(delta.b * 32767.0, -32767.0, 32767.0)),
-        i32(clamp(delta.a * 32767.0, -32767.0, 32767.0))
-    );
-    
-    textureStore(delta_output, coords, delta_int);
+use crate::shader_reload::shader_source;
+
+const DELTA_CALCULATE_SHADER: &str = include_str!("shaders/delta_calculate.wgsl");
+
+const FRAME_RECONSTRUCT_SHADER: &str = include_str!("shaders/frame_reconstruct.wgsl");
+
+/// Formats the compute passes write through write-only storage textures
+const STORAGE_FORMATS: [wgpu::TextureFormat; 2] = [
+    wgpu::TextureFormat::Rgba16Sint,
+    wgpu::TextureFormat::Rgba8Unorm,
+];
+
+/// Stored value for a full-range channel difference, the finest the `i16`
+/// deltas can hold. Every 8-bit step maps to a distinct value, so deltas at
+/// this scale are lossless.
+pub const DEFAULT_DELTA_SCALE: f32 = 32767.0;
+
+/// Uniforms shared by both compute shaders
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct DeltaParams {
+    scale: f32,
+    tile_size: u32,
+    _padding: [u32; 2],
 }
-"#;
-
-const FRAME_RECONSTRUCT_SHADER: &str = r#"
-@group(0) @binding(0)
-var base_frame: texture_2d<f32>;
-@group(0) @binding(1)
-var delta_frame: texture_2d<i32>;
-@group(0) @binding(2)
-var output_frame: texture_storage_2d<rgba8unorm, write>;
-
-@compute @workgroup_size(8, 8)
-fn cs_main(@builtin(global_invocation_id) global_id: vec3<u32>) {
-    let dims = textureDimensions(base_frame);
-    let coords = vec2<i32>(i32(global_id.x), i32(global_id.y));
-    
-    if (coords.x >= i32(dims.x) || coords.y >= i32(dims.y)) {
-        return;
-    }
-    
-    let base_pixel = textureLoad(base_frame, coords, 0);
-    let delta_pixel = textureLoad(delta_frame, coords, 0);
-    
-    // Convert delta back to float with higher precision
-    let delta_float = vec4<f32>(
-        f32(delta_pixel.r) / 32767.0,
-        f32(delta_pixel.g) / 32767.0,
-        f32(delta_pixel.b) / 32767.0,
-        f32(delta_pixel.a) / 32767.0
-    );
-    
-    let reconstructed = clamp(base_pixel + delta_float, vec4<f32>(0.0), vec4<f32>(1.0));
-    textureStore(output_frame, coords, reconstructed);
+
+/// How a sequence is split into keyframes and quantized into deltas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompressionOptions {
+    /// Store every Nth frame in full as well as frame 0
+    pub keyframe_interval: Option<usize>,
+    /// Stored value for a full-range channel difference, from 1 up to
+    /// [`DEFAULT_DELTA_SCALE`]. Lower scales quantize more coarsely, which
+    /// makes deltas more repetitive at the cost of small per-frame errors.
+    pub delta_scale: f32,
+    /// Side in pixels of the square tiles deltas are split into, so only
+    /// tiles with a change are stored and uploaded
+    pub tile_size: u32,
+    /// Store a delta for the whole frame once more than this share of its
+    /// tiles changed, where the tile list no longer pays for itself
+    pub max_dirty_fraction: f32,
+}
+
+impl Default for CompressionOptions {
+    fn default() -> Self {
+        Self {
+            keyframe_interval: None,
+            delta_scale: DEFAULT_DELTA_SCALE,
+            tile_size: 16,
+            max_dirty_fraction: 0.5,
+        }
+    }
 }
-"#;
 
+/// Tiles covered by a sparse delta
+struct DeltaTiles {
+    /// Side of the square tiles in pixels
+    size: u32,
+    /// Column and row of every tile with a change, in the order their data is stored
+    dirty: Vec<(u32, u32)>,
+}
+
+/// Per-channel differences producing one frame from the one before it.
+///
+/// Most of a delta is usually zero, so the data is kept in memory with zero
+/// runs collapsed: a `0` is followed by the length of its run (1 to 65535,
+/// stored as the bits of a `u16`) and any other value stands for itself.
+/// [`DeltaFrame::data`] expands it again right before upload.
+///
+/// Deltas split into tiles hold data only for the tiles that changed, row by
+/// row within each tile, and only those tiles are uploaded.
 pub struct DeltaFrame {
-    pub data: Vec<i16>,
+    runs: Vec<i16>,
+    /// Tiles the data covers, or `None` when it covers the whole frame
+    tiles: Option<DeltaTiles>,
+    /// Share of tiles with a change, 1 for deltas never split into tiles
+    dirty_fraction: f32,
     pub width: u32,
     pub height: u32,
+    /// Scale the data was quantized with
+    pub scale: f32,
+}
+
+impl DeltaFrame {
+    /// Encode `data`, one value per channel of a `width` x `height` frame
+    pub fn new(data: &[i16], width: u32, height: u32, scale: f32) -> Self {
+        debug_assert_eq!(data.len(), width as usize * height as usize * 4);
+        Self {
+            runs: encode_zero_runs(data),
+            tiles: None,
+            dirty_fraction: 1.0,
+            width,
+            height,
+            scale,
+        }
+    }
+
+    /// Encode `data` keeping only the `tile_size` tiles with a change, or the
+    /// whole frame when more than `max_dirty_fraction` of the tiles changed
+    pub fn tiled(
+        data: &[i16],
+        width: u32,
+        height: u32,
+        scale: f32,
+        tile_size: u32,
+        max_dirty_fraction: f32,
+    ) -> Self {
+        let columns = width.div_ceil(tile_size);
+        let rows = height.div_ceil(tile_size);
+
+        let mut dirty = Vec::new();
+        let mut tile_data = Vec::new();
+        for tile in (0..rows).flat_map(|row| (0..columns).map(move |column| (column, row))) {
+            let changed = tile_rows(width, height, tile_size, tile)
+                .any(|range| data[range].iter().any(|&value| value != 0));
+            if changed {
+                dirty.push(tile);
+                for range in tile_rows(width, height, tile_size, tile) {
+                    tile_data.extend_from_slice(&data[range]);
+                }
+            }
+        }
+
+        let dirty_fraction = dirty.len() as f32 / (columns * rows) as f32;
+        if dirty_fraction > max_dirty_fraction {
+            return Self {
+                dirty_fraction,
+                ..Self::new(data, width, height, scale)
+            };
+        }
+
+        Self {
+            runs: encode_zero_runs(&tile_data),
+            tiles: Some(DeltaTiles {
+                size: tile_size,
+                dirty,
+            }),
+            dirty_fraction,
+            width,
+            height,
+            scale,
+        }
+    }
+
+    /// The decoded delta, one value per channel
+    pub fn data(&self) -> Vec<i16> {
+        let stored = self.stored_values();
+        let Some(tiles) = &self.tiles else {
+            return stored;
+        };
+
+        let mut data = vec![0; self.width as usize * self.height as usize * 4];
+        let mut offset = 0;
+        for &tile in &tiles.dirty {
+            for range in tile_rows(self.width, self.height, tiles.size, tile) {
+                let len = range.len();
+                data[range].copy_from_slice(&stored[offset..offset + len]);
+                offset += len;
+            }
+        }
+        data
+    }
+
+    /// Share of the frame's tiles with a change
+    pub fn dirty_fraction(&self) -> f32 {
+        self.dirty_fraction
+    }
+
+    /// The values held, with zero runs expanded but tiles left packed
+    fn stored_values(&self) -> Vec<i16> {
+        let mut data = Vec::new();
+        let mut runs = self.runs.iter();
+        while let Some(&value) = runs.next() {
+            if value == 0 {
+                let length = runs.next().map_or(0, |&length| length as u16);
+                data.resize(data.len() + length as usize, 0);
+            } else {
+                data.push(value);
+            }
+        }
+        data
+    }
+
+    /// Bytes held by the encoded delta data and its tile list
+    pub fn byte_size(&self) -> usize {
+        let tiles_size = self.tiles.as_ref().map_or(0, |tiles| {
+            tiles.dirty.len() * std::mem::size_of::<(u32, u32)>()
+        });
+        self.runs.len() * std::mem::size_of::<i16>() + tiles_size
+    }
+
+    /// Whether applying this delta leaves the frame unchanged
+    pub fn is_zero(&self) -> bool {
+        // Only zero runs, which come in pairs of marker and length
+        self.runs.iter().step_by(2).all(|&value| value == 0)
+    }
+}
+
+/// Position and size of a tile, clipped to the frame
+fn tile_rect(width: u32, height: u32, tile_size: u32, (column, row): (u32, u32)) -> [u32; 4] {
+    let x = column * tile_size;
+    let y = row * tile_size;
+    [x, y, tile_size.min(width - x), tile_size.min(height - y)]
+}
+
+/// Ranges of a whole frame's delta data covered by each row of a tile
+fn tile_rows(
+    width: u32,
+    height: u32,
+    tile_size: u32,
+    tile: (u32, u32),
+) -> impl Iterator<Item = Range<usize>> {
+    let [x, y, tile_width, tile_height] = tile_rect(width, height, tile_size, tile);
+    (y..y + tile_height).map(move |row| {
+        let start = (row as usize * width as usize + x as usize) * 4;
+        start..start + tile_width as usize * 4
+    })
+}
+
+/// Collapse runs of zeros in `data` into a `0` marker plus the run length
+fn encode_zero_runs(data: &[i16]) -> Vec<i16> {
+    let mut runs = Vec::new();
+    let mut zeros = 0u16;
+    for &value in data {
+        if value == 0 {
+            if zeros == u16::MAX {
+                runs.extend([0, zeros as i16]);
+                zeros = 0;
+            }
+            zeros += 1;
+        } else {
+            if zeros > 0 {
+                runs.extend([0, zeros as i16]);
+                zeros = 0;
+            }
+            runs.push(value);
+        }
+    }
+    if zeros > 0 {
+        runs.extend([0, zeros as i16]);
+    }
+    runs
 }
 
 pub struct CompressedSequence {
-    pub base_frame: RgbaImage,
-    pub deltas: Vec<DeltaFrame>,
+    /// Frames stored in full, keyed by index. Always holds frame 0.
+    pub keyframes: BTreeMap<usize, RgbaImage>,
+    /// Delta producing every other frame from the one before it, keyed by index
+    pub deltas: BTreeMap<usize, DeltaFrame>,
     pub frame_count: usize,
 }
 
+/// Machine-readable summary of a compressed sequence
+#[derive(Debug, Serialize)]
+pub struct CompressionStats {
+    pub anibuddy_version: &'static str,
+    pub frame_count: usize,
+    pub width: u32,
+    pub height: u32,
+    pub original_size: usize,
+    pub compressed_size: usize,
+    pub compression_ratio: f32,
+    /// Frames stored in full rather than as deltas
+    pub keyframes: Vec<usize>,
+    /// Size in bytes of the delta producing each frame that isn't a keyframe
+    pub delta_sizes: Vec<usize>,
+    /// Frames identical to the one before them
+    pub zero_delta_frames: usize,
+    /// Percentage of tiles with a change, averaged over the deltas
+    pub average_dirty_tile_percent: f32,
+    /// How closely playback reproduces the original frames, when measured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<QualityMetrics>,
+}
+
+/// How closely a compressed sequence reproduces the frames it was built from
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct QualityMetrics {
+    /// PSNR in dB of the mean squared error over every channel of every
+    /// frame, infinite (`null` in JSON) when the sequence is lossless
+    pub average_psnr: f64,
+    /// Frame with the largest mean squared error
+    pub worst_frame: usize,
+    pub worst_frame_psnr: f64,
+    /// Largest difference of any channel of any pixel, out of 255
+    pub max_channel_error: u8,
+}
+
+/// Sum of squared channel differences between two frames of the same size,
+/// along with the largest single difference
+fn frame_error(a: &RgbaImage, b: &RgbaImage) -> (f64, u8) {
+    a.as_raw()
+        .iter()
+        .zip(b.as_raw())
+        .fold((0.0, 0), |(squared_error, max_error), (&x, &y)| {
+            let difference = x.abs_diff(y);
+            (
+                squared_error + f64::from(difference).powi(2),
+                max_error.max(difference),
+            )
+        })
+}
+
+/// Peak signal-to-noise ratio of 8-bit channels for a mean squared error
+fn psnr(mean_squared_error: f64) -> f64 {
+    if mean_squared_error == 0.0 {
+        f64::INFINITY
+    } else {
+        10.0 * (255.0 * 255.0 / mean_squared_error).log10()
+    }
+}
+
 pub struct DeltaCompressor {
     device: Arc<wgpu::Device>,
     queue: Arc<wgpu::Queue>,
@@ -103,17 +358,29 @@ pub struct DeltaCompressor {
     // Buffer for reading back delta data
     staging_buffer: Option<wgpu::Buffer>,
 
+    // Delta scale uniform bound by both pipelines
+    params_buffer: wgpu::Buffer,
+
+    // One texel per tile, nonzero where the reconstruct pass applies the delta
+    tile_mask: Option<wgpu::Texture>,
+    tile_mask_size: u32,
+
     current_dimensions: (u32, u32),
 }
 
 impl DeltaCompressor {
+    /// Check that `adapter` can run the compute pipelines before creating them,
+    /// since some downlevel backends (notably GL) lack compute or storage textures
+    pub fn check_support(adapter: &wgpu::Adapter) -> Result<()> {
+        check_capabilities(
+            &adapter.get_downlevel_capabilities(),
+            &adapter.limits(),
+            |format| adapter.get_texture_format_features(format),
+        )
+    }
+
     pub fn new(device: Arc<wgpu::Device>, queue: Arc<wgpu::Queue>) -> Result<Self> {
         // Create delta calculation pipeline
-        let delta_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Delta Calculate Shader"),
-            source: wgpu::ShaderSource::Wgsl(DELTA_CALCULATE_SHADER.into()),
-        });
-
         let delta_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("Delta Bind Group Layout"),
@@ -148,31 +415,22 @@ impl DeltaCompressor {
                         },
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
                 ],
             });
 
-        let delta_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Delta Pipeline Layout"),
-                bind_group_layouts: &[&delta_bind_group_layout],
-                push_constant_ranges: &[],
-            });
-
-        let delta_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Delta Calculate Pipeline"),
-            layout: Some(&delta_pipeline_layout),
-            module: &delta_shader,
-            entry_point: Some("cs_main"),
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
-            cache: None,
-        });
+        let delta_pipeline = Self::create_delta_pipeline(&device, &delta_bind_group_layout);
 
         // Create frame reconstruction pipeline
-        let reconstruct_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Frame Reconstruct Shader"),
-            source: wgpu::ShaderSource::Wgsl(FRAME_RECONSTRUCT_SHADER.into()),
-        });
-
         let reconstruct_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("Reconstruct Bind Group Layout"),
@@ -207,25 +465,41 @@ impl DeltaCompressor {
                         },
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Uint,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
                 ],
             });
 
-        let reconstruct_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Reconstruct Pipeline Layout"),
-                bind_group_layouts: &[&reconstruct_bind_group_layout],
-                push_constant_ranges: &[],
-            });
-
         let reconstruct_pipeline =
-            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-                label: Some("Frame Reconstruct Pipeline"),
-                layout: Some(&reconstruct_pipeline_layout),
-                module: &reconstruct_shader,
-                entry_point: Some("cs_main"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                cache: None,
-            });
+            Self::create_reconstruct_pipeline(&device, &reconstruct_bind_group_layout);
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Delta Params Buffer"),
+            contents: bytemuck::cast_slice(&[DeltaParams {
+                scale: DEFAULT_DELTA_SCALE,
+                tile_size: 1,
+                _padding: [0; 2],
+            }]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
 
         Ok(Self {
             device,
@@ -239,10 +513,168 @@ impl DeltaCompressor {
             working_texture_delta: None,
             working_texture_output: None,
             staging_buffer: None,
+            params_buffer,
+            tile_mask: None,
+            tile_mask_size: 0,
             current_dimensions: (0, 0),
         })
     }
 
+    /// Set the delta scale and tile size used by the next dispatch of either pipeline
+    fn write_params(&self, scale: f32, tile_size: u32) {
+        self.queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::cast_slice(&[DeltaParams {
+                scale,
+                tile_size,
+                _padding: [0; 2],
+            }]),
+        );
+    }
+
+    /// Mark the tiles the next reconstruct pass applies the delta texture to,
+    /// all of them without `tiles`. Returns the tile size the mask is for.
+    fn upload_tile_mask(&mut self, tiles: Option<&DeltaTiles>) -> u32 {
+        let (width, height) = self.current_dimensions;
+        let tile_size = match tiles {
+            Some(tiles) => tiles.size,
+            // Any mask will do when every tile is applied, so keep the current one
+            None if self.tile_mask.is_some() => self.tile_mask_size,
+            None => width.max(height),
+        };
+        let columns = width.div_ceil(tile_size);
+        let rows = height.div_ceil(tile_size);
+
+        if self.tile_mask.is_none() || self.tile_mask_size != tile_size {
+            self.tile_mask = Some(self.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Tile Mask"),
+                size: wgpu::Extent3d {
+                    width: columns,
+                    height: rows,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R8Uint,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            }));
+            self.tile_mask_size = tile_size;
+        }
+
+        let mask = match tiles {
+            Some(tiles) => {
+                let mut mask = vec![0u8; (columns * rows) as usize];
+                for &(column, row) in &tiles.dirty {
+                    mask[(row * columns + column) as usize] = 1;
+                }
+                mask
+            }
+            None => vec![1; (columns * rows) as usize],
+        };
+
+        self.queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: self.tile_mask.as_ref().unwrap(),
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &mask,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(columns),
+                rows_per_image: Some(rows),
+            },
+            wgpu::Extent3d {
+                width: columns,
+                height: rows,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        tile_size
+    }
+
+    fn create_delta_pipeline(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> wgpu::ComputePipeline {
+        let delta_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Delta Calculate Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_source(
+                "delta_calculate.wgsl",
+                DELTA_CALCULATE_SHADER,
+            )),
+        });
+
+        let delta_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Delta Pipeline Layout"),
+                bind_group_layouts: &[bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Delta Calculate Pipeline"),
+            layout: Some(&delta_pipeline_layout),
+            module: &delta_shader,
+            entry_point: Some("cs_main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: None,
+        })
+    }
+
+    fn create_reconstruct_pipeline(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> wgpu::ComputePipeline {
+        let reconstruct_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Frame Reconstruct Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_source(
+                "frame_reconstruct.wgsl",
+                FRAME_RECONSTRUCT_SHADER,
+            )),
+        });
+
+        let reconstruct_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Reconstruct Pipeline Layout"),
+                bind_group_layouts: &[bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Frame Reconstruct Pipeline"),
+            layout: Some(&reconstruct_pipeline_layout),
+            module: &reconstruct_shader,
+            entry_point: Some("cs_main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: None,
+        })
+    }
+
+    /// Rebuild both compute pipelines from the current shader sources.
+    /// The previous pipelines are kept if either shader fails to compile.
+    #[cfg(feature = "shader-hot-reload")]
+    pub fn reload_shaders(&mut self) -> Result<()> {
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let delta_pipeline =
+            Self::create_delta_pipeline(&self.device, &self.delta_bind_group_layout);
+        let reconstruct_pipeline =
+            Self::create_reconstruct_pipeline(&self.device, &self.reconstruct_bind_group_layout);
+
+        if let Some(e) = pollster::block_on(self.device.pop_error_scope()) {
+            return Err(anyhow::anyhow!("{}", e));
+        }
+
+        self.delta_pipeline = delta_pipeline;
+        self.reconstruct_pipeline = reconstruct_pipeline;
+        Ok(())
+    }
+
     pub fn cleanup(&mut self) {
         log::debug!("Cleaning up DeltaCompressor resources");
 
@@ -251,6 +683,7 @@ impl DeltaCompressor {
         self.working_texture_previous = None;
         self.working_texture_delta = None;
         self.working_texture_output = None;
+        self.tile_mask = None;
 
         // Clear staging buffer
         self.staging_buffer = None;
@@ -340,7 +773,7 @@ impl DeltaCompressor {
             // Calculate aligned buffer size for staging buffer (16-bit data now)
             let unpadded_bytes_per_row = width * 8; // 8 bytes per pixel for Rgba16Sint (4 channels * 2 bytes each)
             let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
-            let padded_bytes_per_row = ((unpadded_bytes_per_row + align - 1) / align) * align;
+            let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
             let buffer_size = (padded_bytes_per_row * height) as u64;
 
             self.staging_buffer = Some(self.device.create_buffer(&wgpu::BufferDescriptor {
@@ -350,6 +783,9 @@ impl DeltaCompressor {
                 mapped_at_creation: false,
             }));
 
+            // The tile grid depends on the frame size
+            self.tile_mask = None;
+
             self.current_dimensions = (width, height);
         }
     }
@@ -357,16 +793,20 @@ impl DeltaCompressor {
     fn calculate_aligned_bytes_per_row(width: u32) -> u32 {
         let unpadded_bytes_per_row = width * 8; // 8 bytes per pixel for Rgba16Sint
         let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
-        ((unpadded_bytes_per_row + align - 1) / align) * align
+        unpadded_bytes_per_row.div_ceil(align) * align
     }
 
-    pub async fn compress_sequence(&mut self, images: &[RgbaImage]) -> Result<CompressedSequence> {
-        if images.is_empty() {
-            return Err(anyhow::anyhow!("No images to compress"));
-        }
-
+    /// Compress a sequence of straight-alpha frames into keyframes plus deltas.
+    /// Frame 0 is always a keyframe; with a `keyframe_interval` every Nth frame
+    /// after it is stored in full too, so seeking replays at most N - 1 deltas.
+    pub async fn compress_sequence(
+        &mut self,
+        images: &[RgbaImage],
+        options: CompressionOptions,
+    ) -> Result<CompressedSequence> {
+        let (width, height) = check_frame_sizes(images)?;
+        check_options(&options)?;
         let first_image = &images[0];
-        let (width, height) = first_image.dimensions();
 
         log::info!(
             "Compressing sequence of {} frames ({}x{})",
@@ -376,13 +816,27 @@ impl DeltaCompressor {
         );
 
         self.ensure_working_textures(width, height);
+        // Deltas are calculated for the whole frame, so reconstruct all of it
+        let tile_size = self.upload_tile_mask(None);
+        self.write_params(options.delta_scale, tile_size);
 
-        let mut deltas = Vec::with_capacity(images.len() - 1);
+        let mut keyframes = BTreeMap::from([(0, first_image.clone())]);
+        let mut deltas = BTreeMap::new();
 
         // Upload first image as previous frame
         self.upload_image_to_texture(first_image, self.working_texture_previous.as_ref().unwrap())?;
 
         for (i, current_image) in images.iter().enumerate().skip(1) {
+            if is_keyframe(i, options.keyframe_interval) {
+                log::debug!("Storing frame {} as a keyframe", i);
+                keyframes.insert(i, current_image.clone());
+                self.upload_image_to_texture(
+                    current_image,
+                    self.working_texture_previous.as_ref().unwrap(),
+                )?;
+                continue;
+            }
+
             log::debug!("Calculating delta for frame {}", i);
 
             // Upload current image
@@ -392,29 +846,35 @@ impl DeltaCompressor {
             )?;
 
             // Calculate delta
-            let delta = self.calculate_delta().await?;
-            deltas.push(delta);
-
-            // Copy current to previous for next iteration
+            let delta = self.calculate_delta(&options).await?;
+            deltas.insert(i, delta);
+
+            // Diff the next frame against what playback will reconstruct rather
+            // than against the original, so any rounding in a delta is corrected
+            // by the next one instead of compounding down the chain
+            self.reconstruct_frame_compute(self.working_texture_previous.as_ref().unwrap())
+                .await?;
             self.copy_texture_to_texture(
-                self.working_texture_current.as_ref().unwrap(),
+                self.working_texture_output.as_ref().unwrap(),
                 self.working_texture_previous.as_ref().unwrap(),
             )?;
         }
 
         log::info!(
-            "Successfully compressed {} frames into {} deltas",
+            "Successfully compressed {} frames into {} keyframes and {} deltas",
             images.len(),
+            keyframes.len(),
             deltas.len()
         );
 
         Ok(CompressedSequence {
-            base_frame: first_image.clone(),
+            keyframes,
             deltas,
             frame_count: images.len(),
         })
     }
 
+    /// Apply `delta` to `base_frame`, returning the next straight-alpha frame
     pub async fn reconstruct_frame(
         &mut self,
         base_frame: &RgbaImage,
@@ -428,9 +888,12 @@ impl DeltaCompressor {
 
         // Upload delta data
         self.upload_delta_to_texture(delta)?;
+        let tile_size = self.upload_tile_mask(delta.tiles.as_ref());
+        self.write_params(delta.scale, tile_size);
 
         // Reconstruct frame
-        self.reconstruct_frame_compute().await?;
+        self.reconstruct_frame_compute(self.working_texture_current.as_ref().unwrap())
+            .await?;
 
         // Read back result
         self.read_reconstructed_frame(width, height).await
@@ -463,27 +926,43 @@ impl DeltaCompressor {
     }
 
     fn upload_delta_to_texture(&self, delta: &DeltaFrame) -> Result<()> {
-        let unpadded_bytes_per_row = 8 * delta.width;
-
-        self.queue.write_texture(
-            wgpu::TexelCopyTextureInfo {
-                texture: self.working_texture_delta.as_ref().unwrap(),
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            bytemuck::cast_slice(&delta.data),
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(unpadded_bytes_per_row),
-                rows_per_image: Some(delta.height),
-            },
-            wgpu::Extent3d {
-                width: delta.width,
-                height: delta.height,
-                depth_or_array_layers: 1,
-            },
-        );
+        let stored = delta.stored_values();
+
+        // A whole-frame delta is a single region; a sparse one uploads only its
+        // dirty tiles, each stored row by row
+        let regions = match &delta.tiles {
+            Some(tiles) => tiles
+                .dirty
+                .iter()
+                .map(|&tile| tile_rect(delta.width, delta.height, tiles.size, tile))
+                .collect(),
+            None => vec![[0, 0, delta.width, delta.height]],
+        };
+
+        let mut offset = 0;
+        for [x, y, width, height] in regions {
+            let len = width as usize * height as usize * 4;
+            self.queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: self.working_texture_delta.as_ref().unwrap(),
+                    mip_level: 0,
+                    origin: wgpu::Origin3d { x, y, z: 0 },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                bytemuck::cast_slice(&stored[offset..offset + len]),
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(8 * width),
+                    rows_per_image: Some(height),
+                },
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+            offset += len;
+        }
 
         Ok(())
     }
@@ -519,7 +998,7 @@ impl DeltaCompressor {
         Ok(())
     }
 
-    async fn calculate_delta(&self) -> Result<DeltaFrame> {
+    async fn calculate_delta(&self, options: &CompressionOptions) -> Result<DeltaFrame> {
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -561,6 +1040,10 @@ impl DeltaCompressor {
                             .create_view(&wgpu::TextureViewDescriptor::default()),
                     ),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: self.params_buffer.as_entire_binding(),
+                },
             ],
         });
 
@@ -575,8 +1058,8 @@ impl DeltaCompressor {
             compute_pass.set_bind_group(0, &bind_group, &[]);
 
             let (width, height) = self.current_dimensions;
-            let workgroup_count_x = (width + 7) / 8;
-            let workgroup_count_y = (height + 7) / 8;
+            let workgroup_count_x = width.div_ceil(8);
+            let workgroup_count_y = height.div_ceil(8);
 
             compute_pass.dispatch_workgroups(workgroup_count_x, workgroup_count_y, 1);
         }
@@ -635,14 +1118,18 @@ impl DeltaCompressor {
         drop(data);
         self.staging_buffer.as_ref().unwrap().unmap();
 
-        Ok(DeltaFrame {
-            data: delta_data,
+        Ok(DeltaFrame::tiled(
+            &delta_data,
             width,
             height,
-        })
+            options.delta_scale,
+            options.tile_size,
+            options.max_dirty_fraction,
+        ))
     }
 
-    async fn reconstruct_frame_compute(&self) -> Result<()> {
+    /// Apply the delta texture to `base`, writing the result to the output texture
+    async fn reconstruct_frame_compute(&self, base: &wgpu::Texture) -> Result<()> {
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -657,11 +1144,7 @@ impl DeltaCompressor {
                 wgpu::BindGroupEntry {
                     binding: 0,
                     resource: wgpu::BindingResource::TextureView(
-                        &self
-                            .working_texture_current
-                            .as_ref()
-                            .unwrap()
-                            .create_view(&wgpu::TextureViewDescriptor::default()),
+                        &base.create_view(&wgpu::TextureViewDescriptor::default()),
                     ),
                 },
                 wgpu::BindGroupEntry {
@@ -684,6 +1167,20 @@ impl DeltaCompressor {
                             .create_view(&wgpu::TextureViewDescriptor::default()),
                     ),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: self.params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(
+                        &self
+                            .tile_mask
+                            .as_ref()
+                            .unwrap()
+                            .create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
             ],
         });
 
@@ -698,8 +1195,8 @@ impl DeltaCompressor {
             compute_pass.set_bind_group(0, &bind_group, &[]);
 
             let (width, height) = self.current_dimensions;
-            let workgroup_count_x = (width + 7) / 8;
-            let workgroup_count_y = (height + 7) / 8;
+            let workgroup_count_x = width.div_ceil(8);
+            let workgroup_count_y = height.div_ceil(8);
 
             compute_pass.dispatch_workgroups(workgroup_count_x, workgroup_count_y, 1);
         }
@@ -786,7 +1283,7 @@ impl DeltaCompressor {
     fn calculate_aligned_bytes_per_row_rgba8(width: u32) -> u32 {
         let unpadded_bytes_per_row = width * 4;
         let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
-        ((unpadded_bytes_per_row + align - 1) / align) * align
+        unpadded_bytes_per_row.div_ceil(align) * align
     }
 }
 
@@ -797,15 +1294,788 @@ impl Drop for DeltaCompressor {
     }
 }
 
+/// Check that `images` can be delta compressed, returning their shared size
+fn check_frame_sizes(images: &[RgbaImage]) -> Result<(u32, u32)> {
+    let Some(first_image) = images.first() else {
+        return Err(anyhow::anyhow!("No images to compress"));
+    };
+    let (width, height) = first_image.dimensions();
+
+    // Deltas are computed texel by texel, so every frame has to match the first
+    if let Some((index, image)) = images
+        .iter()
+        .enumerate()
+        .find(|(_, image)| image.dimensions() != (width, height))
+    {
+        return Err(anyhow::anyhow!(
+            "Frame {} is {}x{} but frame 0 is {}x{}; delta compression needs equally sized frames",
+            index,
+            image.width(),
+            image.height(),
+            width,
+            height
+        ));
+    }
+
+    Ok((width, height))
+}
+
+/// Whether frame `index` is stored in full rather than as a delta
+fn is_keyframe(index: usize, keyframe_interval: Option<usize>) -> bool {
+    index == 0 || keyframe_interval.is_some_and(|interval| index.is_multiple_of(interval))
+}
+
+/// Reject delta scales the `i16` deltas can't hold and empty tiles
+fn check_options(options: &CompressionOptions) -> Result<()> {
+    if !(1.0..=DEFAULT_DELTA_SCALE).contains(&options.delta_scale) {
+        return Err(anyhow!(
+            "Delta scale must be between 1 and {}, got {}",
+            DEFAULT_DELTA_SCALE,
+            options.delta_scale
+        ));
+    }
+    if options.tile_size == 0 {
+        return Err(anyhow!("Delta tile size must be at least 1"));
+    }
+    Ok(())
+}
+
+/// CPU version of [`DeltaCompressor::compress_sequence`], producing the same
+/// keyframes and deltas
+pub fn compress_sequence_cpu(
+    images: &[RgbaImage],
+    options: CompressionOptions,
+) -> Result<CompressedSequence> {
+    let (width, height) = check_frame_sizes(images)?;
+    check_options(&options)?;
+    let scale = options.delta_scale;
+    log::info!(
+        "Compressing sequence of {} frames ({}x{}) on the CPU",
+        images.len(),
+        width,
+        height
+    );
+
+    let mut keyframes = BTreeMap::from([(0, images[0].clone())]);
+    let mut deltas = BTreeMap::new();
+    let mut previous = images[0].clone();
+
+    for (i, current) in images.iter().enumerate().skip(1) {
+        if is_keyframe(i, options.keyframe_interval) {
+            keyframes.insert(i, current.clone());
+            previous = current.clone();
+            continue;
+        }
+
+        // Matches delta_calculate.wgsl: difference of normalized channels,
+        // scaled and truncated toward zero
+        let data = current
+            .as_raw()
+            .iter()
+            .zip(previous.as_raw())
+            .map(|(&current, &previous)| {
+                let delta = current as f32 / 255.0 - previous as f32 / 255.0;
+                (delta * scale).clamp(-scale, scale) as i16
+            })
+            .collect::<Vec<_>>();
+        let delta = DeltaFrame::tiled(
+            &data,
+            width,
+            height,
+            scale,
+            options.tile_size,
+            options.max_dirty_fraction,
+        );
+
+        // Closed loop, as on the GPU
+        previous = reconstruct_frame_cpu(&previous, &delta);
+        deltas.insert(i, delta);
+    }
+
+    Ok(CompressedSequence {
+        keyframes,
+        deltas,
+        frame_count: images.len(),
+    })
+}
+
+/// CPU version of [`DeltaCompressor::reconstruct_frame`], producing the same frame
+pub fn reconstruct_frame_cpu(base_frame: &RgbaImage, delta: &DeltaFrame) -> RgbaImage {
+    // Matches frame_reconstruct.wgsl and the unorm store rounding to nearest
+    let scale = delta.scale;
+    let data = base_frame
+        .as_raw()
+        .iter()
+        .zip(delta.data())
+        .map(|(&base, delta)| {
+            let value = (base as f32 / 255.0 + delta as f32 / scale).clamp(0.0, 1.0);
+            (value * 255.0).round() as u8
+        })
+        .collect();
+
+    RgbaImage::from_raw(delta.width, delta.height, data)
+        .expect("delta and base frame have the same size")
+}
+
+/// Apply `delta` to `base_frame` with `compressor`, or on the CPU without one
+pub async fn apply_delta(
+    compressor: Option<&mut DeltaCompressor>,
+    base_frame: &RgbaImage,
+    delta: &DeltaFrame,
+) -> Result<RgbaImage> {
+    match compressor {
+        Some(compressor) => compressor.reconstruct_frame(base_frame, delta).await,
+        None => Ok(reconstruct_frame_cpu(base_frame, delta)),
+    }
+}
+
+fn check_capabilities(
+    downlevel: &wgpu::DownlevelCapabilities,
+    limits: &wgpu::Limits,
+    format_features: impl Fn(wgpu::TextureFormat) -> wgpu::TextureFormatFeatures,
+) -> Result<()> {
+    if !downlevel
+        .flags
+        .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+    {
+        return Err(anyhow!("the adapter doesn't support compute shaders"));
+    }
+
+    if limits.max_storage_textures_per_shader_stage == 0 {
+        return Err(anyhow!("the adapter doesn't support storage textures"));
+    }
+
+    for format in STORAGE_FORMATS {
+        if !format_features(format)
+            .allowed_usages
+            .contains(wgpu::TextureUsages::STORAGE_BINDING)
+        {
+            return Err(anyhow!(
+                "the adapter can't use {:?} as a storage texture",
+                format
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 impl CompressedSequence {
+    /// The first frame of the sequence
+    pub fn base_frame(&self) -> &RgbaImage {
+        &self.keyframes[&0]
+    }
+
+    /// Bytes held by the keyframes and the encoded deltas
     pub fn memory_usage(&self) -> usize {
-        let base_size = self.base_frame.as_raw().len();
-        let deltas_size: usize = self.deltas.iter().map(|d| d.data.len() * 2).sum();
-        base_size + deltas_size
+        let keyframes_size: usize = self
+            .keyframes
+            .values()
+            .map(|frame| frame.as_raw().len())
+            .sum();
+        let deltas_size: usize = self.deltas.values().map(DeltaFrame::byte_size).sum();
+        keyframes_size + deltas_size
+    }
+
+    /// Size of the sequence as plain RGBA frames
+    pub fn original_size(&self) -> usize {
+        self.base_frame().as_raw().len() * self.frame_count
+    }
+
+    pub fn stats(&self) -> CompressionStats {
+        let original_size = self.original_size();
+
+        CompressionStats {
+            anibuddy_version: env!("CARGO_PKG_VERSION"),
+            frame_count: self.frame_count,
+            width: self.base_frame().width(),
+            height: self.base_frame().height(),
+            original_size,
+            compressed_size: self.memory_usage(),
+            compression_ratio: self.compression_ratio(original_size),
+            keyframes: self.keyframes.keys().copied().collect(),
+            delta_sizes: self.deltas.values().map(DeltaFrame::byte_size).collect(),
+            zero_delta_frames: self.deltas.values().filter(|delta| delta.is_zero()).count(),
+            average_dirty_tile_percent: if self.deltas.is_empty() {
+                0.0
+            } else {
+                let total: f32 = self.deltas.values().map(DeltaFrame::dirty_fraction).sum();
+                total / self.deltas.len() as f32 * 100.0
+            },
+            quality: None,
+        }
     }
 
     pub fn compression_ratio(&self, original_size: usize) -> f32 {
         let compressed_size = self.memory_usage();
         original_size as f32 / compressed_size as f32
     }
+
+    /// Nearest full frame at or before `index`, along with its position
+    fn nearest_keyframe(&self, index: usize) -> (usize, &RgbaImage) {
+        self.keyframes
+            .range(..=index)
+            .next_back()
+            .map(|(&keyframe_index, keyframe)| (keyframe_index, keyframe))
+            .expect("frame 0 is always a keyframe")
+    }
+
+    /// Reconstruct the frame at `index` without having played the frames
+    /// before it, by applying deltas forward from the nearest preceding keyframe.
+    /// Without a compressor the deltas are applied on the CPU.
+    pub async fn reconstruct_at(
+        &self,
+        mut compressor: Option<&mut DeltaCompressor>,
+        index: usize,
+    ) -> Result<RgbaImage> {
+        if index >= self.frame_count {
+            return Err(anyhow::anyhow!(
+                "Frame {} out of range for sequence of {} frames",
+                index,
+                self.frame_count
+            ));
+        }
+
+        let (keyframe_index, keyframe) = self.nearest_keyframe(index);
+        let mut frame = keyframe.clone();
+        let after_keyframe = (Bound::Excluded(keyframe_index), Bound::Included(index));
+        for (_, delta) in self.deltas.range(after_keyframe) {
+            frame = apply_delta(compressor.as_deref_mut(), &frame, delta).await?;
+        }
+
+        Ok(frame)
+    }
+
+    /// Play the sequence through once and compare every frame to `originals`,
+    /// the frames it was compressed from. Without a compressor the deltas are
+    /// applied on the CPU.
+    pub async fn measure_quality(
+        &self,
+        mut compressor: Option<&mut DeltaCompressor>,
+        originals: &[RgbaImage],
+    ) -> Result<QualityMetrics> {
+        if originals.len() != self.frame_count {
+            return Err(anyhow!(
+                "Expected {} original frames, got {}",
+                self.frame_count,
+                originals.len()
+            ));
+        }
+
+        let channels = self.base_frame().as_raw().len() as f64;
+        let mut total_squared_error = 0.0;
+        let mut worst = (0, 0.0);
+        let mut max_channel_error = 0;
+
+        let mut frame = self.base_frame().clone();
+        for (index, original) in originals.iter().enumerate() {
+            if let Some(keyframe) = self.keyframes.get(&index) {
+                frame = keyframe.clone();
+            } else {
+                frame =
+                    apply_delta(compressor.as_deref_mut(), &frame, &self.deltas[&index]).await?;
+            }
+
+            let (squared_error, max_error) = frame_error(&frame, original);
+            total_squared_error += squared_error;
+            max_channel_error = max_channel_error.max(max_error);
+            if squared_error > worst.1 {
+                worst = (index, squared_error);
+            }
+        }
+
+        Ok(QualityMetrics {
+            average_psnr: psnr(total_squared_error / (channels * self.frame_count as f64)),
+            worst_frame: worst.0,
+            worst_frame_psnr: psnr(worst.1 / channels),
+            max_channel_error,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// Request a device from any available adapter, or `None` on machines
+    /// without one so GPU tests can be skipped instead of failing
+    fn gpu_device() -> Option<(Arc<wgpu::Device>, Arc<wgpu::Queue>)> {
+        pollster::block_on(async {
+            let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+            // Fall back to a software adapter so GPU-less CI runners still cover this
+            let adapter = match instance
+                .request_adapter(&wgpu::RequestAdapterOptions::default())
+                .await
+            {
+                Ok(adapter) => adapter,
+                Err(_) => instance
+                    .request_adapter(&wgpu::RequestAdapterOptions {
+                        force_fallback_adapter: true,
+                        ..Default::default()
+                    })
+                    .await
+                    .ok()?,
+            };
+            let (device, queue) = adapter
+                .request_device(&wgpu::DeviceDescriptor::default())
+                .await
+                .ok()?;
+            Some((Arc::new(device), Arc::new(queue)))
+        })
+    }
+
+    /// Frames of a soft-edged, semi-transparent disc sliding to the right
+    fn soft_edge_frames(count: u32) -> Vec<RgbaImage> {
+        (0..count)
+            .map(|i| {
+                RgbaImage::from_fn(32, 16, |x, y| {
+                    let dx = x as f32 - (8.0 + i as f32 * 2.0);
+                    let dy = y as f32 - 8.0;
+                    let distance = (dx * dx + dy * dy).sqrt();
+                    let coverage = (6.0 - distance).clamp(0.0, 1.0);
+                    let alpha = (coverage * 180.0).round() as u8;
+                    Rgba([255, (x * 8) as u8, 64, alpha])
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_check_capabilities() {
+        let storage = |_| wgpu::TextureFormatFeatures {
+            allowed_usages: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::STORAGE_BINDING,
+            flags: wgpu::TextureFormatFeatureFlags::empty(),
+        };
+        let downlevel = wgpu::DownlevelCapabilities::default();
+        let limits = wgpu::Limits::default();
+        assert!(check_capabilities(&downlevel, &limits, storage).is_ok());
+
+        // Rgba16Sint can be sampled but not written as storage, as on some GL drivers
+        let no_sint_storage = |format| wgpu::TextureFormatFeatures {
+            allowed_usages: if format == wgpu::TextureFormat::Rgba16Sint {
+                wgpu::TextureUsages::TEXTURE_BINDING
+            } else {
+                wgpu::TextureUsages::STORAGE_BINDING
+            },
+            flags: wgpu::TextureFormatFeatureFlags::empty(),
+        };
+        let error = check_capabilities(&downlevel, &limits, no_sint_storage).unwrap_err();
+        assert!(error.to_string().contains("Rgba16Sint"));
+
+        let webgl2 = wgpu::Limits::downlevel_webgl2_defaults();
+        assert!(check_capabilities(&downlevel, &webgl2, storage).is_err());
+
+        let no_compute = wgpu::DownlevelCapabilities {
+            flags: wgpu::DownlevelFlags::empty(),
+            ..Default::default()
+        };
+        assert!(check_capabilities(&no_compute, &limits, storage).is_err());
+    }
+
+    #[test]
+    fn test_zero_run_encoding() {
+        let long_run = 2 * u16::MAX as usize + 3;
+        let mut data = vec![5, 0, 0, -7, 0, 1];
+        data.extend(std::iter::repeat_n(0, long_run));
+        data.push(-1);
+        let delta = DeltaFrame::new(&data, 1, data.len() as u32 / 4, DEFAULT_DELTA_SCALE);
+
+        assert_eq!(delta.data(), data);
+        // 4 literals, 2 short runs and a long run split in 3
+        assert_eq!(delta.byte_size(), (4 + 2 * 2 + 3 * 2) * 2);
+        assert!(!delta.is_zero());
+
+        let zeros = vec![0; long_run + 3];
+        let zero = DeltaFrame::new(&zeros, 1, zeros.len() as u32 / 4, 1.0);
+        assert!(zero.is_zero());
+        assert_eq!(zero.data(), zeros);
+    }
+
+    #[test]
+    fn test_sparse_deltas() {
+        let (width, height) = (40, 24);
+        let mut data = vec![0i16; width as usize * height as usize * 4];
+        // A change at (20, 5), inside tile (1, 0) of a 3x2 grid
+        data[(5 * width as usize + 20) * 4] = 100;
+
+        let sparse = DeltaFrame::tiled(&data, width, height, DEFAULT_DELTA_SCALE, 16, 0.5);
+        assert_eq!(sparse.tiles.as_ref().unwrap().dirty, vec![(1, 0)]);
+        assert_eq!(sparse.dirty_fraction(), 1.0 / 6.0);
+        assert_eq!(sparse.data(), data);
+        // Only the dirty tile is uploaded
+        assert_eq!(sparse.stored_values().len(), 16 * 16 * 4);
+
+        // Past the threshold the whole frame is stored, keeping the fraction
+        let full = DeltaFrame::tiled(&data, width, height, DEFAULT_DELTA_SCALE, 16, 0.1);
+        assert!(full.tiles.is_none());
+        assert_eq!(full.dirty_fraction(), 1.0 / 6.0);
+        assert_eq!(full.data(), data);
+
+        let Some((device, queue)) = gpu_device() else {
+            eprintln!("skipping: no GPU adapter available");
+            return;
+        };
+
+        // Tiles a sparse delta leaves out must not pick up data uploaded for
+        // an earlier delta
+        let base = RgbaImage::from_pixel(width, height, Rgba([10, 20, 30, 40]));
+        let mut compressor = DeltaCompressor::new(device, queue).unwrap();
+        let everywhere = DeltaFrame::new(&vec![2000; data.len()], width, height, 1.0);
+        pollster::block_on(compressor.reconstruct_frame(&base, &everywhere)).unwrap();
+        let frame = pollster::block_on(compressor.reconstruct_frame(&base, &sparse)).unwrap();
+        assert_eq!(frame, reconstruct_frame_cpu(&base, &sparse));
+    }
+
+    #[test]
+    fn test_stats() {
+        let delta = |value: i16| DeltaFrame::new(&[value; 2 * 2 * 4], 2, 2, DEFAULT_DELTA_SCALE);
+        let sequence = CompressedSequence {
+            keyframes: BTreeMap::from([(0, RgbaImage::new(2, 2)), (2, RgbaImage::new(2, 2))]),
+            deltas: BTreeMap::from([(1, delta(0)), (3, delta(3)), (4, delta(0))]),
+            frame_count: 5,
+        };
+
+        let stats = sequence.stats();
+        assert_eq!(stats.original_size, 80);
+        // All-zero deltas collapse to a single run of 4 bytes
+        assert_eq!(stats.compressed_size, 2 * 16 + 32 + 2 * 4);
+        assert_eq!(stats.delta_sizes, vec![4, 32, 4]);
+        assert_eq!(stats.keyframes, vec![0, 2]);
+        assert_eq!(stats.zero_delta_frames, 2);
+        assert_eq!(sequence.nearest_keyframe(1).0, 0);
+        assert_eq!(sequence.nearest_keyframe(4).0, 2);
+
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["anibuddy_version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["frame_count"], 5);
+    }
+
+    /// Synthetic animation: a fixed gradient background with an opaque bar
+    /// sweeping across it, so deltas are sparse but never empty
+    fn test_pattern_frames(count: u32, width: u32, height: u32) -> Vec<RgbaImage> {
+        (0..count)
+            .map(|i| {
+                let bar_x = i * width / count;
+                RgbaImage::from_fn(width, height, |x, y| {
+                    if x >= bar_x && x < bar_x + 4 {
+                        Rgba([255, 255, 255, 255])
+                    } else {
+                        Rgba([(x * 255 / width) as u8, (y * 255 / height) as u8, 128, 200])
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Peak signal-to-noise ratio over all four channels, infinite for identical images
+    fn frame_psnr(a: &RgbaImage, b: &RgbaImage) -> f64 {
+        psnr(frame_error(a, b).0 / a.as_raw().len() as f64)
+    }
+
+    #[test]
+    fn test_compress_reconstruct_roundtrip_psnr() {
+        let Some((device, queue)) = gpu_device() else {
+            eprintln!("skipping: no GPU adapter available");
+            return;
+        };
+
+        let frames = test_pattern_frames(12, 64, 48);
+        let mut compressor = DeltaCompressor::new(device, queue).unwrap();
+
+        let sequence = pollster::block_on(
+            compressor.compress_sequence(&frames, CompressionOptions::default()),
+        )
+        .unwrap();
+        assert_eq!(sequence.frame_count, frames.len());
+        assert_eq!(sequence.deltas.len(), frames.len() - 1);
+
+        let mut frame = sequence.base_frame().clone();
+        for (index, original) in frames.iter().enumerate() {
+            if index > 0 {
+                frame = pollster::block_on(
+                    compressor.reconstruct_frame(&frame, &sequence.deltas[&index]),
+                )
+                .unwrap();
+            }
+            let quality = frame_psnr(&frame, original);
+            assert!(quality > 50.0, "frame {} PSNR {:.1} dB", index, quality);
+        }
+    }
+
+    #[test]
+    fn test_mismatched_frame_is_rejected() {
+        let Some((device, queue)) = gpu_device() else {
+            eprintln!("skipping: no GPU adapter available");
+            return;
+        };
+
+        let mut frames = soft_edge_frames(4);
+        frames[2] = RgbaImage::new(16, 16);
+
+        let mut compressor = DeltaCompressor::new(device, queue).unwrap();
+        let error = pollster::block_on(
+            compressor.compress_sequence(&frames, CompressionOptions::default()),
+        )
+        .err()
+        .expect("mismatched frame should fail");
+        let message = error.to_string();
+        assert!(message.contains("Frame 2 is 16x16"), "{}", message);
+        assert!(message.contains("32x16"), "{}", message);
+    }
+
+    #[test]
+    fn test_semi_transparent_edge_roundtrip() {
+        let Some((device, queue)) = gpu_device() else {
+            eprintln!("skipping: no GPU adapter available");
+            return;
+        };
+
+        let frames = soft_edge_frames(6);
+        let mut compressor = DeltaCompressor::new(device, queue).unwrap();
+        let sequence = pollster::block_on(
+            compressor.compress_sequence(&frames, CompressionOptions::default()),
+        )
+        .unwrap();
+
+        let mut reconstructed = sequence.base_frame().clone();
+        assert_eq!(reconstructed, frames[0]);
+        for (delta, original) in sequence.deltas.values().zip(&frames[1..]) {
+            reconstructed =
+                pollster::block_on(compressor.reconstruct_frame(&reconstructed, delta)).unwrap();
+            assert_eq!(&reconstructed, original);
+        }
+    }
+
+    /// Largest difference between any channel of `a` and `b`
+    fn max_channel_error(a: &RgbaImage, b: &RgbaImage) -> u8 {
+        frame_error(a, b).1
+    }
+
+    #[test]
+    fn test_long_chain_error_stays_bounded() {
+        let Some((device, queue)) = gpu_device() else {
+            eprintln!("skipping: no GPU adapter available");
+            return;
+        };
+
+        // Every channel creeps by an odd step each frame, so any rounding bias
+        // would pile up over the chain if deltas were taken against originals
+        let frames: Vec<RgbaImage> = (0..60u32)
+            .map(|i| {
+                RgbaImage::from_fn(16, 8, |x, y| {
+                    let value = |step: u32| ((x * 13 + y * 7 + i * step) % 256) as u8;
+                    Rgba([value(3), value(5), value(7), value(1)])
+                })
+            })
+            .collect();
+
+        let mut compressor = DeltaCompressor::new(device, queue).unwrap();
+        let sequence = pollster::block_on(
+            compressor.compress_sequence(&frames, CompressionOptions::default()),
+        )
+        .unwrap();
+
+        let mut frame = sequence.base_frame().clone();
+        for (index, original) in frames.iter().enumerate().skip(1) {
+            frame =
+                pollster::block_on(compressor.reconstruct_frame(&frame, &sequence.deltas[&index]))
+                    .unwrap();
+            let error = max_channel_error(&frame, original);
+            assert!(error <= 1, "frame {} off by {}", index, error);
+        }
+        assert_eq!(&frame, frames.last().unwrap());
+    }
+
+    #[test]
+    fn test_cpu_roundtrip() {
+        let frames = soft_edge_frames(6);
+        let sequence = compress_sequence_cpu(
+            &frames,
+            CompressionOptions {
+                keyframe_interval: Some(4),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            sequence.keyframes.keys().copied().collect::<Vec<_>>(),
+            vec![0, 4]
+        );
+
+        for (index, original) in frames.iter().enumerate() {
+            let frame = pollster::block_on(sequence.reconstruct_at(None, index)).unwrap();
+            assert_eq!(&frame, original, "frame {}", index);
+        }
+
+        let mismatched = [RgbaImage::new(4, 4), RgbaImage::new(4, 2)];
+        assert!(compress_sequence_cpu(&mismatched, CompressionOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_cpu_matches_gpu() {
+        let Some((device, queue)) = gpu_device() else {
+            eprintln!("skipping: no GPU adapter available");
+            return;
+        };
+
+        let frames = test_pattern_frames(6, 40, 24);
+        let mut compressor = DeltaCompressor::new(device, queue).unwrap();
+
+        // 127 shares no factor with 255 * 2, so no reconstructed value lands on
+        // a rounding tie that the GPU's unorm conversion may break differently
+        for delta_scale in [DEFAULT_DELTA_SCALE, 127.0] {
+            let options = CompressionOptions {
+                delta_scale,
+                ..Default::default()
+            };
+            let gpu = pollster::block_on(compressor.compress_sequence(&frames, options)).unwrap();
+            let cpu = compress_sequence_cpu(&frames, options).unwrap();
+
+            for (index, delta) in &gpu.deltas {
+                assert_eq!(delta.scale, delta_scale);
+                assert_eq!(delta.data(), cpu.deltas[index].data(), "delta {}", index);
+
+                let previous = &frames[index - 1];
+                let on_gpu =
+                    pollster::block_on(compressor.reconstruct_frame(previous, delta)).unwrap();
+                assert_eq!(
+                    on_gpu,
+                    reconstruct_frame_cpu(previous, delta),
+                    "frame {} at scale {}",
+                    index,
+                    delta_scale
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_delta_scale() {
+        let frames = soft_edge_frames(6);
+        let compress = |delta_scale| {
+            compress_sequence_cpu(
+                &frames,
+                CompressionOptions {
+                    delta_scale,
+                    ..Default::default()
+                },
+            )
+        };
+
+        // The default scale is the one deltas have always used, and is lossless
+        let lossless = compress(DEFAULT_DELTA_SCALE).unwrap();
+        let coarse = compress(16.0).unwrap();
+        for (index, original) in frames.iter().enumerate() {
+            let frame = pollster::block_on(lossless.reconstruct_at(None, index)).unwrap();
+            assert_eq!(&frame, original, "frame {}", index);
+
+            // Each step is within 255 / 16 levels, and closing the loop keeps
+            // that from growing along the chain
+            let frame = pollster::block_on(coarse.reconstruct_at(None, index)).unwrap();
+            assert!(max_channel_error(&frame, original) <= 16, "frame {}", index);
+        }
+
+        assert!(compress(0.0).is_err());
+        assert!(compress(40000.0).is_err());
+    }
+
+    #[test]
+    fn test_measure_quality() {
+        let frames = soft_edge_frames(6);
+        let compress = |delta_scale| {
+            compress_sequence_cpu(
+                &frames,
+                CompressionOptions {
+                    delta_scale,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+
+        let lossless =
+            pollster::block_on(compress(DEFAULT_DELTA_SCALE).measure_quality(None, &frames))
+                .unwrap();
+        assert_eq!(lossless.average_psnr, f64::INFINITY);
+        assert_eq!(lossless.max_channel_error, 0);
+
+        let coarse = compress(16.0);
+        let quality = pollster::block_on(coarse.measure_quality(None, &frames)).unwrap();
+        assert!(quality.average_psnr.is_finite());
+        assert!(quality.worst_frame_psnr <= quality.average_psnr);
+        assert!(quality.max_channel_error > 0 && quality.max_channel_error <= 16);
+        // Frame 0 is stored in full, so it can't be the worst
+        assert_ne!(quality.worst_frame, 0);
+
+        assert!(pollster::block_on(coarse.measure_quality(None, &frames[1..])).is_err());
+    }
+
+    #[test]
+    fn test_delta_params_match_shader_uniforms() {
+        for source in [DELTA_CALCULATE_SHADER, FRAME_RECONSTRUCT_SHADER] {
+            let module = naga::front::wgsl::parse_str(source).unwrap();
+            let mut layouter = naga::proc::Layouter::default();
+            layouter.update(module.to_ctx()).unwrap();
+
+            let (handle, _) = module
+                .types
+                .iter()
+                .find(|(_, ty)| ty.name.as_deref() == Some("DeltaParams"))
+                .expect("delta shaders should declare DeltaParams");
+            assert_eq!(
+                layouter[handle].size as usize,
+                std::mem::size_of::<DeltaParams>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_reconstruct_at_matches_sequential() {
+        let Some((device, queue)) = gpu_device() else {
+            eprintln!("skipping: no GPU adapter available");
+            return;
+        };
+
+        let frames = soft_edge_frames(8);
+        let mut compressor = DeltaCompressor::new(device, queue).unwrap();
+        let sequence = pollster::block_on(
+            compressor.compress_sequence(&frames, CompressionOptions::default()),
+        )
+        .unwrap();
+
+        let mut sequential = vec![sequence.base_frame().clone()];
+        for delta in sequence.deltas.values() {
+            let next =
+                pollster::block_on(compressor.reconstruct_frame(sequential.last().unwrap(), delta))
+                    .unwrap();
+            sequential.push(next);
+        }
+
+        let keyed = pollster::block_on(compressor.compress_sequence(
+            &frames,
+            CompressionOptions {
+                keyframe_interval: Some(3),
+                ..Default::default()
+            },
+        ))
+        .unwrap();
+        assert_eq!(
+            keyed.keyframes.keys().copied().collect::<Vec<_>>(),
+            vec![0, 3, 6]
+        );
+        assert_eq!(keyed.deltas.len(), 5);
+
+        for index in [5, 0, 7, 3, 3, 1, 6] {
+            for sequence in [&sequence, &keyed] {
+                let seeked =
+                    pollster::block_on(sequence.reconstruct_at(Some(&mut compressor), index))
+                        .unwrap();
+                assert_eq!(seeked, sequential[index], "frame {}", index);
+                assert_eq!(seeked, frames[index], "frame {}", index);
+            }
+        }
+        assert!(pollster::block_on(sequence.reconstruct_at(Some(&mut compressor), 8)).is_err());
+    }
 }
@@ -0,0 +1,793 @@
+use anyhow::{Result, anyhow};
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use serde::Serialize;
+use std::fs::File as StdFile;
+use std::io::{BufWriter, Cursor, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::media_loader::{LoadOptions, MediaSequence, MediaSource};
+
+/// Alpha at or above which a pixel stays visible when reduced to GIF's 1-bit transparency
+const GIF_ALPHA_THRESHOLD: u8 = 128;
+
+/// Load a media source and write it out as an animated GIF without opening a window.
+///
+/// GIF can only mark a single palette entry as transparent, so soft alpha can't
+/// survive the export. When `background` is given every frame is composited over
+/// that solid color first, which keeps anti-aliased edges smooth. Otherwise each
+/// pixel is thresholded to fully transparent or fully opaque.
+///
+/// Frames keep their own delays when `use_frame_delays` is set and the source
+/// has them; `frame_interval` times the rest.
+pub fn export_gif(
+    source: MediaSource,
+    load_options: &LoadOptions,
+    output: &Path,
+    frame_interval: Duration,
+    use_frame_delays: bool,
+    background: Option<[u8; 3]>,
+) -> Result<()> {
+    let sequence = MediaSequence::load(source, load_options)?;
+    let images = sequence.get_all_images();
+    let (width, height) = images[0].dimensions();
+
+    let width = u16::try_from(width).map_err(|_| anyhow!("GIF width {} too large", width))?;
+    let height = u16::try_from(height).map_err(|_| anyhow!("GIF height {} too large", height))?;
+
+    let durations = frame_durations(&sequence, frame_interval, use_frame_delays);
+
+    log::info!(
+        "Exporting {} frames to {} ({}x{}, {})",
+        images.len(),
+        output.display(),
+        width,
+        height,
+        match background {
+            Some([r, g, b]) => format!("background #{:02x}{:02x}{:02x}", r, g, b),
+            None => "1-bit transparency".to_string(),
+        }
+    );
+
+    let file = BufWriter::new(StdFile::create(output)?);
+    let mut encoder = gif::Encoder::new(file, width, height, &[])
+        .map_err(|e| anyhow!("Failed to create GIF encoder: {}", e))?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(|e| anyhow!("Failed to write GIF loop extension: {}", e))?;
+
+    for (i, (image, duration)) in images.iter().zip(&durations).enumerate() {
+        if image.dimensions() != (width as u32, height as u32) {
+            return Err(anyhow!(
+                "Frame {} is {}x{}, expected {}x{}",
+                i,
+                image.width(),
+                image.height(),
+                width,
+                height
+            ));
+        }
+
+        let mut pixels = match background {
+            Some(color) => composite_over_background(image, color).into_raw(),
+            None => threshold_alpha(image).into_raw(),
+        };
+
+        let mut frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+        // GIF delays are in hundredths of a second
+        frame.delay = (duration.as_millis() / 10).clamp(1, u16::MAX as u128) as u16;
+        // Every frame covers the full canvas, so clear it rather than drawing on top
+        frame.dispose = gif::DisposalMethod::Background;
+
+        encoder
+            .write_frame(&frame)
+            .map_err(|e| anyhow!("Failed to write GIF frame {}: {}", i, e))?;
+    }
+
+    log::info!("Exported {} frames to {}", images.len(), output.display());
+    Ok(())
+}
+
+/// How long each frame of `sequence` shows: its own delay when the source has
+/// one and `use_frame_delays` is set, otherwise `frame_interval`
+fn frame_durations(
+    sequence: &MediaSequence,
+    frame_interval: Duration,
+    use_frame_delays: bool,
+) -> Vec<Duration> {
+    (0..sequence.get_all_images().len())
+        .map(|i| {
+            sequence
+                .delay(i)
+                .filter(|_| use_frame_delays)
+                .unwrap_or(frame_interval)
+        })
+        .collect()
+}
+
+/// Name of the JSON manifest written alongside the frames in a zip export
+pub const ZIP_MANIFEST_NAME: &str = "manifest.json";
+
+/// Describes a zip export so other tools can play it back without guessing
+#[derive(Debug, Serialize)]
+struct ZipManifest<'a> {
+    anibuddy_version: &'a str,
+    frame_count: usize,
+    width: u32,
+    height: u32,
+    /// Interval for frames without a delay of their own
+    frame_interval_ms: f64,
+    /// How long each frame shows, in the order of `frames`
+    frame_durations_ms: Vec<f64>,
+    frames: &'a [String],
+}
+
+/// File name pattern and image type for exported frames, e.g. `shot_{n:05}.jpg`.
+///
+/// The pattern holds exactly one `{n}` placeholder for the frame number, which
+/// may request zero-padding to a width with `{n:0W}`. The extension picks the
+/// encoder: `png`, `jpg`/`jpeg` or `webp`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameNameTemplate {
+    prefix: String,
+    suffix: String,
+    padding: usize,
+    format: ImageFormat,
+}
+
+impl FrameNameTemplate {
+    pub fn parse(template: &str) -> Result<Self> {
+        let (open, close) = match (template.find('{'), template.find('}')) {
+            (Some(open), Some(close)) if open < close => (open, close),
+            _ => {
+                return Err(anyhow!(
+                    "'{}' needs a {{n}} placeholder for the frame number",
+                    template
+                ));
+            }
+        };
+
+        let prefix = &template[..open];
+        let suffix = &template[close + 1..];
+        if suffix.contains(['{', '}']) || prefix.contains('}') {
+            return Err(anyhow!(
+                "'{}' must contain exactly one {{n}} placeholder",
+                template
+            ));
+        }
+
+        let padding = match &template[open + 1..close] {
+            "n" => 0,
+            spec => spec
+                .strip_prefix("n:0")
+                .and_then(|width| width.parse().ok())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Unsupported placeholder {{{}}} in '{}', use {{n}} or {{n:0W}}",
+                        spec,
+                        template
+                    )
+                })?,
+        };
+
+        let format = suffix
+            .rsplit_once('.')
+            .and_then(|(_, extension)| ImageFormat::from_extension(extension))
+            .filter(|format| {
+                matches!(
+                    format,
+                    ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP
+                )
+            })
+            .ok_or_else(|| anyhow!("'{}' must end in .png, .jpg or .webp", template))?;
+
+        Ok(Self {
+            prefix: prefix.to_string(),
+            suffix: suffix.to_string(),
+            padding,
+            format,
+        })
+    }
+
+    /// `frame_0000.png`, padded to at least four digits (more for longer
+    /// sequences) so the names sort correctly
+    pub fn default_for(count: usize) -> Self {
+        Self {
+            prefix: "frame_".to_string(),
+            suffix: ".png".to_string(),
+            padding: count.saturating_sub(1).to_string().len().max(4),
+            format: ImageFormat::Png,
+        }
+    }
+
+    pub fn name(&self, index: usize) -> String {
+        format!(
+            "{}{:0width$}{}",
+            self.prefix,
+            index,
+            self.suffix,
+            width = self.padding
+        )
+    }
+
+    /// Encode a frame in the template's format. JPEG has no alpha channel, so
+    /// it's dropped rather than failing the export.
+    fn encode(&self, image: &RgbaImage) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let mut cursor = Cursor::new(&mut bytes);
+        match self.format {
+            ImageFormat::Jpeg => DynamicImage::ImageRgba8(image.clone())
+                .to_rgb8()
+                .write_to(&mut cursor, self.format)?,
+            format => image.write_to(&mut cursor, format)?,
+        }
+        Ok(bytes)
+    }
+}
+
+/// Name frames by `frame_format`, or [`FrameNameTemplate::default_for`] the
+/// sequence, warning when the format would drop its transparency
+fn frame_template(
+    sequence: &MediaSequence,
+    frame_format: Option<&FrameNameTemplate>,
+) -> FrameNameTemplate {
+    let template = frame_format
+        .cloned()
+        .unwrap_or_else(|| FrameNameTemplate::default_for(sequence.get_all_images().len()));
+    if template.format == ImageFormat::Jpeg && !sequence.is_opaque() {
+        log::warn!("JPEG can't store transparency, exported frames will be opaque");
+    }
+    template
+}
+
+/// Load a media source and write every composited frame into `dir` as a
+/// numbered image, without opening a window.
+///
+/// The directory is created if needed. Frames are named by `frame_format`, or
+/// [`FrameNameTemplate::default_for`] when none is given, so the directory
+/// plays back as the same sequence.
+pub fn export_frames(
+    source: MediaSource,
+    load_options: &LoadOptions,
+    dir: &Path,
+    frame_format: Option<&FrameNameTemplate>,
+) -> Result<()> {
+    let sequence = MediaSequence::load(source, load_options)?;
+    let images = sequence.get_all_images();
+    let template = frame_template(&sequence, frame_format);
+
+    log::info!("Exporting {} frames to {}", images.len(), dir.display());
+    std::fs::create_dir_all(dir)?;
+
+    for (i, image) in images.iter().enumerate() {
+        let path = dir.join(template.name(i));
+        let encoded = template
+            .encode(image)
+            .map_err(|e| anyhow!("Failed to encode {}: {}", path.display(), e))?;
+        std::fs::write(&path, encoded)
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+    }
+
+    log::info!("Exported {} frames to {}", images.len(), dir.display());
+    Ok(())
+}
+
+/// Load a media source and write every frame into a single zip archive.
+///
+/// Frames are named by `frame_format`, or [`FrameNameTemplate::default_for`]
+/// when none is given, and a [`ZIP_MANIFEST_NAME`] entry records the
+/// dimensions and timing, timed like [`export_gif`].
+pub fn export_zip(
+    source: MediaSource,
+    load_options: &LoadOptions,
+    output: &Path,
+    frame_interval: Duration,
+    use_frame_delays: bool,
+    frame_format: Option<&FrameNameTemplate>,
+) -> Result<()> {
+    let sequence = MediaSequence::load(source, load_options)?;
+    let images = sequence.get_all_images();
+    let (width, height) = images[0].dimensions();
+
+    let template = frame_template(&sequence, frame_format);
+
+    let names: Vec<String> = (0..images.len()).map(|i| template.name(i)).collect();
+    log::info!("Exporting {} frames to {}", images.len(), output.display());
+
+    let file = BufWriter::new(StdFile::create(output)?);
+    let mut archive = zip::ZipWriter::new(file);
+    // Frame data is already compressed, compressing it again only costs time
+    let options =
+        zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+
+    for (name, image) in names.iter().zip(images) {
+        let encoded = template
+            .encode(image)
+            .map_err(|e| anyhow!("Failed to encode {}: {}", name, e))?;
+
+        archive.start_file(name.as_str(), options)?;
+        archive.write_all(&encoded)?;
+    }
+
+    let manifest = ZipManifest {
+        anibuddy_version: env!("CARGO_PKG_VERSION"),
+        frame_count: images.len(),
+        width,
+        height,
+        frame_interval_ms: frame_interval.as_secs_f64() * 1000.0,
+        frame_durations_ms: frame_durations(&sequence, frame_interval, use_frame_delays)
+            .iter()
+            .map(|duration| duration.as_secs_f64() * 1000.0)
+            .collect(),
+        frames: &names,
+    };
+    archive.start_file(
+        ZIP_MANIFEST_NAME,
+        options.compression_method(zip::CompressionMethod::Deflated),
+    )?;
+    serde_json::to_writer_pretty(&mut archive, &manifest)?;
+
+    archive.finish()?;
+    log::info!("Exported {} frames to {}", images.len(), output.display());
+    Ok(())
+}
+
+/// Gap around and between contact sheet thumbnails
+const SHEET_PADDING: u32 = 4;
+const SHEET_BACKGROUND: Rgba<u8> = Rgba([32, 32, 32, 255]);
+/// Size of one font pixel in contact sheet labels
+const LABEL_SCALE: u32 = 2;
+
+/// 3x5 digit glyphs for contact sheet labels, one row per byte with the
+/// leftmost column in bit 2
+const DIGIT_GLYPHS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// Load a media source and write a PNG grid of numbered thumbnails, one per
+/// frame, as a quick visual index of the animation.
+///
+/// Frames are shrunk so their longer side is at most `thumb_size`. Without
+/// `columns` the grid is kept roughly square.
+pub fn export_contact_sheet(
+    source: MediaSource,
+    load_options: &LoadOptions,
+    output: &Path,
+    thumb_size: u32,
+    columns: Option<u32>,
+) -> Result<()> {
+    let sequence = MediaSequence::load(source, load_options)?;
+    let images = sequence.get_all_images();
+
+    let sheet = contact_sheet(images, thumb_size, columns);
+    log::info!(
+        "Writing a {}x{} contact sheet of {} frames to {}",
+        sheet.width(),
+        sheet.height(),
+        images.len(),
+        output.display()
+    );
+    sheet.save_with_format(output, ImageFormat::Png)?;
+    Ok(())
+}
+
+fn contact_sheet(images: &[RgbaImage], thumb_size: u32, columns: Option<u32>) -> RgbaImage {
+    let (width, height) = images[0].dimensions();
+    let scale = (thumb_size as f32 / width.max(height) as f32).min(1.0);
+    let thumb_width = ((width as f32 * scale).round() as u32).max(1);
+    let thumb_height = ((height as f32 * scale).round() as u32).max(1);
+
+    let count = images.len() as u32;
+    let columns = columns
+        .unwrap_or_else(|| (count as f64).sqrt().ceil() as u32)
+        .clamp(1, count);
+    let rows = count.div_ceil(columns);
+
+    let mut sheet = RgbaImage::from_pixel(
+        columns * (thumb_width + SHEET_PADDING) + SHEET_PADDING,
+        rows * (thumb_height + SHEET_PADDING) + SHEET_PADDING,
+        SHEET_BACKGROUND,
+    );
+
+    for (index, image) in images.iter().enumerate() {
+        let column = index as u32 % columns;
+        let row = index as u32 / columns;
+        let x = SHEET_PADDING + column * (thumb_width + SHEET_PADDING);
+        let y = SHEET_PADDING + row * (thumb_height + SHEET_PADDING);
+
+        let thumb = imageops::resize(image, thumb_width, thumb_height, FilterType::Triangle);
+        imageops::overlay(&mut sheet, &thumb, x as i64, y as i64);
+        draw_label(&mut sheet, x, y, index);
+    }
+
+    sheet
+}
+
+/// Draw `number` in white on a black box with its top-left corner at (`x`, `y`)
+fn draw_label(image: &mut RgbaImage, x: u32, y: u32, number: usize) {
+    let digits = number.to_string();
+    let advance = 4 * LABEL_SCALE;
+    let box_width = digits.len() as u32 * advance + LABEL_SCALE;
+    let box_height = 7 * LABEL_SCALE;
+
+    let mut put = |px: u32, py: u32, color: Rgba<u8>| {
+        if px < image.width() && py < image.height() {
+            image.put_pixel(px, py, color);
+        }
+    };
+
+    for dy in 0..box_height {
+        for dx in 0..box_width {
+            put(x + dx, y + dy, Rgba([0, 0, 0, 255]));
+        }
+    }
+
+    for (i, digit) in digits.bytes().enumerate() {
+        let glyph = DIGIT_GLYPHS[(digit - b'0') as usize];
+        let left = x + LABEL_SCALE + i as u32 * advance;
+        let top = y + LABEL_SCALE;
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) == 0 {
+                    continue;
+                }
+                for sy in 0..LABEL_SCALE {
+                    for sx in 0..LABEL_SCALE {
+                        put(
+                            left + column * LABEL_SCALE + sx,
+                            top + row as u32 * LABEL_SCALE + sy,
+                            Rgba([255, 255, 255, 255]),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Blend straight-alpha `image` over an opaque `background`, producing an opaque image
+pub fn composite_over_background(image: &RgbaImage, background: [u8; 3]) -> RgbaImage {
+    let mut output = image.clone();
+    for pixel in output.pixels_mut() {
+        let alpha = pixel[3] as u32;
+        for channel in 0..3 {
+            let blended =
+                pixel[channel] as u32 * alpha + background[channel] as u32 * (255 - alpha);
+            pixel[channel] = ((blended + 127) / 255) as u8;
+        }
+        pixel[3] = 255;
+    }
+    output
+}
+
+/// Reduce alpha to on/off, giving all transparent pixels the same color so they
+/// share a single palette entry
+fn threshold_alpha(image: &RgbaImage) -> RgbaImage {
+    let mut output = image.clone();
+    for pixel in output.pixels_mut() {
+        if pixel[3] >= GIF_ALPHA_THRESHOLD {
+            pixel[3] = 255;
+        } else {
+            *pixel = Rgba([0, 0, 0, 0]);
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_composite_over_background() {
+        let image =
+            RgbaImage::from_vec(3, 1, vec![255, 0, 0, 255, 255, 0, 0, 128, 255, 0, 0, 0]).unwrap();
+
+        let composited = composite_over_background(&image, [0, 0, 255]);
+        assert_eq!(composited.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+        assert_eq!(composited.get_pixel(1, 0), &Rgba([128, 0, 127, 255]));
+        assert_eq!(composited.get_pixel(2, 0), &Rgba([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn test_contact_sheet_layout() {
+        let images: Vec<RgbaImage> = (0..5u8)
+            .map(|i| RgbaImage::from_pixel(64, 32, Rgba([i * 50, 0, 255, 255])))
+            .collect();
+
+        // 32x16 thumbnails in 2 columns and 3 rows
+        let sheet = contact_sheet(&images, 32, Some(2));
+        assert_eq!(sheet.dimensions(), (2 * 36 + 4, 3 * 20 + 4));
+
+        // Bottom-right corner of frame 3's thumbnail, clear of its label
+        assert_eq!(
+            sheet.get_pixel(4 + 36 + 31, 4 + 20 + 15),
+            &Rgba([150, 0, 255, 255])
+        );
+        // The empty sixth cell shows the background
+        assert_eq!(sheet.get_pixel(4 + 36 + 16, 4 + 40 + 8), &SHEET_BACKGROUND);
+
+        // Frame 1's label: a black box with the top of the "1" glyph in white
+        let (x, y) = (4 + 36, 4);
+        assert_eq!(sheet.get_pixel(x, y), &Rgba([0, 0, 0, 255]));
+        assert_eq!(
+            sheet.get_pixel(x + 2 + 2, y + 2),
+            &Rgba([255, 255, 255, 255])
+        );
+        assert_eq!(sheet.get_pixel(x + 2, y + 2), &Rgba([0, 0, 0, 255]));
+
+        // Without a column count the grid stays roughly square
+        assert_eq!(contact_sheet(&images, 32, None).width(), 3 * 36 + 4);
+    }
+
+    #[test]
+    fn test_default_frame_names_padding() {
+        assert_eq!(FrameNameTemplate::default_for(2).name(1), "frame_0001.png");
+        assert_eq!(
+            FrameNameTemplate::default_for(10001).name(10000),
+            "frame_10000.png"
+        );
+        assert_eq!(
+            FrameNameTemplate::default_for(10001).name(7),
+            "frame_00007.png"
+        );
+    }
+
+    #[test]
+    fn test_frame_name_template() {
+        let template = FrameNameTemplate::parse("shot_{n:05}.jpg").unwrap();
+        assert_eq!(template.name(42), "shot_00042.jpg");
+        assert_eq!(template.format, ImageFormat::Jpeg);
+
+        let template = FrameNameTemplate::parse("{n}-frame.webp").unwrap();
+        assert_eq!(template.name(7), "7-frame.webp");
+        assert_eq!(template.format, ImageFormat::WebP);
+
+        for invalid in [
+            "frame.png",
+            "frame_{n}_{n}.png",
+            "frame_{i}.png",
+            "frame_{n:5}.png",
+            "frame_{n}.bmp",
+            "frame_{n}",
+            "frame_}{n.png",
+        ] {
+            assert!(
+                FrameNameTemplate::parse(invalid).is_err(),
+                "{} should be rejected",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_export_zip_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("anibuddy-export-zip-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for i in 0..3u8 {
+            RgbaImage::from_pixel(4, 2, Rgba([i * 40, 0, 0, 255 - i]))
+                .save(dir.join(format!("frame{}.png", i)))
+                .unwrap();
+        }
+
+        let output = dir.join("out.zip");
+        export_zip(
+            MediaSource::Directory(dir.clone()),
+            &LoadOptions::default(),
+            &output,
+            Duration::from_millis(50),
+            true,
+            None,
+        )
+        .unwrap();
+
+        let mut archive = zip::ZipArchive::new(StdFile::open(&output).unwrap()).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(str::to_string).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "frame_0000.png",
+                "frame_0001.png",
+                "frame_0002.png",
+                ZIP_MANIFEST_NAME
+            ]
+        );
+
+        for i in 0..3u8 {
+            let mut png = Vec::new();
+            std::io::Read::read_to_end(
+                &mut archive.by_name(&format!("frame_000{}.png", i)).unwrap(),
+                &mut png,
+            )
+            .unwrap();
+            let frame = image::load_from_memory(&png).unwrap().to_rgba8();
+            assert_eq!(frame.get_pixel(3, 1), &Rgba([i * 40, 0, 0, 255 - i]));
+        }
+
+        let manifest: serde_json::Value =
+            serde_json::from_reader(archive.by_name(ZIP_MANIFEST_NAME).unwrap()).unwrap();
+        assert_eq!(manifest["frame_count"], 3);
+        assert_eq!(manifest["width"], 4);
+        assert_eq!(manifest["frame_interval_ms"], 50.0);
+        assert_eq!(
+            manifest["frame_durations_ms"],
+            serde_json::json!([50.0, 50.0, 50.0])
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_export_frames_roundtrip() {
+        let dir =
+            std::env::temp_dir().join(format!("anibuddy-export-frames-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let gif = dir.join("in.gif");
+        let source = dir.join("source");
+        std::fs::create_dir_all(&source).unwrap();
+        for i in 0..3u8 {
+            RgbaImage::from_pixel(4, 2, Rgba([i * 100, 50, 0, 255]))
+                .save(source.join(format!("frame{}.png", i)))
+                .unwrap();
+        }
+        export_gif(
+            MediaSource::Directory(source),
+            &LoadOptions::default(),
+            &gif,
+            Duration::from_millis(100),
+            true,
+            None,
+        )
+        .unwrap();
+
+        let output = dir.join("frames");
+        export_frames(
+            MediaSource::GifFile(gif.clone()),
+            &LoadOptions::default(),
+            &output,
+            None,
+        )
+        .unwrap();
+
+        let mut names: Vec<String> = std::fs::read_dir(&output)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["frame_0000.png", "frame_0001.png", "frame_0002.png"]
+        );
+
+        // The directory plays back as the frames the GIF composited to
+        let original =
+            MediaSequence::load(MediaSource::GifFile(gif), &LoadOptions::default()).unwrap();
+        let exported =
+            MediaSequence::load(MediaSource::Directory(output), &LoadOptions::default()).unwrap();
+        assert_eq!(exported.get_all_images(), original.get_all_images());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_export_gif_with_background() {
+        let dir = std::env::temp_dir().join(format!("anibuddy-export-gif-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // Half-transparent white frames, which would be all-or-nothing without a background
+        for (i, alpha) in [128u8, 255].iter().enumerate() {
+            RgbaImage::from_pixel(4, 4, Rgba([255, 255, 255, *alpha]))
+                .save(dir.join(format!("frame{}.png", i)))
+                .unwrap();
+        }
+
+        let output = dir.join("out.gif");
+        export_gif(
+            MediaSource::Directory(dir.clone()),
+            &LoadOptions::default(),
+            &output,
+            Duration::from_millis(100),
+            true,
+            Some([0, 0, 0]),
+        )
+        .unwrap();
+
+        let exported =
+            MediaSequence::load(MediaSource::GifFile(output), &LoadOptions::default()).unwrap();
+        let frames = exported.get_all_images();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].get_pixel(0, 0), &Rgba([128, 128, 128, 255]));
+        assert_eq!(frames[1].get_pixel(0, 0), &Rgba([255, 255, 255, 255]));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_export_keeps_frame_delays() {
+        let dir =
+            std::env::temp_dir().join(format!("anibuddy-export-delays-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // A source GIF whose frames show for 50, 100 and 200 ms
+        let source = dir.join("in.gif");
+        {
+            let file = StdFile::create(&source).unwrap();
+            let mut encoder = gif::Encoder::new(file, 2, 2, &[]).unwrap();
+            for (i, delay) in [5u16, 10, 20].into_iter().enumerate() {
+                let mut pixels =
+                    RgbaImage::from_pixel(2, 2, Rgba([i as u8 * 100, 0, 0, 255])).into_raw();
+                let mut frame = gif::Frame::from_rgba_speed(2, 2, &mut pixels, 10);
+                frame.delay = delay;
+                encoder.write_frame(&frame).unwrap();
+            }
+        }
+        let delays = |sequence: &MediaSequence| -> Vec<Option<Duration>> {
+            (0..3).map(|i| sequence.delay(i)).collect()
+        };
+        let ms = |ms: u64| Some(Duration::from_millis(ms));
+
+        let gif = dir.join("out.gif");
+        let zip = dir.join("out.zip");
+        for fps_given in [false, true] {
+            let interval = Duration::from_millis(30);
+            export_gif(
+                MediaSource::GifFile(source.clone()),
+                &LoadOptions::default(),
+                &gif,
+                interval,
+                !fps_given,
+                None,
+            )
+            .unwrap();
+            export_zip(
+                MediaSource::GifFile(source.clone()),
+                &LoadOptions::default(),
+                &zip,
+                interval,
+                !fps_given,
+                None,
+            )
+            .unwrap();
+
+            let exported =
+                MediaSequence::load(MediaSource::GifFile(gif.clone()), &LoadOptions::default())
+                    .unwrap();
+            let mut archive = zip::ZipArchive::new(StdFile::open(&zip).unwrap()).unwrap();
+            let manifest: serde_json::Value =
+                serde_json::from_reader(archive.by_name(ZIP_MANIFEST_NAME).unwrap()).unwrap();
+
+            if fps_given {
+                assert_eq!(delays(&exported), vec![ms(30), ms(30), ms(30)]);
+                assert_eq!(
+                    manifest["frame_durations_ms"],
+                    serde_json::json!([30.0, 30.0, 30.0])
+                );
+            } else {
+                assert_eq!(delays(&exported), vec![ms(50), ms(100), ms(200)]);
+                assert_eq!(
+                    manifest["frame_durations_ms"],
+                    serde_json::json!([50.0, 100.0, 200.0])
+                );
+            }
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,16 +1,31 @@
 mod config;
 mod delta_compression;
+mod export;
 mod media_loader;
 mod overlay;
 mod renderer;
+mod shader_reload;
+mod stats_overlay;
+mod timing_trace;
 
 use anyhow::{Result, anyhow};
-use clap::{CommandFactory, Parser};
-use config::{Config, PresetConfig, is_likely_path};
+use clap::Parser;
+use config::{Config, ConfigWatcher, PresetConfig, get_config_path, is_likely_path};
+use delta_compression::CompressionOptions;
 use env_logger::Env;
-use media_loader::{MediaSource, detect_media_type};
-use overlay::OverlayApplication;
-use std::path::Path;
+use export::FrameNameTemplate;
+use media_loader::{
+    ChromaKey, DEFAULT_FORMATS, DEFAULT_MAX_DIMENSION, FrameRange, LoadOptions, MediaSource,
+    MismatchedFrames, SheetLayout, detect_media_type, sprite_sheet_source,
+};
+use overlay::{
+    ConfigWatch, DEBUG_KEYS, Easing, MonitorSelector, OverlayApplication, OverlayOptions,
+    PLAYBACK_KEYS, PlaybackMode, PositionSave, Reaction, SPEED_RANGE, WindowPosition, check_fps,
+    create_frame_interval,
+};
+use renderer::{AdapterOptions, Backend, BlendMode, ImageTransform, PowerPreference, PresentMode};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 #[derive(Parser)]
@@ -21,47 +36,483 @@ use std::time::Duration;
 - Directories containing image files (PNG, JPG, JPEG)
 - GIF files
 - APNG files
+- Animated WebP files
 - Named presets from config file
 
 Supports delta compression to reduce memory usage for animations with small changes between frames."#
 )]
+#[command(after_long_help = r#"Examples:
+  anibuddy ./frames              # Use frames directory
+  anibuddy --compress ./frames   # Use frames directory with compression
+  anibuddy animation.gif         # Use GIF file
+  anibuddy konata                # Use 'konata' preset
+  anibuddy konata --compress     # Use 'konata' preset with compression (overrides config)
+  anibuddy ./frames --fps 60     # Use frames directory at 60 FPS
+  anibuddy --list-presets        # Show the configured presets"#)]
 struct Args {
-    /// Path to directory with images, GIF file, APNG file, or preset name
+    /// Path to directory with images, GIF, APNG or WebP file, or preset name
     path_or_preset: Option<String>,
 
-    /// Frames per second (overrides preset FPS if specified)
-    #[arg(short, long)]
-    fps: Option<u64>,
+    /// Frames per second (overrides preset FPS and per-frame animation delays if specified)
+    #[arg(short, long, value_parser = parse_fps)]
+    fps: Option<f64>,
+
+    /// Playback speed multiplier, e.g. 2 for twice as fast or 0.5 for half speed
+    /// (+ and - change it while playing)
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0, value_parser = parse_speed)]
+    speed: f64,
 
     /// Enable delta compression for memory efficiency (overrides preset compression if specified)
     #[arg(short, long)]
     compress: bool,
 
-    /// List available presets and exit
+    /// Read presets from this file instead of anibuddy/config.toml under
+    /// $XDG_CONFIG_HOME or ~/.config
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Re-apply the playing preset's fps, opacity, position and path whenever
+    /// the config file changes
+    #[arg(long)]
+    watch: bool,
+
+    /// After dragging the window somewhere new, write its position into the
+    /// playing preset
+    #[arg(long)]
+    save_position: bool,
+
+    /// Treat the image as a sprite sheet of COLS x ROWS frames played in row-major
+    /// order, optionally with only the first FRAMES cells filled
+    #[arg(long, value_name = "COLS,ROWS[,FRAMES]")]
+    sheet: Option<SheetLayout>,
+
+    /// Load directory frames matching this glob, relative to the directory (e.g. "**/*.png")
+    #[arg(long, value_name = "PATTERN", conflicts_with = "watch_dir")]
+    glob: Option<String>,
+
+    /// Comma-separated image extensions to load from directories
+    #[arg(
+        long,
+        value_name = "EXTS",
+        value_delimiter = ',',
+        value_parser = parse_format,
+        default_values_t = DEFAULT_FORMATS.iter().map(|ext| ext.to_string())
+    )]
+    formats: Vec<String>,
+
+    /// Also load frames from subdirectories, in natural order of their relative paths
+    #[arg(long, conflicts_with_all = ["glob", "watch_dir"])]
+    recursive: bool,
+
+    /// Drop frames that are a single flat color, such as stray empty exports
+    #[arg(long)]
+    skip_blank: bool,
+
+    /// Show the first frame this many extra times at the start of each loop
+    #[arg(long, value_name = "FRAMES", default_value_t = 0)]
+    hold_first: usize,
+
+    /// Show the last frame this many extra times at the end of each loop
+    #[arg(long, value_name = "FRAMES", default_value_t = 0)]
+    hold_last: usize,
+
+    /// What to do when frames differ in size: refuse to load, or center every
+    /// frame on a transparent canvas the size of the largest
+    #[arg(long, value_enum, default_value_t = MismatchedFrames::Error)]
+    mismatched_frames: MismatchedFrames,
+
+    /// Make pixels of this RRGGBB background color transparent, for GIFs and
+    /// JPEG frames without alpha
+    #[arg(long, value_name = "RRGGBB", value_parser = parse_hex_rgb)]
+    chroma_key: Option<[u8; 3]>,
+
+    /// How far each channel may stray from --chroma-key and still be keyed
+    /// out. Raise it for JPEG noise and anti-aliased edges
+    #[arg(
+        long,
+        value_name = "0-255",
+        default_value_t = 0,
+        requires = "chroma_key"
+    )]
+    chroma_tolerance: u8,
+
+    /// Keep watching the source directory and append frames as they are written
+    #[arg(long)]
+    watch_dir: bool,
+
+    /// Decode directory frames from disk while playing, keeping at most this many
+    /// in memory, for sequences too long to load at once
+    #[arg(
+        long,
+        value_name = "FRAMES",
+        conflicts_with_all = ["watch_dir", "skip_blank", "hold_first", "hold_last", "mismatched_frames"]
+    )]
+    stream: Option<usize>,
+
+    /// Largest GIF/APNG/WebP canvas width or height to accept, guarding against corrupt metadata
+    #[arg(long, value_name = "PIXELS", default_value_t = DEFAULT_MAX_DIMENSION)]
+    max_dimension: u32,
+
+    /// How the animation blends with the desktop behind it
+    #[arg(long, value_enum, default_value_t = BlendMode::Over)]
+    blend: BlendMode,
+
+    /// How frames are presented: fifo waits for vsync, mailbox and immediate
+    /// cut latency (immediate may tear). Falls back to fifo when unsupported
+    #[arg(long, value_enum, default_value_t = PresentMode::Fifo)]
+    present_mode: PresentMode,
+
+    /// Render with this graphics API instead of the first one that works, to
+    /// troubleshoot transparency or performance problems with a driver
+    #[arg(long, value_enum)]
+    backend: Option<Backend>,
+
+    /// Prefer the integrated (low) or discrete (high) GPU
+    #[arg(long, value_enum)]
+    power_preference: Option<PowerPreference>,
+
+    /// Reduce displayed colors to N levels per channel for a posterized look
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(2..=256))]
+    posterize: Option<u32>,
+
+    /// Repeat the animation in a grid of COLUMNSxROWS copies, e.g. "3x2"
+    #[arg(long, value_name = "COLUMNSxROWS", value_parser = parse_tile)]
+    tile: Option<(u32, u32)>,
+
+    /// Opacity of the whole overlay, from 0.0 to 1.0 (overrides the preset's
+    /// `opacity`)
+    #[arg(long, value_name = "ALPHA", value_parser = parse_opacity)]
+    opacity: Option<f32>,
+
+    /// Color behind the animation as RRGGBBAA or RRGGBB hex, or "transparent".
+    /// Useful where the window manager shows transparency as black anyway.
+    #[arg(
+        long,
+        value_name = "RRGGBBAA",
+        default_value = "transparent",
+        value_parser = parse_background
+    )]
+    background: [u8; 4],
+
+    /// Draw an opaque window, filled with --background (black by default)
+    /// behind the animation. For displays without a compositor, where
+    /// transparency isn't available
+    #[arg(long)]
+    opaque: bool,
+
+    /// Window position, either "x,y" or one of top-left, top-right, bottom-left,
+    /// bottom-right, center (overrides the preset's `position`)
+    #[arg(
+        long,
+        value_name = "POSITION",
+        allow_hyphen_values = true,
+        conflicts_with = "ambient"
+    )]
+    position: Option<WindowPosition>,
+
+    /// Play only frames START to END-1 of the animation, e.g. "1:" to skip
+    /// the first frame (overrides the preset's `range`)
+    #[arg(long, value_name = "START:END", allow_hyphen_values = true)]
+    range: Option<FrameRange>,
+
+    /// Show the window on this monitor, by index from 0 or by name such as DP-1
+    /// (overrides the preset's `monitor`)
+    #[arg(long, value_name = "MONITOR")]
+    monitor: Option<MonitorSelector>,
+
+    /// Stretch the animation over the whole window instead of scaling it to
+    /// fit with its aspect ratio preserved
+    #[arg(long)]
+    fill: bool,
+
+    /// Rotate the animation clockwise by 90, 180 or 270 degrees
+    /// (overrides the preset's `rotate`)
+    #[arg(long, value_name = "DEGREES", value_parser = parse_rotation)]
+    rotate: Option<u32>,
+
+    /// Mirror the animation left to right, after rotating
+    /// (also settable per preset with `flip_h = true`)
+    #[arg(long)]
+    flip_h: bool,
+
+    /// Mirror the animation top to bottom, after rotating
+    /// (also settable per preset with `flip_v = true`)
+    #[arg(long)]
+    flip_v: bool,
+
+    /// Scale with nearest-neighbor sampling so pixel art stays crisp
+    /// (also settable per preset with `nearest = true`)
+    #[arg(long)]
+    nearest: bool,
+
+    /// Generate mipmaps for uncompressed frames so they stay smooth when the
+    /// window is smaller than the animation. Uses a third more video memory
     #[arg(long)]
+    mipmaps: bool,
+
+    /// Timing curve applied within each loop, speeding frames up and slowing them down
+    #[arg(long, value_enum, default_value_t = Easing::Linear)]
+    ease: Easing,
+
+    /// Play frames from last to first (overrides the preset's `playback`)
+    #[arg(long, conflicts_with_all = ["ping_pong", "shuffle"])]
+    reverse: bool,
+
+    /// Play frames to the end and back again without repeating either end
+    /// (overrides the preset's `playback`)
+    #[arg(long, conflicts_with = "shuffle")]
+    ping_pong: bool,
+
+    /// Play frames in a new random order each loop (for directories of independent images)
+    #[arg(long)]
+    shuffle: bool,
+
+    /// Seed for --shuffle, to repeat the same orders
+    #[arg(long, requires = "shuffle")]
+    seed: Option<u64>,
+
+    /// Don't clear the window before each frame. Only takes effect for fully
+    /// opaque animations blended with --blend over
+    #[arg(long)]
+    no_clear: bool,
+
+    /// Play for a fixed wall-clock time, looping as needed, then exit (e.g. 10s, 1m30s, 500ms)
+    #[arg(long, value_parser = parse_duration)]
+    duration: Option<Duration>,
+
+    /// Exit after playing the animation this many times (0 loops forever)
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    loops: u32,
+
+    /// Play the animation once, then exit (same as --loops 1)
+    #[arg(long, conflicts_with = "loops")]
+    play_once: bool,
+
+    /// Record per-frame display timing to this CSV file, for diagnosing stutter
+    #[arg(long, value_name = "FILE")]
+    trace_timing: Option<PathBuf>,
+
+    /// With --compress, write compression statistics as JSON to FILE ("-" for stdout)
+    #[arg(long, value_name = "FILE")]
+    stats_json: Option<PathBuf>,
+
+    /// With --compress, play uncompressed when compression shrinks the sequence
+    /// by less than this factor (e.g. 1.2)
+    #[arg(long, value_name = "RATIO")]
+    min_compression_ratio: Option<f32>,
+
+    /// With --compress, store every Nth frame in full so seeking replays fewer deltas
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    keyframe_interval: Option<u32>,
+
+    /// With --compress, the stored value for a full-range channel difference.
+    /// The default is lossless; lower values quantize deltas more coarsely
+    #[arg(
+        long,
+        value_name = "SCALE",
+        default_value_t = 32767,
+        value_parser = clap::value_parser!(u16).range(1..=32767)
+    )]
+    delta_scale: u16,
+
+    /// With --compress, store and upload deltas in square tiles of this many
+    /// pixels, skipping tiles that didn't change
+    #[arg(
+        long,
+        value_name = "PIXELS",
+        default_value_t = 16,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    delta_tile_size: u32,
+
+    /// With --compress, reconstruct the first N frames into textures once at load so
+    /// they play without a GPU round trip (a value of at least the frame count
+    /// reconstructs every frame, trading the memory savings for smooth playback)
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    cache_frames: usize,
+
+    /// With --compress, play the compressed sequence through once at load and log
+    /// its average PSNR, worst frame and peak error against the original frames
+    /// (roughly doubles the time compression takes)
+    #[arg(long)]
+    verify: bool,
+
+    /// With --compress, switch to uncompressed playback after this many consecutive
+    /// frames miss their deadline (0 never switches)
+    #[arg(long, default_value_t = 30, value_name = "N")]
+    max_missed_deadlines: u32,
+
+    /// Write the animation to a GIF file instead of opening a window
+    #[arg(long, value_name = "FILE")]
+    export_gif: Option<PathBuf>,
+
+    /// Composite exported GIF frames over this solid color (RRGGBB) instead of
+    /// reducing soft alpha to 1-bit transparency
+    #[arg(long, value_name = "RRGGBB", value_parser = parse_hex_rgb, requires = "export_gif")]
+    export_bg: Option<[u8; 3]>,
+
+    /// Write every frame as a PNG into this zip archive, with a JSON manifest,
+    /// instead of opening a window
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with = "export_gif",
+        group = "frame_export"
+    )]
+    export_zip: Option<PathBuf>,
+
+    /// Write every composited frame as a numbered PNG into this directory
+    /// instead of opening a window
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with = "export_gif",
+        group = "frame_export"
+    )]
+    export: Option<PathBuf>,
+
+    /// Name and type of frames in --export-zip or --export, with one {n} or
+    /// zero-padded {n:0W} placeholder (e.g. "shot_{n:05}.jpg"; png, jpg or webp)
+    #[arg(long, value_name = "TEMPLATE", value_parser = parse_frame_format, requires = "frame_export")]
+    frame_format: Option<FrameNameTemplate>,
+
+    /// Write a PNG grid of numbered frame thumbnails instead of opening a window
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["export_gif", "export_zip", "export"])]
+    contact_sheet: Option<PathBuf>,
+
+    /// Longest side of each --contact-sheet thumbnail
+    #[arg(long, value_name = "PIXELS", default_value_t = 128, value_parser = clap::value_parser!(u32).range(1..))]
+    thumb_size: u32,
+
+    /// Thumbnails per row in --contact-sheet (default: a roughly square grid)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..), requires = "contact_sheet")]
+    sheet_columns: Option<u32>,
+
+    /// Let mouse clicks pass through the window to the applications below it
+    /// (also settable per preset with `click_through = true`)
+    #[arg(long)]
+    click_through: bool,
+
+    /// Keep the window above other windows; 't' toggles it while playing
+    /// (also settable per preset with `always_on_top = true`)
+    #[arg(long, conflicts_with = "ambient")]
+    always_on_top: bool,
+
+    /// Borderless fullscreen, click-through window kept below other windows,
+    /// for a screen-wide ambient animation
+    #[arg(long)]
+    ambient: bool,
+
+    /// Log at info level and enable debug keys ('p' logs the pixel under the
+    /// cursor, 'm' toggles compressed playback, 'c' logs compression info)
+    #[arg(long)]
+    debug: bool,
+
+    /// Show the frame index, measured and target FPS, playback mode and frame
+    /// memory in the top-left corner (toggle with 's')
+    #[arg(long)]
+    stats: bool,
+
+    /// List available presets with their paths and FPS, then exit
+    #[arg(short, long)]
     list_presets: bool,
 }
 
 fn main() -> Result<()> {
-    // Initialize logger with default level None
-    env_logger::Builder::from_env(Env::default().default_filter_or("none")).init();
-
     let args = Args::parse();
 
+    // Initialize logger with default level None, or info when debugging
+    let default_filter = if args.debug { "info" } else { "none" };
+    env_logger::Builder::from_env(Env::default().default_filter_or(default_filter)).init();
+
     // Load config file
-    let config = Config::load()?;
+    let config = match &args.config {
+        Some(path) => Config::load(Some(path))?,
+        None => config_or_warn(
+            Config::load(None),
+            args.path_or_preset.as_deref(),
+            args.list_presets,
+        )?,
+    };
 
     // Handle list presets command
     if args.list_presets {
-        print_presets(&config);
+        print_presets(&config, config_path(&args).as_deref());
         return Ok(());
     }
 
+    // Display settings a preset can turn on as well as the command line
+    let preset = config
+        .as_ref()
+        .and_then(|cfg| cfg.selected_preset(args.path_or_preset.as_deref()));
+    let click_through = args.click_through || preset.is_some_and(PresetConfig::click_through);
+    let always_on_top = args.always_on_top || preset.is_some_and(PresetConfig::always_on_top);
+    let nearest = args.nearest || preset.is_some_and(PresetConfig::nearest);
+    let opacity = args
+        .opacity
+        .or(preset.and_then(|preset| preset.opacity))
+        .map_or(1.0, |opacity| opacity.clamp(0.0, 1.0));
+    let quarter_turns = match (args.rotate, preset.and_then(|preset| preset.rotate)) {
+        (Some(quarter_turns), _) => quarter_turns,
+        (None, Some(degrees)) => {
+            rotation_quarter_turns(degrees).map_err(|e| anyhow!("Invalid preset rotate: {}", e))?
+        }
+        (None, None) => 0,
+    };
+    let transform = ImageTransform {
+        quarter_turns,
+        flip_horizontal: args.flip_h || preset.is_some_and(PresetConfig::flip_h),
+        flip_vertical: args.flip_v || preset.is_some_and(PresetConfig::flip_v),
+    };
+    let playback = if args.reverse {
+        PlaybackMode::Reverse
+    } else if args.ping_pong {
+        PlaybackMode::PingPong
+    } else {
+        preset
+            .and_then(|preset| preset.playback)
+            .unwrap_or_default()
+    };
+    let position = match (
+        args.position,
+        preset.and_then(|preset| preset.position.as_ref()),
+    ) {
+        (Some(position), _) => Some(position),
+        (None, Some(position)) => Some(
+            position
+                .parse()
+                .map_err(|e| anyhow!("Invalid preset position: {}", e))?,
+        ),
+        (None, None) => None,
+    };
+    let range = match (args.range, preset.and_then(|preset| preset.range.as_ref())) {
+        (Some(range), _) => Some(range),
+        (None, Some(range)) => Some(
+            range
+                .parse()
+                .map_err(|e| anyhow!("Invalid preset range: {}", e))?,
+        ),
+        (None, None) => None,
+    };
+    let config_watch = if args.watch {
+        watch_config(&args, preset)
+    } else {
+        None
+    };
+    let save_position = if args.save_position {
+        position_save(&args, preset)
+    } else {
+        None
+    };
+    let monitor = args
+        .monitor
+        .or_else(|| preset.and_then(|preset| preset.monitor.clone()));
+
     // Determine media source, fps, and compression
-    let (media_source, fps, use_compression) = match args.path_or_preset {
+    let (media_source, fps, use_compression) = match &args.path_or_preset {
         Some(path_or_preset) => {
             let (source, config_fps, config_compress) =
-                resolve_path_or_preset(&config, &path_or_preset, args.fps)?;
+                resolve_path_or_preset(&config, path_or_preset, args.fps)?;
             let final_fps = args.fps.unwrap_or(config_fps);
             let final_compress = if args.compress { true } else { config_compress };
             (source, final_fps, final_compress)
@@ -76,36 +527,91 @@ fn main() -> Result<()> {
                 }
                 Err(_) => {
                     eprintln!(
-                        "Error: No path or preset specified and no default preset configured."
+                        "No path or preset specified and no default preset configured, \
+                         playing the built-in animation. See --help for all options."
                     );
-                    eprintln!();
-
-                    // Print the clap help message
-                    Args::command().print_help().unwrap();
-                    eprintln!(); // Add extra newline after help
-
-                    // Optionally still show config-specific hints
-                    if let Some(config) = &config {
-                        let presets = config.list_presets();
-                        if !presets.is_empty() {
-                            eprintln!("Available presets: {}", presets.join(", "));
-                            eprintln!("Use --list-presets to see preset details.");
-                        } else {
-                            eprintln!("No presets configured in config file.");
-                        }
-                    } else {
-                        eprintln!(
-                            "No config file found. Create ~/.config/anibuddy/config.toml to use presets."
-                        );
-                    }
-
-                    std::process::exit(1);
+
+                    (
+                        MediaSource::Embedded,
+                        args.fps.unwrap_or(30.0),
+                        args.compress,
+                    )
                 }
             }
         }
     };
 
+    // --sheet reinterprets whichever image the path or preset points to
+    let media_source = match args.sheet {
+        Some(layout) => {
+            let path = preset
+                .map(|preset| preset.path.as_str())
+                .or(args.path_or_preset.as_deref())
+                .ok_or_else(|| anyhow!("--sheet needs an image to slice"))?;
+            sprite_sheet_source(Path::new(path), layout)?
+        }
+        None => media_source,
+    };
+
     let frame_interval = create_frame_interval(fps);
+    let load_options = LoadOptions {
+        glob: args.glob,
+        recursive: args.recursive,
+        max_dimension: args.max_dimension,
+        skip_blank: args.skip_blank,
+        hold_first: args.hold_first,
+        hold_last: args.hold_last,
+        mismatched_frames: args.mismatched_frames,
+        formats: args.formats,
+        chroma_key: args.chroma_key.map(|color| ChromaKey {
+            color,
+            tolerance: args.chroma_tolerance,
+        }),
+    };
+
+    if let Some(output) = &args.export_gif {
+        return export::export_gif(
+            media_source,
+            &load_options,
+            output,
+            frame_interval,
+            args.fps.is_none(),
+            args.export_bg,
+        );
+    }
+
+    if let Some(output) = &args.export_zip {
+        return export::export_zip(
+            media_source,
+            &load_options,
+            output,
+            frame_interval,
+            args.fps.is_none(),
+            args.frame_format.as_ref(),
+        );
+    }
+
+    if let Some(dir) = &args.export {
+        return export::export_frames(media_source, &load_options, dir, args.frame_format.as_ref());
+    }
+
+    if let Some(output) = &args.contact_sheet {
+        return export::export_contact_sheet(
+            media_source,
+            &load_options,
+            output,
+            args.thumb_size,
+            args.sheet_columns,
+        );
+    }
+
+    let reactions = resolve_reactions(&config, args.debug)?;
+
+    let shuffle_seed = args.shuffle.then(|| {
+        let seed = args.seed.unwrap_or_else(rand::random);
+        log::info!("Shuffling frames with seed {}", seed);
+        seed
+    });
 
     if use_compression {
         log::info!("Starting application with delta compression enabled");
@@ -113,22 +619,101 @@ fn main() -> Result<()> {
         log::info!("Starting application with standard (uncompressed) mode");
     }
 
-    let mut app = OverlayApplication::new(media_source, frame_interval, use_compression);
+    let mut app = OverlayApplication::new(
+        media_source,
+        OverlayOptions {
+            load_options,
+            range,
+            watch_dir: args.watch_dir,
+            stream_window: args.stream,
+            frame_interval,
+            // An explicit --fps keeps every frame on the same interval
+            use_frame_delays: args.fps.is_none(),
+            speed: args.speed,
+            use_compression,
+            blend_mode: args.blend,
+            background: args.background,
+            present_mode: args.present_mode,
+            adapter: AdapterOptions {
+                backend: args.backend,
+                power_preference: args.power_preference,
+            },
+            mipmaps: args.mipmaps,
+            posterize: args.posterize,
+            tile: args.tile,
+            nearest,
+            fill: args.fill,
+            transform,
+            opacity,
+            position,
+            monitor,
+            easing: args.ease,
+            playback,
+            shuffle_seed,
+            play_duration: args.duration,
+            loops: if args.play_once { 1 } else { args.loops },
+            min_compression_ratio: args.min_compression_ratio,
+            compression_options: CompressionOptions {
+                keyframe_interval: args.keyframe_interval.map(|interval| interval as usize),
+                delta_scale: f32::from(args.delta_scale),
+                tile_size: args.delta_tile_size,
+                ..Default::default()
+            },
+            cache_frames: args.cache_frames,
+            verify: args.verify,
+            max_missed_deadlines: args.max_missed_deadlines,
+            no_clear: args.no_clear,
+            opaque: args.opaque,
+            stats_json: args.stats_json,
+            trace_timing: args.trace_timing,
+            config_watch,
+            save_position,
+            ambient: args.ambient,
+            click_through,
+            always_on_top,
+            debug: args.debug,
+            stats: args.stats,
+            reactions,
+        },
+    );
     app.run()?;
 
     Ok(())
 }
 
+/// Keep going without a config when it couldn't be loaded (e.g. no home
+/// directory) but the arguments name a path that doesn't need one
+fn config_or_warn(
+    loaded: Result<Option<Config>>,
+    path_or_preset: Option<&str>,
+    list_presets: bool,
+) -> Result<Option<Config>> {
+    let needs_config = list_presets
+        || match path_or_preset {
+            Some(arg) => !is_likely_path(arg) && !Path::new(arg).exists(),
+            // Plays the built-in animation
+            None => false,
+        };
+
+    match loaded {
+        Err(e) if !needs_config => {
+            log::warn!("Continuing without config: {}", e);
+            Ok(None)
+        }
+        result => result,
+    }
+}
+
 /// Resolve a path or preset name to a MediaSource, FPS, and compression setting
 fn resolve_path_or_preset(
     config: &Option<Config>,
     path_or_preset: &str,
-    fps_override: Option<u64>,
-) -> Result<(MediaSource, u64, bool)> {
+    fps_override: Option<f64>,
+) -> Result<(MediaSource, f64, bool)> {
     if is_likely_path(path_or_preset) {
         // Treat as path
         let media_source = create_media_source_from_path(path_or_preset)?;
-        let fps = fps_override.unwrap_or(30);
+        let fps = fps_override.unwrap_or(30.0);
         let compress = false; // Default to no compression for direct paths
         log::info!(
             "Using path: {} (fps: {}, compress: {})",
@@ -141,7 +726,7 @@ fn resolve_path_or_preset(
         // Try as preset first
         if let Some(preset) = cfg.get_preset(path_or_preset) {
             let media_source = create_media_source_from_preset(preset)?;
-            let fps = fps_override.unwrap_or(preset.fps.unwrap_or(30));
+            let fps = fps_override.unwrap_or(preset.fps.unwrap_or(30.0));
             let compress = preset.use_compression();
 
             if fps_override.is_some() {
@@ -170,7 +755,7 @@ fn resolve_path_or_preset(
     } else {
         // No config file, treat as path
         let media_source = create_media_source_from_path(path_or_preset)?;
-        let fps = fps_override.unwrap_or(30);
+        let fps = fps_override.unwrap_or(30.0);
         let compress = false; // Default to no compression when no config
         log::info!(
             "Using path: {} (fps: {}, compress: {})",
@@ -182,15 +767,108 @@ fn resolve_path_or_preset(
     }
 }
 
+/// Start watching the config for `--watch`, when a preset is playing
+fn watch_config(args: &Args, preset: Option<&PresetConfig>) -> Option<ConfigWatch> {
+    let Some(preset) = preset else {
+        log::warn!("--watch only applies when playing a preset, ignoring");
+        return None;
+    };
+    let name = args.path_or_preset.as_deref().unwrap_or("default");
+    let path = config_path(args)?;
+
+    match ConfigWatcher::new(&path) {
+        Ok(watcher) => Some(ConfigWatch {
+            watcher,
+            preset: name.to_string(),
+            path: preset.path.clone(),
+            fps_overridden: args.fps.is_some(),
+            opacity_overridden: args.opacity.is_some(),
+            position_overridden: args.position.is_some(),
+        }),
+        Err(e) => {
+            log::warn!("Can't watch {} for changes: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Where `--save-position` writes, when a preset is playing
+fn position_save(args: &Args, preset: Option<&PresetConfig>) -> Option<PositionSave> {
+    let Some(preset) = preset else {
+        log::warn!("--save-position only applies when playing a preset, ignoring");
+        return None;
+    };
+    Some(PositionSave {
+        config_path: config_path(args)?,
+        preset: args
+            .path_or_preset
+            .clone()
+            .unwrap_or_else(|| "default".to_string()),
+        relative_to_monitor: preset.monitor.is_some(),
+    })
+}
+
+/// Resolve the config's `[reactions]` section into playable sources keyed by
+/// lowercase key name, warning about keys that clash with built-in controls
+fn resolve_reactions(config: &Option<Config>, debug: bool) -> Result<HashMap<String, Reaction>> {
+    let mut reactions = HashMap::new();
+    let Some(cfg) = config else {
+        return Ok(reactions);
+    };
+
+    for (key, preset_name) in &cfg.reactions {
+        let preset = cfg.get_preset(preset_name).ok_or_else(|| {
+            anyhow!(
+                "Reaction for key '{}' refers to unknown preset '{}'",
+                key,
+                preset_name
+            )
+        })?;
+        let fps = preset.fps.unwrap_or(30.0);
+        log::info!(
+            "Key '{}' plays preset '{}': {} (fps: {})",
+            key,
+            preset_name,
+            preset.path,
+            fps
+        );
+
+        let name = key.to_lowercase();
+        if debug && DEBUG_KEYS.contains(&name.as_str()) {
+            log::warn!(
+                "Key '{}' is a debug key, so it won't play preset '{}' with --debug",
+                key,
+                preset_name
+            );
+        } else if PLAYBACK_KEYS.contains(&name.as_str()) {
+            log::warn!(
+                "Key '{}' plays preset '{}' instead of its built-in playback control",
+                key,
+                preset_name
+            );
+        }
+
+        reactions.insert(
+            name,
+            Reaction {
+                source: create_media_source_from_preset(preset)?,
+                frame_interval: create_frame_interval(fps),
+            },
+        );
+    }
+
+    Ok(reactions)
+}
+
 /// Get the default preset if available
 fn get_default_preset(
     config: &Option<Config>,
-    fps_override: Option<u64>,
-) -> Result<(MediaSource, u64, bool)> {
+    fps_override: Option<f64>,
+) -> Result<(MediaSource, f64, bool)> {
     if let Some(cfg) = config {
         if let Some(default_preset) = cfg.get_default() {
             let media_source = create_media_source_from_preset(default_preset)?;
-            let fps = fps_override.unwrap_or(default_preset.fps.unwrap_or(30));
+            let fps = fps_override.unwrap_or(default_preset.fps.unwrap_or(30.0));
             let compress = default_preset.use_compression();
 
             if fps_override.is_some() {
@@ -222,8 +900,8 @@ fn get_default_preset(
 fn try_path_fallback(
     config: &Config,
     arg: &str,
-    fps_override: Option<u64>,
-) -> Result<(MediaSource, u64, bool)> {
+    fps_override: Option<f64>,
+) -> Result<(MediaSource, f64, bool)> {
     let path = Path::new(arg);
     if !path.exists() {
         let available_presets = config.list_presets();
@@ -243,7 +921,7 @@ fn try_path_fallback(
     }
 
     let media_source = detect_media_type(path)?;
-    let fps = fps_override.unwrap_or(30);
+    let fps = fps_override.unwrap_or(30.0);
     let compress = false; // Default to no compression for fallback paths
     log::info!("Using path: {} (fps: {}, compress: {})", arg, fps, compress);
     Ok((media_source, fps, compress))
@@ -258,7 +936,15 @@ fn create_media_source_from_preset(preset: &PresetConfig) -> Result<MediaSource>
             preset.path
         ));
     }
-    detect_media_type(path)
+    match &preset.sheet {
+        Some(sheet) => {
+            let layout = sheet
+                .parse()
+                .map_err(|e| anyhow!("Invalid preset sheet: {}", e))?;
+            sprite_sheet_source(path, layout)
+        }
+        None => detect_media_type(path),
+    }
 }
 
 /// Create a MediaSource from a path string
@@ -270,92 +956,389 @@ fn create_media_source_from_path(path_str: &str) -> Result<MediaSource> {
     detect_media_type(path)
 }
 
-/// Create a Duration for the frame interval based on FPS
-fn create_frame_interval(fps: u64) -> Duration {
-    if fps > 0 {
-        Duration::from_secs_f64(1.0 / fps as f64)
-    } else {
-        Duration::from_millis(33)
+/// Parse a human-friendly duration such as `10s`, `1m30s`, `500ms` or `2.5`.
+/// A bare number is interpreted as seconds.
+fn parse_duration(input: &str) -> std::result::Result<Duration, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("duration cannot be empty".to_string());
+    }
+
+    if let Ok(seconds) = input.parse::<f64>() {
+        return Duration::try_from_secs_f64(seconds).map_err(|e| e.to_string());
+    }
+
+    let mut total = Duration::ZERO;
+    let mut rest = input;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .ok_or_else(|| format!("missing unit after '{}' (use h, m, s or ms)", rest))?;
+        let (number, tail) = rest.split_at(number_len);
+        let value: f64 = number
+            .parse()
+            .map_err(|_| format!("invalid number in duration '{}'", input))?;
+
+        let unit_len = tail
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        let seconds = match unit {
+            "h" => value * 3600.0,
+            "m" => value * 60.0,
+            "s" => value,
+            "ms" => value / 1000.0,
+            _ => {
+                return Err(format!(
+                    "unknown duration unit '{}' (use h, m, s or ms)",
+                    unit
+                ));
+            }
+        };
+
+        total += Duration::try_from_secs_f64(seconds).map_err(|e| e.to_string())?;
+        rest = tail;
+    }
+
+    Ok(total)
+}
+
+/// Parse an exported frame file name template such as `shot_{n:05}.jpg`
+fn parse_frame_format(input: &str) -> std::result::Result<FrameNameTemplate, String> {
+    FrameNameTemplate::parse(input).map_err(|e| e.to_string())
+}
+
+/// Parse an `RRGGBB` hex color, with or without a leading `#`
+fn parse_hex_rgb(input: &str) -> std::result::Result<[u8; 3], String> {
+    let hex = input.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("'{}' is not an RRGGBB hex color", input));
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+    Ok([channel(0), channel(2), channel(4)])
+}
+
+/// Parse an `RRGGBBAA` or `RRGGBB` hex color, or `transparent`
+fn parse_background(input: &str) -> std::result::Result<[u8; 4], String> {
+    if input.trim().eq_ignore_ascii_case("transparent") {
+        return Ok([0, 0, 0, 0]);
+    }
+
+    let hex = input.trim().trim_start_matches('#');
+    let (rgb, alpha) = match hex.len() {
+        8 => hex.split_at(6),
+        _ => (hex, "ff"),
+    };
+    let invalid = || format!("'{}' is not an RRGGBBAA hex color or transparent", input);
+    let [r, g, b] = parse_hex_rgb(rgb).map_err(|_| invalid())?;
+    if !alpha.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let a = u8::from_str_radix(alpha, 16).map_err(|_| invalid())?;
+    Ok([r, g, b, a])
+}
+
+/// Parse an image file extension such as `png` or `.TIF`, refusing formats the
+/// decoder can't read
+fn parse_format(input: &str) -> std::result::Result<String, String> {
+    let ext = input.trim().trim_start_matches('.').to_lowercase();
+    match image::ImageFormat::from_extension(&ext) {
+        Some(format) if format.reading_enabled() => Ok(ext),
+        _ => Err(format!("'{}' is not a supported image format", input)),
+    }
+}
+
+fn parse_fps(input: &str) -> std::result::Result<f64, String> {
+    let fps: f64 = input
+        .trim()
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", input))?;
+    check_fps(fps)
+}
+
+fn parse_opacity(input: &str) -> std::result::Result<f32, String> {
+    let opacity: f32 = input
+        .trim()
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", input))?;
+    if !opacity.is_finite() {
+        return Err(format!("'{}' is not a number", input));
+    }
+    Ok(opacity.clamp(0.0, 1.0))
+}
+
+/// Clockwise quarter turns for a rotation given in degrees
+fn rotation_quarter_turns(degrees: u32) -> std::result::Result<u32, String> {
+    match degrees {
+        0 | 90 | 180 | 270 => Ok(degrees / 90),
+        _ => Err(format!(
+            "rotation must be 0, 90, 180 or 270 degrees, got {}",
+            degrees
+        )),
+    }
+}
+
+fn parse_rotation(input: &str) -> std::result::Result<u32, String> {
+    let degrees: u32 = input
+        .trim()
+        .parse()
+        .map_err(|_| format!("'{}' is not a number of degrees", input))?;
+    rotation_quarter_turns(degrees)
+}
+
+fn parse_speed(input: &str) -> std::result::Result<f64, String> {
+    let speed: f64 = input
+        .trim()
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", input))?;
+    if !speed.is_finite() || speed <= 0.0 {
+        return Err(format!("'{}' is not a positive speed", input));
+    }
+    Ok(speed.clamp(*SPEED_RANGE.start(), *SPEED_RANGE.end()))
+}
+
+fn parse_tile(input: &str) -> std::result::Result<(u32, u32), String> {
+    let invalid = || format!("'{}' is not a grid size like 3x2", input);
+    let (columns, rows) = input.split_once(['x', 'X']).ok_or_else(invalid)?;
+    let columns: u32 = columns.trim().parse().map_err(|_| invalid())?;
+    let rows: u32 = rows.trim().parse().map_err(|_| invalid())?;
+
+    if columns == 0 || rows == 0 {
+        return Err(format!("'{}' needs at least one column and one row", input));
+    }
+    Ok((columns, rows))
+}
+
+/// The config file in use: `--config`, or the default location when there is one
+fn config_path(args: &Args) -> Option<PathBuf> {
+    match &args.config {
+        Some(path) => Some(path.clone()),
+        None => get_config_path().ok(),
     }
 }
 
-/// Print available presets
-fn print_presets(config: &Option<Config>) {
+/// Print available presets, or where to create the config file at `path`
+fn print_presets(config: &Option<Config>, path: Option<&Path>) {
     if let Some(cfg) = config {
-        let presets = cfg.list_presets();
-        if !presets.is_empty() {
-            println!("Available presets:");
-            for preset_name in presets {
-                if let Some(preset) = cfg.get_preset(&preset_name) {
-                    println!(
-                        "  {} -> {} (fps: {}, compress: {})",
-                        preset_name,
-                        preset.path,
-                        preset.fps.unwrap_or(30),
-                        preset.use_compression()
-                    );
+        print!("{}", preset_listing(cfg));
+    } else {
+        match path {
+            Some(path) => println!(
+                "No config file found at {}. Create it, or pass --config, to configure presets.",
+                path.display()
+            ),
+            None => println!("No config file found. Pass --config to configure presets."),
+        }
+    }
+}
+
+/// One line per preset with its path and effective FPS, flagging paths that
+/// no longer exist
+fn preset_listing(config: &Config) -> String {
+    let presets = config.list_presets();
+    if presets.is_empty() {
+        return "No presets configured.\n".to_string();
+    }
+
+    let mut listing = String::from("Available presets:\n");
+    for preset_name in presets {
+        if let Some(preset) = config.get_preset(&preset_name) {
+            listing.push_str(&format!(
+                "  {} -> {} (fps: {}, compress: {}){}\n",
+                preset_name,
+                preset.path,
+                preset.fps.unwrap_or(30.0),
+                preset.use_compression(),
+                if Path::new(&preset.path).exists() {
+                    ""
+                } else {
+                    " [missing]"
                 }
-            }
-        } else {
-            println!("No presets configured.");
+            ));
         }
-    } else {
-        println!("No config file found.");
-        println!("Create ~/.config/anibuddy/config.toml to configure presets.");
     }
+    listing
 }
 
-/// Print usage hints and examples
-fn print_usage_hint(config: &Option<Config>) {
-    println!("Examples:");
-    println!("  anibuddy ./frames              # Use frames directory");
-    println!("  anibuddy --compress ./frames   # Use frames directory with compression");
-    println!("  anibuddy animation.gif         # Use GIF file");
-    println!("  anibuddy -c animation.gif      # Use GIF file with compression");
-    println!("  anibuddy konata                # Use 'konata' preset");
-    println!(
-        "  anibuddy --compress konata     # Use 'konata' preset with compression (overrides config)"
-    );
-    println!("  anibuddy ./frames --fps 60     # Use frames directory at 60 FPS");
-    println!("  anibuddy -c ./frames --fps 60  # Use frames directory at 60 FPS with compression");
-    println!();
-    println!("Controls:");
-    println!();
-
-    if let Some(config) = config {
-        let presets = config.list_presets();
-        if !presets.is_empty() {
-            println!("Available presets: {}", presets.join(", "));
-            println!("Use --list-presets to see preset details.");
-        } else {
-            println!("No presets configured.");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_listing_flags_missing_paths() {
+        let existing = std::env::temp_dir();
+        let config = Config::parse(&format!(
+            "[here]\npath = {:?}\nfps = 12\n\n[gone]\npath = \"/definitely/not/here.gif\"\n",
+            existing.display().to_string()
+        ))
+        .unwrap();
+
+        let listing = preset_listing(&config);
+        assert!(
+            listing.contains(
+                "  gone -> /definitely/not/here.gif (fps: 30, compress: false) [missing]"
+            )
+        );
+        assert!(listing.contains(&format!(
+            "  here -> {} (fps: 12, compress: false)\n",
+            existing.display()
+        )));
+    }
+
+    #[test]
+    fn test_args_in_any_order() {
+        for argv in [
+            [
+                "anibuddy",
+                "--fps",
+                "24",
+                "konata",
+                "--compress",
+                "--config",
+                "a.toml",
+            ],
+            ["anibuddy", "konata", "-c", "--config", "a.toml", "-f", "24"],
+        ] {
+            let args = Args::try_parse_from(argv).unwrap();
+            assert_eq!(args.path_or_preset.as_deref(), Some("konata"));
+            assert_eq!(args.fps, Some(24.0));
+            assert!(args.compress);
+            assert_eq!(args.config, Some(PathBuf::from("a.toml")));
         }
-    } else {
-        println!("No config file found. Create ~/.config/anibuddy/config.toml to use presets.");
-    }
-
-    println!();
-    println!("Config file format (~/.config/anibuddy/config.toml):");
-    println!("[default]");
-    println!("path = \"/path/to/default/animation\"");
-    println!("fps = 30");
-    println!("compress = false");
-    println!();
-    println!("[konata]");
-    println!("path = \"/path/to/konata/frames\"");
-    println!("fps = 24");
-    println!("compress = true");
-    println!();
-    println!("[1]");
-    println!("path = \"/path/to/animation1.gif\"");
-    println!("fps = 60");
-    println!("compress = false");
-    println!();
-    println!("Delta Compression:");
-    println!("Delta compression reduces memory usage by storing only the differences");
-    println!("between consecutive frames. This is especially effective for animations");
-    println!("with small changes between frames, potentially reducing memory usage");
-    println!("by 50-90% depending on the content.");
-    println!();
-    println!("The --compress CLI flag overrides the preset's compression setting.");
+
+        // A mistyped FPS is an error rather than a second path
+        assert!(Args::try_parse_from(["anibuddy", "konata", "--fps", "6o"]).is_err());
+        assert!(Args::try_parse_from(["anibuddy", "konata", "extra"]).is_err());
+        assert!(
+            Args::try_parse_from(["anibuddy"])
+                .unwrap()
+                .path_or_preset
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_adapter_flags() {
+        let args =
+            Args::try_parse_from(["anibuddy", "--backend", "dx12", "--power-preference", "low"])
+                .unwrap();
+        assert_eq!(args.backend, Some(Backend::Dx12));
+        assert_eq!(args.power_preference, Some(PowerPreference::Low));
+        assert!(Args::try_parse_from(["anibuddy", "--backend", "opengl"]).is_err());
+    }
+
+    #[test]
+    fn test_config_failure_only_fatal_for_presets() {
+        let no_home = || Err(anyhow!("Could not determine home directory"));
+
+        assert!(matches!(
+            config_or_warn(no_home(), Some("./frames"), false),
+            Ok(None)
+        ));
+        assert!(matches!(
+            config_or_warn(no_home(), Some("wave.gif"), false),
+            Ok(None)
+        ));
+        assert!(config_or_warn(no_home(), Some("konata"), false).is_err());
+        assert!(matches!(config_or_warn(no_home(), None, false), Ok(None)));
+        assert!(config_or_warn(no_home(), Some("./frames"), true).is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("10"), Ok(Duration::from_secs(10)));
+        assert_eq!(parse_duration("2.5"), Ok(Duration::from_millis(2500)));
+        assert_eq!(parse_duration("10s"), Ok(Duration::from_secs(10)));
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("1m30s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("10x").is_err());
+        assert!(parse_duration("-1").is_err());
+        assert!(parse_duration("s").is_err());
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(parse_format("png"), Ok("png".to_string()));
+        assert_eq!(parse_format(".TIF"), Ok("tif".to_string()));
+        assert!(parse_format("docx").is_err());
+    }
+
+    #[test]
+    fn test_parse_background() {
+        assert_eq!(parse_background("transparent"), Ok([0, 0, 0, 0]));
+        assert_eq!(parse_background("#10203080"), Ok([16, 32, 48, 128]));
+        assert_eq!(parse_background("102030"), Ok([16, 32, 48, 255]));
+        assert!(parse_background("1020308").is_err());
+        assert!(parse_background("102030zz").is_err());
+        assert!(parse_background("black").is_err());
+    }
+
+    #[test]
+    fn test_parse_hex_rgb() {
+        assert_eq!(parse_hex_rgb("ff8000"), Ok([255, 128, 0]));
+        assert_eq!(parse_hex_rgb("#00FF7f"), Ok([0, 255, 127]));
+
+        assert!(parse_hex_rgb("fff").is_err());
+        assert!(parse_hex_rgb("gg0000").is_err());
+        assert!(parse_hex_rgb("ff00001").is_err());
+    }
+
+    #[test]
+    fn test_parse_rotation() {
+        assert_eq!(parse_rotation("90"), Ok(1));
+        assert_eq!(parse_rotation("270"), Ok(3));
+        assert_eq!(parse_rotation("0"), Ok(0));
+        assert!(parse_rotation("45").is_err());
+        assert!(parse_rotation("-90").is_err());
+        assert!(parse_rotation("left").is_err());
+    }
+
+    #[test]
+    fn test_parse_fps() {
+        assert_eq!(parse_fps("7.5"), Ok(7.5));
+        assert_eq!(parse_fps("0.5"), Ok(0.5));
+        assert_eq!(parse_fps("60"), Ok(60.0));
+        assert_eq!(parse_fps("0"), Ok(0.0));
+        assert!(parse_fps("-1").is_err());
+        assert!(parse_fps("1e-20").is_err());
+        assert!(parse_fps("inf").is_err());
+        assert!(parse_fps("fast").is_err());
+    }
+
+    #[test]
+    fn test_parse_opacity() {
+        assert_eq!(parse_opacity("0.6"), Ok(0.6));
+        assert_eq!(parse_opacity("1.5"), Ok(1.0));
+        assert_eq!(parse_opacity("-1"), Ok(0.0));
+
+        assert!(parse_opacity("half").is_err());
+        assert!(parse_opacity("NaN").is_err());
+    }
+
+    #[test]
+    fn test_parse_speed() {
+        assert_eq!(parse_speed("2"), Ok(2.0));
+        assert_eq!(parse_speed("0.01"), Ok(0.05));
+        assert_eq!(parse_speed("100"), Ok(20.0));
+
+        assert!(parse_speed("0").is_err());
+        assert!(parse_speed("-2").is_err());
+        assert!(parse_speed("inf").is_err());
+        assert!(parse_speed("fast").is_err());
+    }
+
+    #[test]
+    fn test_parse_tile() {
+        assert_eq!(parse_tile("3x2"), Ok((3, 2)));
+        assert_eq!(parse_tile("1X4"), Ok((1, 4)));
+
+        assert!(parse_tile("3").is_err());
+        assert!(parse_tile("0x2").is_err());
+        assert!(parse_tile("3x-1").is_err());
+        assert!(parse_tile("axb").is_err());
+    }
 }
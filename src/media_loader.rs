@@ -1,76 +1,605 @@
 use anyhow::{Result, anyhow};
 use glob::glob;
-use image::{Rgba, RgbaImage};
+use image::codecs::webp::WebPDecoder;
+use image::{AnimationDecoder, ImageDecoder, Rgba, RgbaImage, imageops};
+use rayon::prelude::*;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs::File as StdFile;
+use std::io::BufReader;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc::{Receiver, channel};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 pub enum MediaSource {
     Directory(PathBuf),
     GifFile(PathBuf),
     ApngFile(PathBuf),
+    WebpFile(PathBuf),
+    /// A single image holding the frames in a grid, read in row-major order
+    SpriteSheet {
+        path: PathBuf,
+        layout: SheetLayout,
+    },
+    /// The small animation built into the binary, played when there's nothing else
+    Embedded,
+}
+
+/// A 48x48 GIF of a bouncing blob, kept tiny so it barely adds to the binary
+const EMBEDDED_ANIMATION: &[u8] = include_bytes!("../assets/default.gif");
+
+/// Largest canvas width or height accepted from GIF/APNG/WebP metadata by default
+pub const DEFAULT_MAX_DIMENSION: u32 = 16384;
+
+/// Still image extensions loaded from frame directories by default
+pub const DEFAULT_FORMATS: &[&str] = &["png", "jpg", "jpeg", "bmp", "tif", "tiff", "webp"];
+
+/// Delay used for animation frames that ask for 10ms or less. Browsers show
+/// such frames for 100ms too, and animations are authored against that.
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// What to do when a sequence's frames aren't all the same size
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MismatchedFrames {
+    /// Refuse to load the sequence, naming the first frame that differs
+    #[default]
+    Error,
+    /// Center every frame on a transparent canvas the size of the largest
+    Pad,
+}
+
+/// Options controlling how a `MediaSource` is turned into frames
+#[derive(Debug, Clone)]
+pub struct LoadOptions {
+    /// Glob pattern, relative to the source directory, selecting the frames to load.
+    /// Supports recursive patterns such as `**/*.png`.
+    pub glob: Option<String>,
+    /// Also load images from subdirectories of the source directory
+    pub recursive: bool,
+    /// Reject GIF/APNG/WebP files whose canvas is wider or taller than this, before
+    /// allocating anything for them
+    pub max_dimension: u32,
+    /// Drop frames that are a single flat color (including fully transparent)
+    pub skip_blank: bool,
+    /// Extra copies of the first frame to insert at the start of the sequence
+    pub hold_first: usize,
+    /// Extra copies of the last frame to append at the end of the sequence
+    pub hold_last: usize,
+    /// Whether frames of different sizes are an error or get padded
+    pub mismatched_frames: MismatchedFrames,
+    /// Lowercase file extensions loaded as frames from directories
+    pub formats: Vec<String>,
+    /// Background color to make transparent in every frame
+    pub chroma_key: Option<ChromaKey>,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            glob: None,
+            recursive: false,
+            max_dimension: DEFAULT_MAX_DIMENSION,
+            skip_blank: false,
+            hold_first: 0,
+            hold_last: 0,
+            mismatched_frames: MismatchedFrames::default(),
+            formats: DEFAULT_FORMATS.iter().map(|ext| ext.to_string()).collect(),
+            chroma_key: None,
+        }
+    }
+}
+
+/// A solid background color keyed out to transparency, for GIFs and JPEG
+/// frame dumps that have no alpha of their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChromaKey {
+    pub color: [u8; 3],
+    /// Largest difference in any channel still counted as the key color, to
+    /// catch compression noise and anti-aliased edges close to it
+    pub tolerance: u8,
+}
+
+impl ChromaKey {
+    /// Replace every pixel matching the key color with transparent black
+    pub fn apply(&self, image: &mut RgbaImage) {
+        for pixel in image.pixels_mut() {
+            let [r, g, b, _] = pixel.0;
+            if [r, g, b]
+                .iter()
+                .zip(self.color)
+                .all(|(&channel, key)| channel.abs_diff(key) <= self.tolerance)
+            {
+                *pixel = Rgba([0, 0, 0, 0]);
+            }
+        }
+    }
+}
+
+/// Grid of frames in a sprite sheet, `COLS,ROWS` or `COLS,ROWS,FRAMES` when
+/// the last row is only partly filled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SheetLayout {
+    pub columns: u32,
+    pub rows: u32,
+    /// Frames actually present, when fewer than `columns * rows`
+    pub frames: Option<usize>,
+}
+
+impl FromStr for SheetLayout {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, String> {
+        let invalid = || format!("'{}' is not a sheet layout like 4,2 or 4,2,7", input);
+        let parts: Vec<&str> = input.split(',').map(str::trim).collect();
+        let (columns, rows, frames) = match parts.as_slice() {
+            [columns, rows] => (columns, rows, None),
+            [columns, rows, frames] => (columns, rows, Some(frames)),
+            _ => return Err(invalid()),
+        };
+        let columns: u32 = columns.parse().map_err(|_| invalid())?;
+        let rows: u32 = rows.parse().map_err(|_| invalid())?;
+        let frames: Option<usize> = frames
+            .map(|frames| frames.parse())
+            .transpose()
+            .map_err(|_| invalid())?;
+
+        let cells = columns as usize * rows as usize;
+        if cells == 0 {
+            return Err(format!("'{}' needs at least one column and row", input));
+        }
+        if let Some(frames) = frames
+            && !(1..=cells).contains(&frames)
+        {
+            return Err(format!(
+                "'{}' has {} frames, but the grid holds 1 to {}",
+                input, frames, cells
+            ));
+        }
+
+        Ok(Self {
+            columns,
+            rows,
+            frames,
+        })
+    }
+}
+
+/// Half-open range of frame indices, `START:END`. Either side may be left out
+/// to start at the first frame or run to the last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameRange {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+}
+
+impl FromStr for FrameRange {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, String> {
+        let invalid = || format!("'{}' is not a frame range like 10:50, 10: or :50", input);
+        let (start, end) = input.split_once(':').ok_or_else(invalid)?;
+        let bound = |bound: &str| match bound.trim() {
+            "" => Ok(None),
+            bound => bound.parse().map(Some).map_err(|_| invalid()),
+        };
+        Ok(Self {
+            start: bound(start)?,
+            end: bound(end)?,
+        })
+    }
+}
+
+impl FrameRange {
+    /// The first and one-past-last frame index this range selects from `count`
+    /// frames, clamping out-of-range bounds with a warning
+    pub fn bounds(self, count: usize) -> Result<(usize, usize)> {
+        let clamp = |bound: i64, side: &str| {
+            let clamped = bound.clamp(0, count as i64) as usize;
+            if clamped as i64 != bound {
+                log::warn!(
+                    "Range {} {} is outside the {} frames, using {}",
+                    side,
+                    bound,
+                    count,
+                    clamped
+                );
+            }
+            clamped
+        };
+        let start = self.start.map_or(0, |start| clamp(start, "start"));
+        let end = self.end.map_or(count, |end| clamp(end, "end"));
+
+        if start >= end {
+            return Err(anyhow!(
+                "Frame range {} leaves none of the {} frames",
+                self,
+                count
+            ));
+        }
+
+        log::info!("Playing frames {} to {} of {}", start, end - 1, count);
+        Ok((start, end))
+    }
+}
+
+impl fmt::Display for FrameRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(start) = self.start {
+            write!(f, "{}", start)?;
+        }
+        write!(f, ":")?;
+        if let Some(end) = self.end {
+            write!(f, "{}", end)?;
+        }
+        Ok(())
+    }
 }
 
 pub struct MediaSequence {
     images: Vec<RgbaImage>,
+    /// Files the frames were decoded from, for directory sources
+    paths: Vec<PathBuf>,
+    /// How long each frame is shown, for sources that carry their own timing
+    delays: Vec<Duration>,
     current_index: usize,
 }
 
 impl MediaSequence {
-    pub fn load(source: MediaSource) -> Result<Self> {
-        let images = match source {
-            MediaSource::Directory(path) => Self::load_image_directory(&path)?,
-            MediaSource::GifFile(path) => Self::load_gif(&path)?,
-            MediaSource::ApngFile(path) => Self::load_apng(&path)?,
+    pub fn load(source: MediaSource, options: &LoadOptions) -> Result<Self> {
+        let (mut images, mut paths, mut delays) = match source {
+            MediaSource::Directory(path) => {
+                let paths = Self::find_frame_paths(&path, options)?;
+                let (images, paths) = Self::decode_images(paths)?;
+                (images, paths, Vec::new())
+            }
+            MediaSource::GifFile(path) => {
+                let (images, delays) = Self::load_gif(&path, options.max_dimension)?;
+                (images, Vec::new(), delays)
+            }
+            MediaSource::ApngFile(path) => {
+                let (images, delays) = Self::load_apng(&path, options.max_dimension)?;
+                (images, Vec::new(), delays)
+            }
+            MediaSource::WebpFile(path) => {
+                let (images, delays) = Self::load_webp(&path, options.max_dimension)?;
+                (images, Vec::new(), delays)
+            }
+            MediaSource::SpriteSheet { path, layout } => (
+                Self::load_sprite_sheet(&path, layout)?,
+                Vec::new(),
+                Vec::new(),
+            ),
+            MediaSource::Embedded => {
+                log::info!("Loading the built-in animation");
+                let (images, delays) = Self::decode_gif(EMBEDDED_ANIMATION, options.max_dimension)?;
+                (images, Vec::new(), delays)
+            }
         };
 
         if images.is_empty() {
             return Err(anyhow!("No images loaded from source"));
         }
 
+        // Before blank detection, so frames that were only background count as blank
+        if let Some(key) = options.chroma_key {
+            images.par_iter_mut().for_each(|image| key.apply(image));
+        }
+        Self::match_frame_sizes(&mut images, &paths, options.mismatched_frames)?;
+        Self::handle_blank_frames(&mut images, &mut paths, &mut delays, options.skip_blank);
+        Self::add_hold_frames(&mut images, &mut paths, &mut delays, options);
+
         Ok(Self {
             images,
+            paths,
+            delays,
             current_index: 0,
         })
     }
 
-    fn load_image_directory(directory: &Path) -> Result<Vec<RgbaImage>> {
-        let patterns = ["*.png", "*.jpg", "*.jpeg"];
-        let mut image_paths = Vec::new();
+    /// Make sure every frame has the same size, which the renderer relies on.
+    /// Depending on `mismatched`, a frame that differs from the first is an
+    /// error or every frame is padded to the largest width and height.
+    fn match_frame_sizes(
+        images: &mut [RgbaImage],
+        paths: &[PathBuf],
+        mismatched: MismatchedFrames,
+    ) -> Result<()> {
+        let first = images[0].dimensions();
+        let Some(index) = images.iter().position(|image| image.dimensions() != first) else {
+            return Ok(());
+        };
+
+        if mismatched == MismatchedFrames::Error {
+            let frame = match paths.get(index) {
+                Some(path) => format!("Frame {} ({})", index, path.display()),
+                None => format!("Frame {}", index),
+            };
+            return Err(anyhow!(
+                "{} is {}x{}, but frame 0 is {}x{} (use --mismatched-frames pad to \
+                 letterbox frames of different sizes)",
+                frame,
+                images[index].width(),
+                images[index].height(),
+                first.0,
+                first.1
+            ));
+        }
+
+        let width = images.iter().map(RgbaImage::width).max().unwrap_or(0);
+        let height = images.iter().map(RgbaImage::height).max().unwrap_or(0);
+        let mut padded = 0;
+        for image in images.iter_mut() {
+            if image.dimensions() == (width, height) {
+                continue;
+            }
+            let mut canvas = RgbaImage::new(width, height);
+            let x = (width - image.width()) / 2;
+            let y = (height - image.height()) / 2;
+            imageops::replace(&mut canvas, image, x as i64, y as i64);
+            *image = canvas;
+            padded += 1;
+        }
+
+        log::info!(
+            "Frames differ in size, padded {} of them to {}x{}",
+            padded,
+            width,
+            height
+        );
+        Ok(())
+    }
+
+    /// Log blank frames and, when `skip` is set, remove them along with their
+    /// paths and delays. A sequence that is blank throughout is kept as is.
+    fn handle_blank_frames(
+        images: &mut Vec<RgbaImage>,
+        paths: &mut Vec<PathBuf>,
+        delays: &mut Vec<Duration>,
+        skip: bool,
+    ) {
+        let blank: Vec<bool> = images.iter().map(is_blank).collect();
+        let blank_count = blank.iter().filter(|&&b| b).count();
+        if blank_count == 0 {
+            return;
+        }
+
+        for (index, _) in blank.iter().enumerate().filter(|(_, is_blank)| **is_blank) {
+            log::info!("Frame {} is a single flat color", index);
+        }
+
+        if !skip {
+            return;
+        }
+
+        if blank_count == images.len() {
+            log::warn!("Every frame is blank, keeping them all");
+            return;
+        }
+
+        let mut keep = blank.iter().map(|&b| !b);
+        images.retain(|_| keep.next().unwrap_or(true));
+        if !paths.is_empty() {
+            let mut keep = blank.iter().map(|&b| !b);
+            paths.retain(|_| keep.next().unwrap_or(true));
+        }
+        if !delays.is_empty() {
+            let mut keep = blank.iter().map(|&b| !b);
+            delays.retain(|_| keep.next().unwrap_or(true));
+        }
+
+        log::info!("Skipped {} blank frames", blank_count);
+    }
+
+    /// Repeat the first and last frames, with their paths and delays, so the
+    /// animation dwells at its start and end each loop
+    fn add_hold_frames(
+        images: &mut Vec<RgbaImage>,
+        paths: &mut Vec<PathBuf>,
+        delays: &mut Vec<Duration>,
+        options: &LoadOptions,
+    ) {
+        if options.hold_first == 0 && options.hold_last == 0 {
+            return;
+        }
+
+        fn hold<T: Clone>(items: &mut Vec<T>, first: usize, last: usize) {
+            let (Some(head), Some(tail)) = (items.first().cloned(), items.last().cloned()) else {
+                return;
+            };
+            items.splice(0..0, std::iter::repeat_n(head, first));
+            items.extend(std::iter::repeat_n(tail, last));
+        }
+
+        hold(images, options.hold_first, options.hold_last);
+        hold(paths, options.hold_first, options.hold_last);
+        hold(delays, options.hold_first, options.hold_last);
+
+        log::info!(
+            "Holding the first frame {} and the last frame {} extra times, {} frames in total",
+            options.hold_first,
+            options.hold_last,
+            images.len()
+        );
+    }
+
+    /// The files a directory source plays, in order, without decoding them
+    pub fn find_frame_paths(directory: &Path, options: &LoadOptions) -> Result<Vec<PathBuf>> {
+        match &options.glob {
+            Some(pattern) => Self::find_glob_images(directory, pattern),
+            None if options.recursive => Self::find_images_recursive(directory, &options.formats),
+            None => Self::find_directory_images(directory, &options.formats),
+        }
+    }
 
-        for pattern in &patterns {
-            let full_pattern = directory.join(pattern).to_string_lossy().to_string();
-            let paths: Vec<PathBuf> = glob(&full_pattern)?.filter_map(Result::ok).collect();
-            image_paths.extend(paths);
+    fn find_directory_images(directory: &Path, formats: &[String]) -> Result<Vec<PathBuf>> {
+        let mut image_paths = Vec::new();
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.is_file() && is_directory_image(&path, formats) {
+                image_paths.push(path);
+            }
         }
 
-        image_paths.sort();
+        // Natural order across every format so frame2.bmp plays before frame10.png
+        image_paths.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
 
         if image_paths.is_empty() {
-            return Err(anyhow!("No image files found in {}", directory.display()));
+            return Err(anyhow!(
+                "No {} files found in {}",
+                formats.join("/"),
+                directory.display()
+            ));
         }
 
         log::info!("Found {} images in directory", image_paths.len());
+        Ok(image_paths)
+    }
 
-        let mut images = Vec::with_capacity(image_paths.len());
-        for path in image_paths {
-            log::debug!("Loading {}", path.display());
-            let img = image::open(&path)?.to_rgba8();
-            images.push(img);
+    /// Find every image below `directory`, following symlinks but visiting each
+    /// real directory only once so cycles terminate. Ordered by a natural sort of
+    /// the path relative to `directory`, like `find_glob_images`.
+    fn find_images_recursive(directory: &Path, formats: &[String]) -> Result<Vec<PathBuf>> {
+        let mut image_paths = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![directory.to_path_buf()];
+
+        while let Some(dir) = pending.pop() {
+            if !visited.insert(dir.canonicalize()?) {
+                log::debug!("Skipping already visited directory {}", dir.display());
+                continue;
+            }
+
+            for entry in std::fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else if path.is_file() && is_directory_image(&path, formats) {
+                    image_paths.push(path);
+                }
+            }
+        }
+
+        if image_paths.is_empty() {
+            return Err(anyhow!(
+                "No image files found in {} or its subdirectories",
+                directory.display()
+            ));
         }
 
-        Ok(images)
+        image_paths.sort_by(|a, b| {
+            let a = a.strip_prefix(directory).unwrap_or(a).to_string_lossy();
+            let b = b.strip_prefix(directory).unwrap_or(b).to_string_lossy();
+            natural_cmp(&a, &b)
+        });
+
+        log::info!(
+            "Found {} images in {} directories",
+            image_paths.len(),
+            visited.len()
+        );
+        Ok(image_paths)
+    }
+
+    /// Find every file matching `pattern` below `directory`, ordered by a natural
+    /// sort of the path relative to `directory` so `a/frame2` comes before `a/frame10`
+    fn find_glob_images(directory: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+        let full_pattern = Path::new(&glob::Pattern::escape(&directory.to_string_lossy()))
+            .join(pattern)
+            .to_string_lossy()
+            .to_string();
+
+        let mut image_paths: Vec<PathBuf> = glob(&full_pattern)?
+            .filter_map(Result::ok)
+            .filter(|path| path.is_file())
+            .collect();
+
+        if image_paths.is_empty() {
+            return Err(anyhow!(
+                "No files matching '{}' found in {}",
+                pattern,
+                directory.display()
+            ));
+        }
+
+        image_paths.sort_by(|a, b| {
+            let a = a.strip_prefix(directory).unwrap_or(a).to_string_lossy();
+            let b = b.strip_prefix(directory).unwrap_or(b).to_string_lossy();
+            natural_cmp(&a, &b)
+        });
+
+        log::info!(
+            "Found {} images matching '{}' in directory",
+            image_paths.len(),
+            pattern
+        );
+        Ok(image_paths)
+    }
+
+    /// Decode `image_paths` across all cores, returning the frames in the same
+    /// order as the paths. Each file is converted to RGBA as soon as it's decoded
+    /// so only one intermediate image per worker is alive at a time.
+    ///
+    /// Files that fail to decode are skipped with a warning and left out of the
+    /// returned paths; it's only an error if none of them decode.
+    fn decode_images(image_paths: Vec<PathBuf>) -> Result<(Vec<RgbaImage>, Vec<PathBuf>)> {
+        let decoded: Vec<Option<RgbaImage>> = image_paths
+            .par_iter()
+            .map(|path| {
+                log::debug!("Loading {}", path.display());
+                match image::open(path) {
+                    Ok(img) => Some(img.into_rgba8()),
+                    Err(e) => {
+                        log::warn!("Skipping {}: {}", path.display(), e);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        let found = image_paths.len();
+        let (images, paths): (Vec<RgbaImage>, Vec<PathBuf>) = decoded
+            .into_iter()
+            .zip(image_paths)
+            .filter_map(|(image, path)| Some((image?, path)))
+            .unzip();
+
+        if images.is_empty() {
+            return Err(anyhow!(
+                "None of the {} image files could be decoded",
+                found
+            ));
+        }
+        if images.len() < found {
+            log::warn!("Skipped {} of {} image files", found - images.len(), found);
+        }
+        Ok((images, paths))
     }
 
-    fn load_gif(path: &Path) -> Result<Vec<RgbaImage>> {
+    /// Decode every GIF frame composited onto the full canvas, along with how
+    /// long each frame is shown.
+    ///
+    /// Global and local color tables, per-frame transparent indices, interlaced
+    /// frames, sub-rectangle frames and all three disposal methods are handled.
+    fn load_gif(path: &Path, max_dimension: u32) -> Result<(Vec<RgbaImage>, Vec<Duration>)> {
         log::info!("Loading GIF file: {}", path.display());
+        Self::decode_gif(StdFile::open(path)?, max_dimension)
+    }
 
-        let file = StdFile::open(path)?;
+    /// [`Self::load_gif`] for GIF data from any reader
+    fn decode_gif(
+        reader: impl std::io::Read,
+        max_dimension: u32,
+    ) -> Result<(Vec<RgbaImage>, Vec<Duration>)> {
         let mut decoder = gif::DecodeOptions::new();
         decoder.set_color_output(gif::ColorOutput::RGBA);
 
         let mut decoder = decoder
-            .read_info(file)
+            .read_info(reader)
             .map_err(|e| anyhow!("Failed to read GIF info: {}", e))?;
 
         // Get the logical screen dimensions (full canvas size)
@@ -78,10 +607,13 @@ impl MediaSequence {
         let canvas_height = decoder.height() as u32;
 
         log::info!("GIF canvas size: {}x{}", canvas_width, canvas_height);
+        check_canvas_size(canvas_width, canvas_height, max_dimension)?;
 
         let mut images = Vec::new();
+        let mut delays = Vec::new();
         let mut canvas = RgbaImage::new(canvas_width, canvas_height);
-        let mut previous_canvas: Option<RgbaImage> = None;
+        // Canvas as it was before the current frame was drawn, for `Previous` disposal
+        let mut previous_canvas = canvas.clone();
 
         while let Some(frame) = decoder
             .read_next_frame()
@@ -111,9 +643,10 @@ impl MediaSequence {
                     let canvas_x = frame_left + x;
                     let canvas_y = frame_top + y;
 
-                    if canvas_x < canvas_width && canvas_y < canvas_height {
-                        let pixel = frame_image.get_pixel(x, y);
-                        // Always composite the pixel (including transparent ones for proper clearing)
+                    let pixel = frame_image.get_pixel(x, y);
+                    // The transparent index leaves whatever is already on the canvas
+                    // visible; clearing is the job of the previous frame's disposal
+                    if canvas_x < canvas_width && canvas_y < canvas_height && pixel[3] != 0 {
                         canvas.put_pixel(canvas_x, canvas_y, *pixel);
                     }
                 }
@@ -121,6 +654,7 @@ impl MediaSequence {
 
             // Save the current canvas state as this frame's output
             images.push(canvas.clone());
+            delays.push(gif_frame_delay(frame.delay));
 
             // Now handle disposal method to prepare canvas for the next frame
             match frame.dispose {
@@ -137,9 +671,7 @@ impl MediaSequence {
                 }
                 gif::DisposalMethod::Previous => {
                     // Restore to the state before this frame
-                    if let Some(ref prev) = previous_canvas {
-                        canvas = prev.clone();
-                    }
+                    canvas = previous_canvas.clone();
                     // Don't update previous_canvas in this case
                     continue;
                 }
@@ -147,20 +679,24 @@ impl MediaSequence {
             }
 
             // Update previous canvas for potential restore
-            if frame.dispose != gif::DisposalMethod::Previous {
-                previous_canvas = Some(canvas.clone());
-            }
+            previous_canvas = canvas.clone();
         }
 
         log::info!("Loaded {} frames from GIF", images.len());
-        Ok(images)
+        Ok((images, delays))
     }
 
-    fn load_apng(path: &Path) -> Result<Vec<RgbaImage>> {
+    /// Decode every APNG frame, along with the delay from its frame control.
+    /// Frames are composited onto the canvas at their offsets, honoring each
+    /// frame's blend and dispose operations, so they come out ready to display.
+    /// A still PNG loads as a single frame without a delay.
+    fn load_apng(path: &Path, max_dimension: u32) -> Result<(Vec<RgbaImage>, Vec<Duration>)> {
         log::info!("Loading APNG file: {}", path.display());
 
         let file = StdFile::open(path)?;
-        let decoder = png::Decoder::new(file);
+        let mut decoder = png::Decoder::new(file);
+        // Palette, low bit depth and 16-bit images all come out as 8-bit samples
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
         let mut reader = decoder
             .read_info()
             .map_err(|e| anyhow!("Failed to read PNG info: {}", e))?;
@@ -170,8 +706,10 @@ impl MediaSequence {
         let canvas_height = reader.info().height;
 
         log::info!("APNG canvas size: {}x{}", canvas_width, canvas_height);
+        check_canvas_size(canvas_width, canvas_height, max_dimension)?;
 
         let mut images = Vec::new();
+        let mut delays = Vec::new();
 
         // Check if it's animated
         if let Some(animation_control) = reader.info().animation_control() {
@@ -179,45 +717,79 @@ impl MediaSequence {
 
             let buffer_size = reader.output_buffer_size();
             let mut buffer = vec![0; buffer_size];
+            let mut canvas = RgbaImage::new(canvas_width, canvas_height);
 
             // Read all frames
             loop {
                 match reader.next_frame(&mut buffer) {
                     Ok(output_info) => {
-                        // Always use the full canvas dimensions, not the frame output dimensions
-                        let width = canvas_width;
-                        let height = canvas_height;
-
-                        let rgba_buffer = match output_info.color_type {
-                            png::ColorType::Rgba => buffer.clone(),
-                            png::ColorType::Rgb => {
-                                let mut rgba = Vec::with_capacity((width * height * 4) as usize);
-                                for chunk in buffer.chunks(3) {
-                                    rgba.extend_from_slice(chunk);
-                                    rgba.push(255);
-                                }
-                                rgba
-                            }
-                            _ => {
-                                return Err(anyhow!(
-                                    "Unsupported PNG color type: {:?}",
-                                    output_info.color_type
-                                ));
-                            }
+                        // A default image without a frame control isn't part of the animation
+                        let Some(control) = reader.info().frame_control().copied() else {
+                            log::debug!("Skipping default image outside the animation");
+                            continue;
                         };
 
-                        // Ensure we have the right buffer size for the full canvas
-                        let expected_size = (width * height * 4) as usize;
-                        let mut full_buffer = rgba_buffer;
-                        if full_buffer.len() != expected_size {
-                            // If the frame buffer doesn't match canvas size, pad or crop as needed
-                            full_buffer.resize(expected_size, 0);
+                        let samples = &buffer[..output_info.buffer_size()];
+                        let frame_image = RgbaImage::from_raw(
+                            output_info.width,
+                            output_info.height,
+                            png_samples_to_rgba(samples, output_info.color_type)?,
+                        )
+                        .ok_or_else(|| anyhow!("Failed to create image from APNG frame"))?;
+
+                        let frame_left = control.x_offset;
+                        let frame_top = control.y_offset;
+                        log::debug!(
+                            "Frame: {}x{} at ({}, {}) blend: {:?} dispose: {:?}",
+                            output_info.width,
+                            output_info.height,
+                            frame_left,
+                            frame_top,
+                            control.blend_op,
+                            control.dispose_op
+                        );
+
+                        // Canvas as it was before this frame, for `Previous` disposal
+                        let previous_canvas = (control.dispose_op == png::DisposeOp::Previous)
+                            .then(|| canvas.clone());
+
+                        for (x, y, pixel) in frame_image.enumerate_pixels() {
+                            let canvas_x = frame_left + x;
+                            let canvas_y = frame_top + y;
+                            if canvas_x >= canvas_width || canvas_y >= canvas_height {
+                                continue;
+                            }
+
+                            let composited = match control.blend_op {
+                                png::BlendOp::Source => *pixel,
+                                png::BlendOp::Over => {
+                                    blend_over(*canvas.get_pixel(canvas_x, canvas_y), *pixel)
+                                }
+                            };
+                            canvas.put_pixel(canvas_x, canvas_y, composited);
                         }
 
-                        let rgba_image = RgbaImage::from_raw(width, height, full_buffer)
-                            .ok_or_else(|| anyhow!("Failed to create image from APNG frame"))?;
+                        images.push(canvas.clone());
+                        delays.push(apng_frame_delay(control.delay_num, control.delay_den));
 
-                        images.push(rgba_image);
+                        // Prepare the canvas for the next frame
+                        match control.dispose_op {
+                            png::DisposeOp::None => {}
+                            png::DisposeOp::Background => {
+                                let right = (frame_left + output_info.width).min(canvas_width);
+                                let bottom = (frame_top + output_info.height).min(canvas_height);
+                                for y in frame_top..bottom {
+                                    for x in frame_left..right {
+                                        canvas.put_pixel(x, y, Rgba([0, 0, 0, 0]));
+                                    }
+                                }
+                            }
+                            png::DisposeOp::Previous => {
+                                if let Some(previous_canvas) = previous_canvas {
+                                    canvas = previous_canvas;
+                                }
+                            }
+                        }
                     }
                     Err(e) if format!("{}", e).contains("End of image has been reached") => {
                         // Gracefully end loop
@@ -239,7 +811,85 @@ impl MediaSequence {
         }
 
         log::info!("Loaded {} frames from APNG", images.len());
-        Ok(images)
+        Ok((images, delays))
+    }
+
+    /// Slice a sprite sheet into its frames, left to right and top to bottom.
+    /// Pixels left over when the sheet doesn't divide evenly are ignored.
+    fn load_sprite_sheet(path: &Path, layout: SheetLayout) -> Result<Vec<RgbaImage>> {
+        log::info!("Loading sprite sheet: {}", path.display());
+
+        let sheet = image::open(path)
+            .map_err(|e| anyhow!("Failed to load {}: {}", path.display(), e))?
+            .into_rgba8();
+        let (width, height) = sheet.dimensions();
+        let cell_width = width / layout.columns;
+        let cell_height = height / layout.rows;
+        if cell_width == 0 || cell_height == 0 {
+            return Err(anyhow!(
+                "{}x{} sheet is too small for {} columns and {} rows",
+                width,
+                height,
+                layout.columns,
+                layout.rows
+            ));
+        }
+        if width % layout.columns != 0 || height % layout.rows != 0 {
+            log::warn!(
+                "{}x{} sheet doesn't divide into {}x{} cells evenly, ignoring the remainder",
+                width,
+                height,
+                layout.columns,
+                layout.rows
+            );
+        }
+
+        let cells = (layout.columns * layout.rows) as usize;
+        let frames = (0..cells)
+            .take(layout.frames.unwrap_or(cells))
+            .map(|cell| {
+                let x = (cell as u32 % layout.columns) * cell_width;
+                let y = (cell as u32 / layout.columns) * cell_height;
+                imageops::crop_imm(&sheet, x, y, cell_width, cell_height).to_image()
+            })
+            .collect::<Vec<_>>();
+
+        log::info!(
+            "Sliced {} frames of {}x{}",
+            frames.len(),
+            cell_width,
+            cell_height
+        );
+        Ok(frames)
+    }
+
+    /// Decode every frame of an animated WebP with its delay. The decoder
+    /// composites each frame onto the full canvas, applying the blend and
+    /// disposal methods, so frames come out ready to display.
+    fn load_webp(path: &Path, max_dimension: u32) -> Result<(Vec<RgbaImage>, Vec<Duration>)> {
+        log::info!("Loading WebP file: {}", path.display());
+
+        let mut decoder = WebPDecoder::new(BufReader::new(StdFile::open(path)?))
+            .map_err(|e| anyhow!("Failed to read WebP info: {}", e))?;
+
+        let (canvas_width, canvas_height) = decoder.dimensions();
+        log::info!("WebP canvas size: {}x{}", canvas_width, canvas_height);
+        check_canvas_size(canvas_width, canvas_height, max_dimension)?;
+
+        // Disposed areas become transparent, as in GIFs, rather than taking the
+        // background color stored in the file
+        decoder.set_background_color(Rgba([0, 0, 0, 0]))?;
+
+        let mut images = Vec::new();
+        let mut delays = Vec::new();
+        for frame in decoder.into_frames() {
+            let frame = frame.map_err(|e| anyhow!("Failed to read WebP frame: {}", e))?;
+            delays.push(authored_delay(Duration::from(frame.delay())));
+            images.push(frame.into_buffer());
+        }
+
+        log::info!("Loaded {} frames from WebP", images.len());
+        Ok((images, delays))
     }
 
     pub fn current_image(&self) -> Option<&RgbaImage> {
@@ -253,6 +903,383 @@ impl MediaSequence {
     pub fn get_all_images(&self) -> &[RgbaImage] {
         &self.images
     }
+
+    /// Whether every frame is fully opaque
+    pub fn is_opaque(&self) -> bool {
+        self.images.iter().all(is_opaque)
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// How long frame `index` should be shown, when the source specifies it
+    pub fn delay(&self, index: usize) -> Option<Duration> {
+        self.delays.get(index).copied()
+    }
+
+    /// Keep only the frames in `range`, along with their paths and delays.
+    /// Bounds outside the sequence are clamped with a warning; a range that
+    /// leaves no frames is an error.
+    pub fn trim(&mut self, range: FrameRange) -> Result<()> {
+        let count = self.images.len();
+        let (start, end) = range.bounds(count)?;
+
+        fn keep<T>(items: &mut Vec<T>, start: usize, end: usize) {
+            if items.len() >= end {
+                items.truncate(end);
+                items.drain(..start);
+            }
+        }
+
+        keep(&mut self.images, start, end);
+        keep(&mut self.paths, start, end);
+        keep(&mut self.delays, start, end);
+        self.current_index = 0;
+        Ok(())
+    }
+
+    /// Append a frame decoded after the initial load
+    pub fn push_image(&mut self, image: RgbaImage, path: PathBuf) {
+        self.images.push(image);
+        self.paths.push(path);
+    }
+}
+
+/// How long a new file may keep failing to decode, from its first failure,
+/// before it's given up on. Covers exporters that write frames slowly or in
+/// several chunks; writing to a given-up file queues it again.
+const WATCH_DECODE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Watches a frame directory for new images so a live-rendered animation can
+/// grow while it plays
+pub struct DirectoryWatcher {
+    _watcher: notify::RecommendedWatcher,
+    receiver: Receiver<PathBuf>,
+    known: HashSet<PathBuf>,
+    /// Extensions of the files picked up as new frames
+    formats: Vec<String>,
+    /// New files waiting to decode, with when they first failed to
+    pending: Vec<(PathBuf, Option<Instant>)>,
+    /// Files that failed to decode for longer than `decode_timeout`
+    given_up: HashSet<PathBuf>,
+    decode_timeout: Duration,
+}
+
+impl DirectoryWatcher {
+    /// Start watching `directory`. Create this before loading the sequence so files
+    /// added during the load aren't missed, then call [`Self::mark_known`].
+    pub fn new(directory: &Path, formats: &[String]) -> Result<Self> {
+        use notify::{EventKind, RecursiveMode, Watcher};
+
+        let (sender, receiver) = channel();
+        let mut watcher =
+            notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+                if let Ok(event) = result
+                    && matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                {
+                    for path in event.paths {
+                        let _ = sender.send(path);
+                    }
+                }
+            })?;
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+        log::info!("Watching {} for new frames", directory.display());
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver,
+            known: HashSet::new(),
+            formats: formats.to_vec(),
+            pending: Vec::new(),
+            given_up: HashSet::new(),
+            decode_timeout: WATCH_DECODE_TIMEOUT,
+        })
+    }
+
+    /// Record files that are already part of the sequence
+    pub fn mark_known(&mut self, paths: &[PathBuf]) {
+        self.known.extend(paths.iter().cloned());
+        self.pending.retain(|(path, _)| !self.known.contains(path));
+    }
+
+    /// Decode files that appeared since the last poll, in natural order.
+    ///
+    /// Stops at the first file that doesn't decode yet (it's probably still being
+    /// written) so frames are never appended out of order.
+    pub fn poll_new_frames(&mut self) -> Vec<(PathBuf, RgbaImage)> {
+        for path in self.receiver.try_iter() {
+            if !is_directory_image(&path, &self.formats)
+                || self.known.contains(&path)
+                || self.pending.iter().any(|(pending, _)| *pending == path)
+            {
+                continue;
+            }
+            if self.given_up.remove(&path) {
+                log::info!("{} changed, trying it again", path.display());
+            }
+            self.pending.push((path, None));
+        }
+
+        if self.pending.is_empty() {
+            return Vec::new();
+        }
+
+        self.pending
+            .sort_by(|(a, _), (b, _)| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+
+        let mut frames = Vec::new();
+        while let Some((path, first_failure)) = self.pending.first_mut() {
+            match image::open(&*path) {
+                Ok(image) => {
+                    let (path, _) = self.pending.remove(0);
+                    log::info!("Appending new frame {}", path.display());
+                    self.known.insert(path.clone());
+                    frames.push((path, image.to_rgba8()));
+                }
+                Err(e) => {
+                    let failing_since = *first_failure.get_or_insert_with(Instant::now);
+                    if failing_since.elapsed() >= self.decode_timeout {
+                        log::warn!("Giving up on {} until it changes: {}", path.display(), e);
+                        let (path, _) = self.pending.remove(0);
+                        self.given_up.insert(path);
+                        continue;
+                    }
+                    log::debug!("{} not ready yet: {}", path.display(), e);
+                    break;
+                }
+            }
+        }
+
+        frames
+    }
+}
+
+/// Decodes the frames of a directory on a background thread as playback
+/// reaches them, so only a window of decoded frames is ever in memory.
+/// Clones share the same decoder, which stops once the last one is dropped.
+#[derive(Clone)]
+pub struct FrameStream {
+    inner: Arc<StreamHandle>,
+}
+
+struct StreamHandle {
+    shared: Arc<StreamShared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+struct StreamShared {
+    paths: Vec<PathBuf>,
+    dimensions: (u32, u32),
+    chroma_key: Option<ChromaKey>,
+    /// Most frames kept decoded at once, counting from the one wanted next
+    window: usize,
+    state: Mutex<StreamState>,
+    changed: Condvar,
+}
+
+struct StreamState {
+    frames: HashMap<usize, RgbaImage>,
+    /// Frame playback needs next; the decoder works forward from here
+    wanted: usize,
+    shutdown: bool,
+}
+
+impl FrameStream {
+    /// Start streaming `paths`, keeping at most `window` frames decoded. The
+    /// first frame is decoded right away since it sets the sequence size.
+    pub fn new(paths: Vec<PathBuf>, window: usize, chroma_key: Option<ChromaKey>) -> Result<Self> {
+        let first_path = paths
+            .first()
+            .ok_or_else(|| anyhow!("No frames to stream"))?;
+        let mut first = image::open(first_path)
+            .map_err(|e| anyhow!("Failed to load {}: {}", first_path.display(), e))?
+            .into_rgba8();
+        if let Some(key) = chroma_key {
+            key.apply(&mut first);
+        }
+
+        let window = window.clamp(1, paths.len());
+        log::info!(
+            "Streaming {} frames, keeping up to {} decoded",
+            paths.len(),
+            window
+        );
+
+        let shared = Arc::new(StreamShared {
+            dimensions: first.dimensions(),
+            chroma_key,
+            window,
+            state: Mutex::new(StreamState {
+                frames: HashMap::from([(0, first)]),
+                wanted: 0,
+                shutdown: false,
+            }),
+            changed: Condvar::new(),
+            paths,
+        });
+
+        let worker = {
+            let shared = Arc::clone(&shared);
+            std::thread::Builder::new()
+                .name("frame-stream".to_string())
+                .spawn(move || shared.decode_ahead())?
+        };
+
+        Ok(Self {
+            inner: Arc::new(StreamHandle {
+                shared,
+                worker: Some(worker),
+            }),
+        })
+    }
+
+    pub fn count(&self) -> usize {
+        self.inner.shared.paths.len()
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.inner.shared.dimensions
+    }
+
+    pub fn window(&self) -> usize {
+        self.inner.shared.window
+    }
+
+    /// Take frame `index` out of the window, waiting for the decoder if it
+    /// hasn't got there yet, and let it move on to the frames after it
+    pub fn frame(&self, index: usize) -> RgbaImage {
+        let shared = &self.inner.shared;
+        let index = index % self.count();
+        let mut state = shared.state.lock().unwrap();
+        shared.advance(&mut state, index);
+
+        let frame = loop {
+            if let Some(frame) = state.frames.remove(&index) {
+                break frame;
+            }
+            log::debug!("Waiting for frame {} to decode", index);
+            state = shared.changed.wait(state).unwrap();
+        };
+
+        shared.advance(&mut state, (index + 1) % self.count());
+        frame
+    }
+
+    #[cfg(test)]
+    fn resident(&self) -> usize {
+        self.inner.shared.state.lock().unwrap().frames.len()
+    }
+}
+
+impl StreamShared {
+    /// How far `index` is ahead of the wanted frame, wrapping at the end
+    fn offset(&self, index: usize, wanted: usize) -> usize {
+        (index + self.paths.len() - wanted) % self.paths.len()
+    }
+
+    /// Point the decoder at `wanted`, evicting frames that fell out of the window
+    fn advance(&self, state: &mut StreamState, wanted: usize) {
+        if state.wanted == wanted {
+            return;
+        }
+        state.wanted = wanted;
+        state
+            .frames
+            .retain(|&index, _| self.offset(index, wanted) < self.window);
+        self.changed.notify_all();
+    }
+
+    /// Worker loop: decode the first missing frame of the window, or sleep
+    /// until playback moves the window
+    fn decode_ahead(&self) {
+        let mut state = self.state.lock().unwrap();
+        while !state.shutdown {
+            let missing = (0..self.window)
+                .map(|offset| (state.wanted + offset) % self.paths.len())
+                .find(|index| !state.frames.contains_key(index));
+            let Some(index) = missing else {
+                state = self.changed.wait(state).unwrap();
+                continue;
+            };
+
+            drop(state);
+            let frame = self.decode(index);
+            state = self.state.lock().unwrap();
+
+            if self.offset(index, state.wanted) < self.window {
+                state.frames.insert(index, frame);
+                self.changed.notify_all();
+            }
+        }
+    }
+
+    /// Decode frame `index`, substituting a transparent frame when the file
+    /// is unreadable or a different size, since the frame count is fixed
+    fn decode(&self, index: usize) -> RgbaImage {
+        let path = &self.paths[index];
+        let (width, height) = self.dimensions;
+        match image::open(path) {
+            Ok(image) if image.width() == width && image.height() == height => {
+                let mut frame = image.into_rgba8();
+                if let Some(key) = self.chroma_key {
+                    key.apply(&mut frame);
+                }
+                frame
+            }
+            Ok(image) => {
+                log::warn!(
+                    "{} is {}x{}, but the sequence is {}x{}; showing a blank frame",
+                    path.display(),
+                    image.width(),
+                    image.height(),
+                    width,
+                    height
+                );
+                RgbaImage::new(width, height)
+            }
+            Err(e) => {
+                log::warn!(
+                    "Failed to load {}: {}; showing a blank frame",
+                    path.display(),
+                    e
+                );
+                RgbaImage::new(width, height)
+            }
+        }
+    }
+}
+
+impl Drop for StreamHandle {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().shutdown = true;
+        self.shared.changed.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// A sprite sheet source for the image at `path`
+pub fn sprite_sheet_source(path: &Path, layout: SheetLayout) -> Result<MediaSource> {
+    if !path.is_file() {
+        return Err(anyhow!(
+            "Sprite sheet '{}' is not an image file",
+            path.display()
+        ));
+    }
+    Ok(MediaSource::SpriteSheet {
+        path: path.to_path_buf(),
+        layout,
+    })
+}
+
+/// Whether `path` has one of `formats` as its extension, ignoring case
+fn is_directory_image(path: &Path, formats: &[String]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| formats.contains(&ext.to_lowercase()))
+        .unwrap_or(false)
 }
 
 // Helper function to detect media type from path
@@ -279,7 +1306,18 @@ pub fn detect_media_type(path: &Path) -> Result<MediaSource> {
                     Ok(MediaSource::Directory(parent.to_path_buf()))
                 }
             }
-            Some("jpg") | Some("jpeg") => {
+            Some("webp") => {
+                if is_animated_webp(path)? {
+                    Ok(MediaSource::WebpFile(path.to_path_buf()))
+                } else {
+                    // Still WebP, treat as directory with one file
+                    let parent = path
+                        .parent()
+                        .ok_or_else(|| anyhow!("Cannot get parent directory"))?;
+                    Ok(MediaSource::Directory(parent.to_path_buf()))
+                }
+            }
+            Some("jpg" | "jpeg" | "bmp" | "tif" | "tiff") => {
                 // Single image, treat as directory
                 let parent = path
                     .parent()
@@ -293,9 +1331,1001 @@ pub fn detect_media_type(path: &Path) -> Result<MediaSource> {
     }
 }
 
+/// Refuse implausibly large canvases reported by file metadata, which would
+/// otherwise trigger a huge allocation for every frame
+fn check_canvas_size(width: u32, height: u32, max_dimension: u32) -> Result<()> {
+    if width > max_dimension || height > max_dimension {
+        return Err(anyhow!(
+            "Canvas size {}x{} exceeds the maximum of {}x{}; the file may be corrupt \
+             (use --max-dimension to allow larger canvases)",
+            width,
+            height,
+            max_dimension,
+            max_dimension
+        ));
+    }
+    Ok(())
+}
+
+/// Convert a GIF frame delay, in hundredths of a second, to a duration
+fn gif_frame_delay(delay: u16) -> Duration {
+    authored_delay(Duration::from_millis(delay as u64 * 10))
+}
+
+/// Expand 8-bit PNG samples of any color type to RGBA
+fn png_samples_to_rgba(samples: &[u8], color_type: png::ColorType) -> Result<Vec<u8>> {
+    Ok(match color_type {
+        png::ColorType::Rgba => samples.to_vec(),
+        png::ColorType::Rgb => samples
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => samples
+            .chunks_exact(2)
+            .flat_map(|gray| [gray[0], gray[0], gray[0], gray[1]])
+            .collect(),
+        png::ColorType::Grayscale => samples
+            .iter()
+            .flat_map(|&gray| [gray, gray, gray, 255])
+            .collect(),
+        color_type => return Err(anyhow!("Unsupported PNG color type: {:?}", color_type)),
+    })
+}
+
+/// Composite straight-alpha `source` over `destination`, as APNG's `Over` blend op does
+fn blend_over(destination: Rgba<u8>, source: Rgba<u8>) -> Rgba<u8> {
+    let source_alpha = source[3] as u32;
+    match source_alpha {
+        255 => return source,
+        0 => return destination,
+        _ => {}
+    }
+
+    // Weights and alpha scaled by 255 * 255
+    let source_weight = source_alpha * 255;
+    let destination_weight = destination[3] as u32 * (255 - source_alpha);
+    let alpha = source_weight + destination_weight;
+
+    let mut blended = [0; 4];
+    for channel in 0..3 {
+        let weighted = source[channel] as u32 * source_weight
+            + destination[channel] as u32 * destination_weight;
+        blended[channel] = ((weighted + alpha / 2) / alpha) as u8;
+    }
+    blended[3] = ((alpha + 127) / 255) as u8;
+    Rgba(blended)
+}
+
+/// Convert an APNG frame delay fraction, in seconds, to a duration. A zero
+/// denominator means hundredths of a second, per the APNG spec.
+fn apng_frame_delay(numerator: u16, denominator: u16) -> Duration {
+    let denominator = if denominator == 0 { 100 } else { denominator };
+    authored_delay(Duration::from_secs_f64(
+        numerator as f64 / denominator as f64,
+    ))
+}
+
+/// Replace near-zero delays with [`DEFAULT_FRAME_DELAY`]
+fn authored_delay(delay: Duration) -> Duration {
+    if delay <= Duration::from_millis(10) {
+        DEFAULT_FRAME_DELAY
+    } else {
+        delay
+    }
+}
+
+/// Whether `image` is a single flat color. Fully transparent pixels match
+/// each other whatever color they carry.
+pub fn is_blank(image: &RgbaImage) -> bool {
+    let mut pixels = image.pixels();
+    let Some(first) = pixels.next() else {
+        return true;
+    };
+
+    pixels.all(|pixel| pixel == first || (pixel[3] == 0 && first[3] == 0))
+}
+
+/// Whether every pixel of `image` has full alpha
+pub fn is_opaque(image: &RgbaImage) -> bool {
+    image.pixels().all(|pixel| pixel[3] == 255)
+}
+
+/// Compare two strings treating runs of ASCII digits as numbers, so `frame2`
+/// sorts before `frame10`. Falls back to a plain comparison to break ties
+/// (e.g. `frame01` vs `frame1`), keeping the order total and deterministic.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(ca), Some(cb)) if ca.is_ascii_digit() && cb.is_ascii_digit() => {
+                let a_num = take_digits(&mut a_chars);
+                let b_num = take_digits(&mut b_chars);
+                let a_trimmed = a_num.trim_start_matches('0');
+                let b_trimmed = b_num.trim_start_matches('0');
+
+                let ordering = a_trimmed
+                    .len()
+                    .cmp(&b_trimmed.len())
+                    .then_with(|| a_trimmed.cmp(b_trimmed));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(ca), Some(cb)) => {
+                if ca != cb {
+                    return ca.cmp(&cb);
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.peek().copied().filter(char::is_ascii_digit) {
+        digits.push(c);
+        chars.next();
+    }
+    digits
+}
+
 fn is_apng(path: &Path) -> Result<bool> {
     let file = StdFile::open(path)?;
     let decoder = png::Decoder::new(file);
     let reader = decoder.read_info()?;
     Ok(reader.info().animation_control().is_some())
 }
+
+fn is_animated_webp(path: &Path) -> Result<bool> {
+    let decoder = WebPDecoder::new(BufReader::new(StdFile::open(path)?))?;
+    Ok(decoder.has_animation())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create an empty scratch directory under the system temp dir
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("anibuddy-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Write a 1x1 image, in the format given by `path`'s extension, whose red
+    /// channel encodes `marker`
+    fn write_marker_image(path: &Path, marker: u8) {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        RgbaImage::from_pixel(1, 1, Rgba([marker, 0, 0, 255]))
+            .save(path)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_chroma_key_antialiased_edge() {
+        // A green background fading into a red shape over one anti-aliased pixel
+        let row = [[0, 255, 0], [8, 247, 0], [128, 128, 0], [255, 0, 0]];
+        let image = RgbaImage::from_fn(4, 1, |x, _| {
+            let [r, g, b] = row[x as usize];
+            Rgba([r, g, b, 255])
+        });
+        let keyed = |tolerance| {
+            let mut image = image.clone();
+            ChromaKey {
+                color: [0, 255, 0],
+                tolerance,
+            }
+            .apply(&mut image);
+            image.pixels().map(|pixel| pixel.0[3]).collect::<Vec<_>>()
+        };
+
+        // An exact key leaves a fringe of nearly-green pixels
+        assert_eq!(keyed(0), [0, 255, 255, 255]);
+        assert_eq!(keyed(8), [0, 0, 255, 255]);
+        // The half-blended pixel only goes with a loose tolerance
+        assert_eq!(keyed(128), [0, 0, 0, 255]);
+
+        let mut keyed_image = image.clone();
+        ChromaKey {
+            color: [0, 255, 0],
+            tolerance: 0,
+        }
+        .apply(&mut keyed_image);
+        assert_eq!(*keyed_image.get_pixel(0, 0), Rgba([0, 0, 0, 0]));
+        assert_eq!(keyed_image.get_pixel(3, 0), image.get_pixel(3, 0));
+    }
+
+    #[test]
+    fn test_skip_blank_drops_interspersed_blank_frames() {
+        let dir = scratch_dir("skip-blank");
+        let content_frame = |marker: u8| {
+            let mut frame = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 0]));
+            frame.put_pixel(0, 0, Rgba([marker, 0, 0, 255]));
+            frame
+        };
+        content_frame(1).save(dir.join("frame1.png")).unwrap();
+        // Fully transparent, but with stray color values left in the RGB channels
+        let mut blank = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 0]));
+        blank.put_pixel(2, 2, Rgba([90, 10, 10, 0]));
+        blank.save(dir.join("frame2.png")).unwrap();
+        content_frame(3).save(dir.join("frame3.png")).unwrap();
+
+        let options = LoadOptions {
+            skip_blank: true,
+            ..LoadOptions::default()
+        };
+        let sequence = MediaSequence::load(MediaSource::Directory(dir.clone()), &options).unwrap();
+        assert_eq!(sequence.count(), 2);
+        assert_eq!(sequence.get_all_images()[1].get_pixel(0, 0)[0], 3);
+        assert_eq!(
+            sequence.paths(),
+            &[dir.join("frame1.png"), dir.join("frame3.png")]
+        );
+
+        let sequence =
+            MediaSequence::load(MediaSource::Directory(dir.clone()), &LoadOptions::default())
+                .unwrap();
+        assert_eq!(sequence.count(), 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_mismatched_frame_sizes() {
+        let dir = scratch_dir("mismatched-sizes");
+        RgbaImage::from_pixel(4, 2, Rgba([255, 0, 0, 255]))
+            .save(dir.join("frame1.png"))
+            .unwrap();
+        RgbaImage::from_pixel(2, 4, Rgba([0, 255, 0, 255]))
+            .save(dir.join("frame2.png"))
+            .unwrap();
+
+        let source = || MediaSource::Directory(dir.clone());
+        let error = MediaSequence::load(source(), &LoadOptions::default())
+            .err()
+            .expect("mismatched frames should fail");
+        let message = error.to_string();
+        assert!(message.contains("frame2.png"), "{}", message);
+        assert!(message.contains("2x4"), "{}", message);
+
+        let options = LoadOptions {
+            mismatched_frames: MismatchedFrames::Pad,
+            ..LoadOptions::default()
+        };
+        let sequence = MediaSequence::load(source(), &options).unwrap();
+        let frames = sequence.get_all_images();
+        assert!(frames.iter().all(|frame| frame.dimensions() == (4, 4)));
+        // Centered, with transparent bars around
+        assert_eq!(frames[0].get_pixel(0, 0), &Rgba([0, 0, 0, 0]));
+        assert_eq!(frames[0].get_pixel(3, 1), &Rgba([255, 0, 0, 255]));
+        assert_eq!(frames[1].get_pixel(0, 3), &Rgba([0, 0, 0, 0]));
+        assert_eq!(frames[1].get_pixel(1, 3), &Rgba([0, 255, 0, 255]));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_trim_to_frame_range() {
+        let dir = scratch_dir("trim");
+        for marker in 0..5 {
+            write_marker_image(&dir.join(format!("frame{}.png", marker)), marker);
+        }
+        let load = || {
+            MediaSequence::load(MediaSource::Directory(dir.clone()), &LoadOptions::default())
+                .unwrap()
+        };
+        let markers = |sequence: &MediaSequence| -> Vec<u8> {
+            sequence
+                .get_all_images()
+                .iter()
+                .map(|image| image.get_pixel(0, 0)[0])
+                .collect()
+        };
+
+        let mut sequence = load();
+        sequence.trim("1:3".parse().unwrap()).unwrap();
+        assert_eq!(markers(&sequence), vec![1, 2]);
+        assert_eq!(
+            sequence.paths(),
+            &[dir.join("frame1.png"), dir.join("frame2.png")]
+        );
+
+        // Out-of-bounds and negative bounds clamp to the sequence
+        let mut sequence = load();
+        sequence.trim("-2:99".parse().unwrap()).unwrap();
+        assert_eq!(sequence.count(), 5);
+
+        let mut sequence = load();
+        sequence.trim("3:".parse().unwrap()).unwrap();
+        assert_eq!(markers(&sequence), vec![3, 4]);
+
+        assert!(load().trim("3:3".parse().unwrap()).is_err());
+        assert!(load().trim("7:9".parse().unwrap()).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_frame_range() {
+        assert_eq!(
+            "10:50".parse(),
+            Ok(FrameRange {
+                start: Some(10),
+                end: Some(50)
+            })
+        );
+        assert_eq!(
+            ":-1".parse(),
+            Ok(FrameRange {
+                start: None,
+                end: Some(-1)
+            })
+        );
+        assert_eq!(":".parse::<FrameRange>().unwrap().to_string(), ":");
+        assert_eq!("5:".parse::<FrameRange>().unwrap().to_string(), "5:");
+        assert!("5".parse::<FrameRange>().is_err());
+        assert!("a:b".parse::<FrameRange>().is_err());
+    }
+
+    #[test]
+    fn test_hold_first_and_last_frames() {
+        let dir = scratch_dir("hold-frames");
+        for marker in 1..=3 {
+            write_marker_image(&dir.join(format!("frame{}.png", marker)), marker);
+        }
+
+        let options = LoadOptions {
+            hold_first: 2,
+            hold_last: 1,
+            ..LoadOptions::default()
+        };
+        let sequence = MediaSequence::load(MediaSource::Directory(dir.clone()), &options).unwrap();
+        let markers: Vec<u8> = sequence
+            .get_all_images()
+            .iter()
+            .map(|image| image.get_pixel(0, 0)[0])
+            .collect();
+        assert_eq!(markers, [1, 1, 1, 2, 3, 3]);
+        assert_eq!(sequence.paths().len(), 6);
+        assert_eq!(sequence.paths()[2], dir.join("frame1.png"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_is_opaque() {
+        let mut image = RgbaImage::from_pixel(2, 2, image::Rgba([10, 20, 30, 255]));
+        assert!(is_opaque(&image));
+
+        image.put_pixel(1, 1, image::Rgba([10, 20, 30, 254]));
+        assert!(!is_opaque(&image));
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec![
+            "frame10", "frame2", "frame1", "frame02", "a/frame3", "b/frame1",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            vec![
+                "a/frame3", "b/frame1", "frame1", "frame02", "frame2", "frame10"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_glob_loads_subdirectories_in_natural_order() {
+        let dir = scratch_dir("glob");
+        write_marker_image(&dir.join("part1/frame10.png"), 3);
+        write_marker_image(&dir.join("part1/frame2.png"), 2);
+        write_marker_image(&dir.join("part1/frame1.png"), 1);
+        write_marker_image(&dir.join("part2/frame1.png"), 4);
+        std::fs::write(dir.join("notes.txt"), "not a frame").unwrap();
+
+        let options = LoadOptions {
+            glob: Some("**/*.png".to_string()),
+            ..LoadOptions::default()
+        };
+        for _ in 0..2 {
+            let sequence = MediaSequence::load(MediaSource::Directory(dir.clone()), &options)
+                .expect("glob load should succeed");
+            let markers: Vec<u8> = sequence
+                .get_all_images()
+                .iter()
+                .map(|img| img.get_pixel(0, 0)[0])
+                .collect();
+            assert_eq!(markers, vec![1, 2, 3, 4]);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_directory_loads_in_natural_order() {
+        let dir = scratch_dir("natural");
+        write_marker_image(&dir.join("frame10.png"), 10);
+        write_marker_image(&dir.join("frame2.png"), 2);
+        write_marker_image(&dir.join("frame1.png"), 1);
+        write_marker_image(&dir.join("frame12.png"), 12);
+
+        let sequence =
+            MediaSequence::load(MediaSource::Directory(dir.clone()), &LoadOptions::default())
+                .expect("directory load should succeed");
+        let markers: Vec<u8> = sequence
+            .get_all_images()
+            .iter()
+            .map(|img| img.get_pixel(0, 0)[0])
+            .collect();
+        assert_eq!(markers, vec![1, 2, 10, 12]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_directory_mixes_formats_and_skips_corrupt_files() {
+        let dir = scratch_dir("formats");
+        write_marker_image(&dir.join("frame1.bmp"), 1);
+        write_marker_image(&dir.join("frame2.TIF"), 2);
+        write_marker_image(&dir.join("frame10.png"), 10);
+        write_marker_image(&dir.join("frame3.tiff"), 3);
+        std::fs::write(dir.join("frame4.png"), b"not a png").unwrap();
+
+        let markers = |options: &LoadOptions| -> Vec<u8> {
+            MediaSequence::load(MediaSource::Directory(dir.clone()), options)
+                .expect("directory load should succeed")
+                .get_all_images()
+                .iter()
+                .map(|img| img.get_pixel(0, 0)[0])
+                .collect()
+        };
+        assert_eq!(markers(&LoadOptions::default()), vec![1, 2, 3, 10]);
+
+        let png_only = LoadOptions {
+            formats: vec!["png".to_string()],
+            ..LoadOptions::default()
+        };
+        assert_eq!(markers(&png_only), vec![10]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_frame_stream_keeps_a_bounded_window() {
+        let dir = scratch_dir("stream");
+        let paths: Vec<PathBuf> = (0..10)
+            .map(|i| {
+                let path = dir.join(format!("frame{}.png", i));
+                write_marker_image(&path, i as u8);
+                path
+            })
+            .collect();
+        std::fs::write(&paths[4], b"not a png").unwrap();
+
+        let stream = FrameStream::new(paths, 3, None).unwrap();
+        assert_eq!(stream.count(), 10);
+        assert_eq!(stream.dimensions(), (1, 1));
+
+        let marker = |index: usize| stream.frame(index).get_pixel(0, 0)[0];
+        for index in [0, 1, 2, 3, 5, 6, 7, 8, 9, 0, 1] {
+            assert_eq!(marker(index), index as u8);
+            assert!(stream.resident() <= 3);
+        }
+
+        // Jumping around waits for the decoder instead of failing
+        assert_eq!(marker(7), 7);
+        assert_eq!(marker(2), 2);
+
+        // An unreadable file shows as a transparent frame
+        assert_eq!(*stream.frame(4).get_pixel(0, 0), Rgba([0, 0, 0, 0]));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sprite_sheet_slices_row_major() {
+        let dir = scratch_dir("sheet");
+        let path = dir.join("sheet.png");
+        // 2x2 grid of 3x2 cells, each filled with its cell number
+        RgbaImage::from_fn(6, 4, |x, y| Rgba([(y / 2 * 2 + x / 3) as u8, 0, 0, 255]))
+            .save(&path)
+            .unwrap();
+
+        let markers = |layout: &str| -> Vec<u8> {
+            let source = sprite_sheet_source(&path, layout.parse().unwrap()).unwrap();
+            let sequence = MediaSequence::load(source, &LoadOptions::default()).unwrap();
+            sequence
+                .get_all_images()
+                .iter()
+                .map(|frame| {
+                    assert_eq!(frame.dimensions(), (3, 2));
+                    let marker = frame.get_pixel(0, 0)[0];
+                    assert!(frame.pixels().all(|pixel| pixel[0] == marker));
+                    marker
+                })
+                .collect()
+        };
+        assert_eq!(markers("2,2"), vec![0, 1, 2, 3]);
+        // A partly filled last row
+        assert_eq!(markers("2,2,3"), vec![0, 1, 2]);
+
+        assert!("2,2,5".parse::<SheetLayout>().is_err());
+        assert!("0,2".parse::<SheetLayout>().is_err());
+        assert!("2".parse::<SheetLayout>().is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_embedded_animation_loads() {
+        let sequence = MediaSequence::load(MediaSource::Embedded, &LoadOptions::default()).unwrap();
+        assert!(sequence.count() > 1);
+        assert_eq!(sequence.current_image().unwrap().dimensions(), (48, 48));
+        assert!(sequence.delay(0).is_some());
+        assert!(!sequence.is_opaque());
+    }
+
+    #[test]
+    fn test_recursive_directory_load() {
+        let dir = scratch_dir("recursive");
+        write_marker_image(&dir.join("frame1.png"), 1);
+        write_marker_image(&dir.join("part/frame10.png"), 3);
+        write_marker_image(&dir.join("part/frame2.png"), 2);
+        write_marker_image(&dir.join("part/deeper/frame1.png"), 4);
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&dir, dir.join("part/loop")).unwrap();
+
+        let flat =
+            MediaSequence::load(MediaSource::Directory(dir.clone()), &LoadOptions::default())
+                .expect("flat load should succeed");
+        assert_eq!(flat.get_all_images().len(), 1);
+
+        let options = LoadOptions {
+            recursive: true,
+            ..LoadOptions::default()
+        };
+        let sequence = MediaSequence::load(MediaSource::Directory(dir.clone()), &options)
+            .expect("recursive load should succeed");
+        let markers: Vec<u8> = sequence
+            .get_all_images()
+            .iter()
+            .map(|img| img.get_pixel(0, 0)[0])
+            .collect();
+        assert_eq!(markers, vec![1, 4, 2, 3]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parallel_decode_keeps_order() {
+        let dir = scratch_dir("parallel");
+        let paths: Vec<PathBuf> = (0..32)
+            .map(|i| {
+                let path = dir.join(format!("frame{}.png", i));
+                write_marker_image(&path, i as u8);
+                path
+            })
+            .collect();
+
+        let serial: Vec<RgbaImage> = paths
+            .iter()
+            .map(|path| image::open(path).unwrap().to_rgba8())
+            .collect();
+        let (parallel, decoded_paths) = MediaSequence::decode_images(paths.clone()).unwrap();
+        assert_eq!(parallel, serial);
+        assert_eq!(decoded_paths, paths);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_directory_watcher_appends_completed_frames() {
+        let dir = scratch_dir("watch");
+        write_marker_image(&dir.join("frame1.png"), 1);
+
+        let mut watcher = DirectoryWatcher::new(&dir, &LoadOptions::default().formats).unwrap();
+        watcher.mark_known(&[dir.join("frame1.png")]);
+
+        // A frame that is still being written must not block forever or be skipped
+        std::fs::write(dir.join("frame3.png"), b"\x89PNG").unwrap();
+        write_marker_image(&dir.join("frame2.png"), 2);
+
+        let poll_until = |watcher: &mut DirectoryWatcher, count: usize| {
+            let mut frames = Vec::new();
+            for _ in 0..100 {
+                frames.extend(watcher.poll_new_frames());
+                if frames.len() >= count {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            frames
+        };
+
+        let frames = poll_until(&mut watcher, 1);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].0, dir.join("frame2.png"));
+
+        write_marker_image(&dir.join("frame3.png"), 3);
+        let frames = poll_until(&mut watcher, 1);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].1.get_pixel(0, 0)[0], 3);
+
+        // A file that stays broken past the timeout is dropped until it's written again
+        watcher.decode_timeout = std::time::Duration::from_millis(50);
+        std::fs::write(dir.join("frame4.png"), b"\x89PNG").unwrap();
+        for _ in 0..100 {
+            assert!(watcher.poll_new_frames().is_empty());
+            if watcher.given_up.contains(&dir.join("frame4.png")) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert!(watcher.pending.is_empty());
+
+        write_marker_image(&dir.join("frame4.png"), 4);
+        let frames = poll_until(&mut watcher, 1);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].1.get_pixel(0, 0)[0], 4);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Write indexed GIF frames under an otherwise empty global palette
+    fn write_indexed_gif(path: &Path, width: u16, height: u16, frames: Vec<gif::Frame<'static>>) {
+        let file = StdFile::create(path).unwrap();
+        let mut encoder = gif::Encoder::new(file, width, height, &[0, 0, 0]).unwrap();
+        for frame in frames {
+            encoder.write_frame(&frame).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_interlaced_gif_rows_come_out_in_order() {
+        let dir = scratch_dir("gif-interlaced");
+        let path = dir.join("interlaced.gif");
+
+        // One color per row; the encoder writes rows as given, so hand it the
+        // interlaced order 0, 4, 2, 6, 1, 3, 5, 7
+        let palette: Vec<u8> = (0..8u8).flat_map(|row| [row * 30, 0, 0]).collect();
+        let buffer: Vec<u8> = [0u8, 4, 2, 6, 1, 3, 5, 7]
+            .iter()
+            .flat_map(|&row| [row; 2])
+            .collect();
+        write_indexed_gif(
+            &path,
+            2,
+            8,
+            vec![gif::Frame {
+                width: 2,
+                height: 8,
+                interlaced: true,
+                palette: Some(palette),
+                buffer: buffer.into(),
+                ..gif::Frame::default()
+            }],
+        );
+
+        let (images, _) = MediaSequence::load_gif(&path, DEFAULT_MAX_DIMENSION).unwrap();
+        for row in 0..8u32 {
+            assert_eq!(
+                images[0].get_pixel(1, row),
+                &Rgba([row as u8 * 30, 0, 0, 255])
+            );
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_gif_local_palette_transparency_keeps_canvas() {
+        let dir = scratch_dir("gif-local-palette");
+        let path = dir.join("local.gif");
+
+        write_indexed_gif(
+            &path,
+            3,
+            1,
+            vec![
+                gif::Frame {
+                    width: 3,
+                    height: 1,
+                    dispose: gif::DisposalMethod::Keep,
+                    palette: Some(vec![255, 0, 0, 0, 255, 0]),
+                    buffer: vec![0, 1, 0].into(),
+                    ..gif::Frame::default()
+                },
+                // Local palette where index 1 is transparent: only the middle pixel changes
+                gif::Frame {
+                    width: 3,
+                    height: 1,
+                    dispose: gif::DisposalMethod::Previous,
+                    palette: Some(vec![0, 0, 255, 9, 9, 9]),
+                    transparent: Some(1),
+                    buffer: vec![1, 0, 1].into(),
+                    ..gif::Frame::default()
+                },
+                // Drawn after frame 1 was disposed back to frame 0's canvas
+                gif::Frame {
+                    width: 1,
+                    height: 1,
+                    left: 2,
+                    palette: Some(vec![255, 255, 255, 0, 0, 0]),
+                    transparent: Some(1),
+                    buffer: vec![0].into(),
+                    ..gif::Frame::default()
+                },
+            ],
+        );
+
+        let (images, _) = MediaSequence::load_gif(&path, DEFAULT_MAX_DIMENSION).unwrap();
+        let red = Rgba([255, 0, 0, 255]);
+        let green = Rgba([0, 255, 0, 255]);
+        let blue = Rgba([0, 0, 255, 255]);
+        let white = Rgba([255, 255, 255, 255]);
+        assert_eq!(
+            images[0].pixels().copied().collect::<Vec<_>>(),
+            [red, green, red]
+        );
+        assert_eq!(
+            images[1].pixels().copied().collect::<Vec<_>>(),
+            [red, blue, red]
+        );
+        assert_eq!(
+            images[2].pixels().copied().collect::<Vec<_>>(),
+            [red, green, white]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_gif_frame_delays() {
+        let dir = scratch_dir("gif-delays");
+        let path = dir.join("delays.gif");
+
+        let frames = [5, 150, 0]
+            .into_iter()
+            .map(|delay| gif::Frame {
+                width: 1,
+                height: 1,
+                delay,
+                palette: Some(vec![255, 0, 0]),
+                buffer: vec![0].into(),
+                ..gif::Frame::default()
+            })
+            .collect();
+        write_indexed_gif(&path, 1, 1, frames);
+
+        let sequence =
+            MediaSequence::load(MediaSource::GifFile(path), &LoadOptions::default()).unwrap();
+        assert_eq!(sequence.delay(0), Some(Duration::from_millis(50)));
+        assert_eq!(sequence.delay(1), Some(Duration::from_millis(1500)));
+        assert_eq!(sequence.delay(2), Some(DEFAULT_FRAME_DELAY));
+        assert_eq!(sequence.delay(3), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_apng_frame_delays() {
+        let dir = scratch_dir("apng-delays");
+        let path = dir.join("delays.png");
+
+        let file = StdFile::create(&path).unwrap();
+        let mut encoder = png::Encoder::new(file, 1, 1);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_animated(3, 0).unwrap();
+        let mut writer = encoder.write_header().unwrap();
+        for (numerator, denominator) in [(1, 4), (150, 0), (0, 10)] {
+            writer.set_frame_delay(numerator, denominator).unwrap();
+            writer.write_image_data(&[255, 0, 0, 255]).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let sequence =
+            MediaSequence::load(MediaSource::ApngFile(path), &LoadOptions::default()).unwrap();
+        assert_eq!(sequence.count(), 3);
+        assert_eq!(sequence.delay(0), Some(Duration::from_millis(250)));
+        assert_eq!(sequence.delay(1), Some(Duration::from_millis(1500)));
+        assert_eq!(sequence.delay(2), Some(DEFAULT_FRAME_DELAY));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_apng_compositing() {
+        let dir = scratch_dir("apng-compositing");
+        let path = dir.join("moving.png");
+
+        let blue = [0, 0, 255, 255];
+        let red = [255, 0, 0, 255];
+        let white = [255, 255, 255, 128];
+        let green = [0, 255, 0, 255];
+
+        // A full blue background, then 2x2 sub-rects moving across it
+        let file = StdFile::create(&path).unwrap();
+        let mut encoder = png::Encoder::new(file, 8, 4);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_animated(4, 0).unwrap();
+        let mut writer = encoder.write_header().unwrap();
+        let frames = [
+            (
+                (8, 4),
+                (0, 0),
+                blue,
+                png::BlendOp::Source,
+                png::DisposeOp::None,
+            ),
+            (
+                (2, 2),
+                (1, 1),
+                red,
+                png::BlendOp::Source,
+                png::DisposeOp::Background,
+            ),
+            (
+                (2, 2),
+                (4, 1),
+                white,
+                png::BlendOp::Over,
+                png::DisposeOp::Previous,
+            ),
+            (
+                (2, 2),
+                (5, 2),
+                green,
+                png::BlendOp::Source,
+                png::DisposeOp::None,
+            ),
+        ];
+        for ((width, height), (x, y), color, blend, dispose) in frames {
+            writer.set_frame_dimension(width, height).unwrap();
+            writer.set_frame_position(x, y).unwrap();
+            writer.set_blend_op(blend).unwrap();
+            writer.set_dispose_op(dispose).unwrap();
+            writer
+                .write_image_data(&color.repeat((width * height) as usize))
+                .unwrap();
+        }
+        writer.finish().unwrap();
+
+        let sequence =
+            MediaSequence::load(MediaSource::ApngFile(path), &LoadOptions::default()).unwrap();
+        let frames = sequence.get_all_images();
+        assert_eq!(frames.len(), 4);
+        assert!(frames.iter().all(|frame| frame.dimensions() == (8, 4)));
+
+        assert_eq!(frames[0].get_pixel(2, 2), &Rgba(blue));
+        assert_eq!(frames[1].get_pixel(2, 2), &Rgba(red));
+        assert_eq!(frames[1].get_pixel(3, 2), &Rgba(blue));
+
+        // The red rect was disposed to transparent, and white blends over blue
+        assert_eq!(frames[2].get_pixel(2, 2), &Rgba([0, 0, 0, 0]));
+        assert_eq!(frames[2].get_pixel(4, 1), &Rgba([128, 128, 255, 255]));
+
+        // The white rect was disposed back to the canvas before it
+        assert_eq!(frames[3].get_pixel(4, 1), &Rgba(blue));
+        assert_eq!(frames[3].get_pixel(5, 2), &Rgba(green));
+        assert_eq!(frames[3].get_pixel(1, 1), &Rgba([0, 0, 0, 0]));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Append a RIFF chunk, padded to an even length
+    fn push_chunk(out: &mut Vec<u8>, fourcc: &[u8; 4], data: &[u8]) {
+        out.extend_from_slice(fourcc);
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            out.push(0);
+        }
+    }
+
+    /// Write an animated WebP of full-canvas lossless frames, each shown for
+    /// its paired number of milliseconds. The encoder only writes still
+    /// images, so their VP8L chunks are wrapped in ANMF chunks by hand.
+    fn write_animated_webp(path: &Path, frames: &[(RgbaImage, u32)]) {
+        let (width, height) = frames[0].0.dimensions();
+        let u24 = |value: u32| value.to_le_bytes()[..3].to_vec();
+
+        let mut body = b"WEBP".to_vec();
+        // Alpha and animation flags, then the canvas size minus one
+        let mut vp8x = vec![0x12, 0, 0, 0];
+        vp8x.extend(u24(width - 1));
+        vp8x.extend(u24(height - 1));
+        push_chunk(&mut body, b"VP8X", &vp8x);
+        // Background color and loop forever
+        push_chunk(&mut body, b"ANIM", &[0, 0, 0, 0, 0, 0]);
+
+        for (image, duration_ms) in frames {
+            let mut still = Vec::new();
+            image::codecs::webp::WebPEncoder::new_lossless(&mut still)
+                .encode(image, width, height, image::ExtendedColorType::Rgba8)
+                .unwrap();
+            let vp8l_start = still.windows(4).position(|w| w == b"VP8L").unwrap();
+
+            let mut anmf = [u24(0), u24(0), u24(width - 1), u24(height - 1)].concat();
+            anmf.extend(u24(*duration_ms));
+            // No blending, so each frame replaces the canvas
+            anmf.push(0b10);
+            anmf.extend_from_slice(&still[vp8l_start..]);
+            push_chunk(&mut body, b"ANMF", &anmf);
+        }
+
+        let mut file = b"RIFF".to_vec();
+        file.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        file.extend(body);
+        std::fs::write(path, file).unwrap();
+    }
+
+    #[test]
+    fn test_animated_webp_frames() {
+        let dir = scratch_dir("webp-animated");
+        let path = dir.join("wave.webp");
+
+        let red = Rgba([255, 0, 0, 255]);
+        let clear_blue = Rgba([0, 0, 255, 128]);
+        write_animated_webp(
+            &path,
+            &[
+                (RgbaImage::from_pixel(4, 2, red), 250),
+                (RgbaImage::from_pixel(4, 2, clear_blue), 40),
+            ],
+        );
+
+        let source = detect_media_type(&path).unwrap();
+        assert!(matches!(source, MediaSource::WebpFile(_)));
+
+        let sequence = MediaSequence::load(source, &LoadOptions::default()).unwrap();
+        assert_eq!(sequence.count(), 2);
+        assert_eq!(sequence.get_all_images()[0].get_pixel(3, 1), &red);
+        assert_eq!(sequence.get_all_images()[1].get_pixel(0, 0), &clear_blue);
+        assert_eq!(sequence.delay(0), Some(Duration::from_millis(250)));
+        assert_eq!(sequence.delay(1), Some(Duration::from_millis(40)));
+
+        // A still WebP loads like any other single image
+        let still = dir.join("still").join("frame.webp");
+        std::fs::create_dir_all(still.parent().unwrap()).unwrap();
+        RgbaImage::from_pixel(2, 2, red).save(&still).unwrap();
+        assert!(matches!(
+            detect_media_type(&still).unwrap(),
+            MediaSource::Directory(_)
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_oversized_gif_canvas_is_rejected() {
+        let dir = scratch_dir("oversized-gif");
+        let path = dir.join("huge.gif");
+
+        // A tiny frame on a logical screen claiming to be 60000x60000
+        {
+            let file = StdFile::create(&path).unwrap();
+            let mut encoder = gif::Encoder::new(file, 60000, 60000, &[]).unwrap();
+            let mut pixels = vec![255, 0, 0, 255];
+            let frame = gif::Frame::from_rgba(1, 1, &mut pixels);
+            encoder.write_frame(&frame).unwrap();
+        }
+
+        let error = MediaSequence::load(MediaSource::GifFile(path), &LoadOptions::default())
+            .err()
+            .expect("oversized canvas should be rejected");
+        assert!(error.to_string().contains("60000x60000"), "{}", error);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
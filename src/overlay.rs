@@ -1,40 +1,603 @@
 use anyhow::Result;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use winit::application::ApplicationHandler;
-use winit::dpi::PhysicalSize;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{ElementState, MouseButton};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
-use winit::window::{Window, WindowAttributes, WindowId};
+use winit::keyboard::Key;
+use winit::monitor::MonitorHandle;
+use winit::window::{Fullscreen, Window, WindowAttributes, WindowId, WindowLevel};
 
-use crate::media_loader::{MediaSequence, MediaSource};
-use crate::renderer::Renderer;
+use crate::config::{Config, ConfigWatcher, save_preset_value};
+use crate::delta_compression::CompressionOptions;
+use crate::media_loader::{
+    DirectoryWatcher, FrameRange, FrameStream, LoadOptions, MediaSequence, MediaSource,
+    detect_media_type, is_opaque,
+};
+use crate::renderer::{AdapterOptions, BlendMode, ImageTransform, PresentMode, Renderer};
+use crate::stats_overlay::{FpsCounter, render_text};
+use crate::timing_trace::TimingTrace;
+
+/// Curve applied to the playback position within each loop.
+///
+/// Frames keep their order, but the loop's wall-clock length is spread over
+/// them unevenly so the animation speeds up and slows down cyclically.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Easing {
+    /// Every frame shows for the same time
+    #[default]
+    Linear,
+    /// Start slow and speed up towards the end of the loop
+    EaseIn,
+    /// Start fast and slow down towards the end of the loop
+    EaseOut,
+    /// Slow at both ends of the loop, fastest in the middle
+    EaseInOut,
+}
+
+impl Easing {
+    /// Map loop phase `t` in `[0, 1)` to a playback position in `[0, 1)`
+    pub fn apply(self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - 2.0 * (1.0 - t) * (1.0 - t)
+                }
+            }
+        }
+    }
+
+    /// Frame to show `elapsed` into playback of `frame_count` frames whose
+    /// loop lasts `frame_count * frame_interval`
+    pub fn frame_at(
+        self,
+        elapsed: Duration,
+        frame_interval: Duration,
+        frame_count: usize,
+    ) -> usize {
+        let loop_length = frame_interval.as_secs_f64() * frame_count as f64;
+        if loop_length <= 0.0 {
+            return 0;
+        }
+
+        let phase = (elapsed.as_secs_f64() / loop_length).fract();
+        let index = (self.apply(phase) * frame_count as f64) as usize;
+        index.min(frame_count - 1)
+    }
+}
+
+/// Slowest and fastest playback speed multipliers
+pub const SPEED_RANGE: std::ops::RangeInclusive<f64> = 0.05..=20.0;
+
+/// Slowest frame rate accepted, about one frame every 17 minutes. Anything
+/// slower is almost certainly a typo and would overflow frame timing.
+pub const MIN_FPS: f64 = 0.001;
+
+/// Factor each `+`/`-` key press changes the playback speed by
+const SPEED_STEP: f64 = 1.25;
+
+/// Keys with a built-in playback action, which a reaction bound to them replaces
+pub const PLAYBACK_KEYS: [&str; 8] = ["space", "arrowleft", "arrowright", "+", "=", "-", "t", "s"];
+
+/// Keys `--debug` takes over, even when a reaction is bound to them
+pub const DEBUG_KEYS: [&str; 3] = ["p", "m", "c"];
+
+/// Order frames are visited in within each loop.
+///
+/// Deltas only run forwards, so compressed sequences rebuild every backward
+/// step from the nearest keyframe (see `--keyframe-interval`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PlaybackMode {
+    #[default]
+    Forward,
+    /// Last frame to first
+    Reverse,
+    /// First frame to last and back, without repeating either end
+    PingPong,
+}
+
+impl PlaybackMode {
+    /// Playback positions in one loop over `frame_count` frames
+    pub fn loop_length(self, frame_count: usize) -> usize {
+        match self {
+            PlaybackMode::PingPong if frame_count > 2 => 2 * frame_count - 2,
+            _ => frame_count,
+        }
+    }
+
+    /// Frame shown at `position` within the loop. Position 0 is always frame 0,
+    /// so switching modes never jumps away from the frame on screen at startup.
+    pub fn frame_at(self, position: usize, frame_count: usize) -> usize {
+        match self {
+            PlaybackMode::Forward => position,
+            PlaybackMode::Reverse => (frame_count - position) % frame_count,
+            PlaybackMode::PingPong if position < frame_count => position,
+            PlaybackMode::PingPong => 2 * frame_count - 2 - position,
+        }
+    }
+}
+
+/// Where to put the window on screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowPosition {
+    /// Top-left corner at these desktop coordinates
+    At(i32, i32),
+    /// Against a corner, or centered, on the primary monitor
+    Anchor(Anchor),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Center,
+}
+
+impl FromStr for WindowPosition {
+    type Err = String;
+
+    /// Parse `x,y` or one of `top-left`, `top-right`, `bottom-left`,
+    /// `bottom-right` and `center`
+    fn from_str(input: &str) -> std::result::Result<Self, String> {
+        let anchor = match input.trim() {
+            "top-left" => Anchor::TopLeft,
+            "top-right" => Anchor::TopRight,
+            "bottom-left" => Anchor::BottomLeft,
+            "bottom-right" => Anchor::BottomRight,
+            "center" => Anchor::Center,
+            other => {
+                let invalid = || {
+                    format!(
+                        "'{}' is neither x,y nor one of top-left, top-right, \
+                         bottom-left, bottom-right, center",
+                        input
+                    )
+                };
+                let (x, y) = other.split_once(',').ok_or_else(invalid)?;
+                let x = x.trim().parse().map_err(|_| invalid())?;
+                let y = y.trim().parse().map_err(|_| invalid())?;
+                return Ok(WindowPosition::At(x, y));
+            }
+        };
+        Ok(WindowPosition::Anchor(anchor))
+    }
+}
+
+/// Which monitor to show the window on
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(untagged)]
+pub enum MonitorSelector {
+    /// Position in the list of available monitors, counting from 0
+    Index(usize),
+    /// Name the platform reports for the monitor, such as `DP-1`
+    Name(String),
+}
+
+impl FromStr for MonitorSelector {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, String> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err("monitor can't be empty".to_string());
+        }
+        Ok(input.parse().map_or_else(
+            |_| MonitorSelector::Name(input.to_string()),
+            MonitorSelector::Index,
+        ))
+    }
+}
+
+impl MonitorSelector {
+    /// Position of the selected monitor among `names`, listed in enumeration order
+    pub fn find(&self, names: &[Option<String>]) -> Option<usize> {
+        match self {
+            MonitorSelector::Index(index) => (*index < names.len()).then_some(*index),
+            MonitorSelector::Name(name) => names
+                .iter()
+                .position(|candidate| candidate.as_deref() == Some(name.as_str())),
+        }
+    }
+}
+
+/// A monitor's bounds in desktop coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonitorRect {
+    pub position: PhysicalPosition<i32>,
+    pub size: PhysicalSize<u32>,
+}
+
+impl MonitorRect {
+    fn overlaps(&self, position: PhysicalPosition<i32>, size: PhysicalSize<u32>) -> bool {
+        position.x < self.position.x + self.size.width as i32
+            && position.x + size.width as i32 > self.position.x
+            && position.y < self.position.y + self.size.height as i32
+            && position.y + size.height as i32 > self.position.y
+    }
+
+    /// Squared distance from `point` to the nearest point of the monitor
+    fn distance_squared(&self, point: (i64, i64)) -> i64 {
+        let clamp = |value: i64, start: i32, length: u32| {
+            value.clamp(start as i64, start as i64 + length as i64)
+        };
+        let dx = point.0 - clamp(point.0, self.position.x, self.size.width);
+        let dy = point.1 - clamp(point.1, self.position.y, self.size.height);
+        dx * dx + dy * dy
+    }
+
+    /// Move a window of `size` at `position` as little as possible to lie
+    /// within the monitor, or against its top-left if it's too big to fit
+    fn clamp(
+        &self,
+        position: PhysicalPosition<i32>,
+        size: PhysicalSize<u32>,
+    ) -> PhysicalPosition<i32> {
+        let axis = |value: i32, start: i32, monitor: u32, window: u32| {
+            value.min(start + monitor as i32 - window as i32).max(start)
+        };
+        PhysicalPosition::new(
+            axis(position.x, self.position.x, self.size.width, size.width),
+            axis(position.y, self.position.y, self.size.height, size.height),
+        )
+    }
+}
+
+/// Top-left corner for a window of `window_size` placed at `position`.
+/// `monitors` lists every monitor with the primary one first; anchors need at
+/// least one. Positions that would leave the window on no monitor at all are
+/// moved onto the nearest one, which the returned flag reports.
+pub fn place_window(
+    position: WindowPosition,
+    window_size: PhysicalSize<u32>,
+    monitors: &[MonitorRect],
+) -> Option<(PhysicalPosition<i32>, bool)> {
+    match position {
+        WindowPosition::Anchor(anchor) => {
+            let monitor = monitors.first()?;
+            let right = monitor.size.width as i32 - window_size.width as i32;
+            let bottom = monitor.size.height as i32 - window_size.height as i32;
+            let (x, y) = match anchor {
+                Anchor::TopLeft => (0, 0),
+                Anchor::TopRight => (right, 0),
+                Anchor::BottomLeft => (0, bottom),
+                Anchor::BottomRight => (right, bottom),
+                Anchor::Center => (right / 2, bottom / 2),
+            };
+            Some((
+                PhysicalPosition::new(monitor.position.x + x.max(0), monitor.position.y + y.max(0)),
+                false,
+            ))
+        }
+        WindowPosition::At(x, y) => {
+            let requested = PhysicalPosition::new(x, y);
+            if monitors.is_empty()
+                || monitors
+                    .iter()
+                    .any(|monitor| monitor.overlaps(requested, window_size))
+            {
+                return Some((requested, false));
+            }
+
+            let center = (
+                x as i64 + window_size.width as i64 / 2,
+                y as i64 + window_size.height as i64 / 2,
+            );
+            let nearest = monitors
+                .iter()
+                .min_by_key(|monitor| monitor.distance_squared(center))?;
+            Some((nearest.clamp(requested, window_size), true))
+        }
+    }
+}
+
+/// A preset played once in response to a key press
+pub struct Reaction {
+    pub source: MediaSource,
+    pub frame_interval: Duration,
+}
+
+/// A reaction's frames, loaded up front so triggering it is instant
+struct LoadedReaction {
+    sequence: MediaSequence,
+    frame_interval: Duration,
+}
+
+/// What to restore once the playing reaction finishes
+struct ActiveReaction {
+    key: String,
+    idle_sequence: Option<MediaSequence>,
+    idle_frame_interval: Duration,
+    idle_frame_index: usize,
+    idle_play_position: usize,
+}
+
+/// Preset re-applied whenever the config file changes, with `--watch`
+pub struct ConfigWatch {
+    pub watcher: ConfigWatcher,
+    pub preset: String,
+    /// The preset's media path, to tell when the frames must be reloaded
+    pub path: String,
+    /// Settings given on the command line, which keep precedence over the preset
+    pub fps_overridden: bool,
+    pub opacity_overridden: bool,
+    pub position_overridden: bool,
+}
+
+/// Where a dragged window's position is written, with `--save-position`
+pub struct PositionSave {
+    pub config_path: PathBuf,
+    pub preset: String,
+    /// Save coordinates relative to the window's monitor, which is how a
+    /// preset with `monitor` reads its `position`
+    pub relative_to_monitor: bool,
+}
+
+/// How long the window has to stay put after a drag before its position is
+/// reported, since the window manager moves it in many small steps
+const DRAG_SETTLE: Duration = Duration::from_millis(500);
+
+/// Playback and display settings resolved from the CLI and config
+#[derive(Default)]
+pub struct OverlayOptions {
+    pub load_options: LoadOptions,
+    /// Play only these frames of the main animation
+    pub range: Option<FrameRange>,
+    pub frame_interval: Duration,
+    /// Show frames for the delays stored in GIF/APNG/WebP sources instead of
+    /// `frame_interval`
+    pub use_frame_delays: bool,
+    /// Playback speed multiplier, scaling every frame's time on screen
+    pub speed: f64,
+    pub use_compression: bool,
+    pub blend_mode: BlendMode,
+    /// Straight-alpha sRGB color drawn behind the animation
+    pub background: [u8; 4],
+    /// Vsync, mailbox or immediate presentation
+    pub present_mode: PresentMode,
+    /// Graphics backend and GPU to render with
+    pub adapter: AdapterOptions,
+    /// Timing curve for advancing frames within a loop
+    pub easing: Easing,
+    /// Direction frames are played in
+    pub playback: PlaybackMode,
+    /// Play frames in a new random order each loop, seeded for reproducibility
+    pub shuffle_seed: Option<u64>,
+    /// Exit after playing for this long
+    pub play_duration: Option<Duration>,
+    /// Exit after this many loops (0 loops forever)
+    pub loops: u32,
+    /// Keep watching a directory source and append frames added to it
+    pub watch_dir: bool,
+    /// Decode directory frames from disk during playback, keeping at most this
+    /// many in memory, instead of loading them all up front
+    pub stream_window: Option<usize>,
+    /// Quantize displayed colors to this many levels per channel
+    pub posterize: Option<u32>,
+    /// Repeat the animation in a columns x rows grid
+    pub tile: Option<(u32, u32)>,
+    /// Scale with nearest-neighbor sampling instead of bilinear
+    pub nearest: bool,
+    /// Upload frames with a full mip chain for smoother downscaling
+    pub mipmaps: bool,
+    /// Stretch the animation over the window instead of letterboxing it
+    pub fill: bool,
+    /// Rotate and mirror the animation
+    pub transform: ImageTransform,
+    /// Fade the whole overlay, 1.0 showing it as authored
+    pub opacity: f32,
+    /// Move the window here once it's created
+    pub position: Option<WindowPosition>,
+    /// Monitor to show the window on
+    pub monitor: Option<MonitorSelector>,
+    /// Skip the per-frame transparent clear when the content allows it
+    pub no_clear: bool,
+    /// Draw an opaque window over the background color instead of a transparent one
+    pub opaque: bool,
+    /// Fill the screen behind other windows and let clicks pass through
+    pub ambient: bool,
+    /// Let clicks pass through the window without changing its placement
+    pub click_through: bool,
+    /// Keep the window above other windows, toggled with 't'
+    pub always_on_top: bool,
+    /// Enable debug keys such as 'p' to inspect the pixel under the cursor
+    pub debug: bool,
+    /// Draw frame index, measured FPS, playback mode and frame memory in a
+    /// corner, toggled with 's'
+    pub stats: bool,
+    /// One-shot reactions keyed by lowercase key name (see [`key_name`])
+    pub reactions: HashMap<String, Reaction>,
+    /// Write per-frame timing rows to this CSV file
+    pub trace_timing: Option<PathBuf>,
+    /// Re-apply the playing preset when the config file changes
+    pub config_watch: Option<ConfigWatch>,
+    /// Store the window position in the preset after it's dragged
+    pub save_position: Option<PositionSave>,
+    /// Write compression statistics as JSON here once compressed ("-" for stdout)
+    pub stats_json: Option<PathBuf>,
+    /// Play uncompressed when compression saves less than this factor
+    pub min_compression_ratio: Option<f32>,
+    /// Keyframe spacing and delta quantization used when compressing
+    pub compression_options: CompressionOptions,
+    /// Compressed frames to reconstruct into textures once at load
+    pub cache_frames: usize,
+    /// Measure PSNR and peak error of compressed sequences against the originals
+    pub verify: bool,
+    /// Consecutive late compressed frames tolerated before switching to
+    /// uncompressed playback (0 disables the fallback)
+    pub max_missed_deadlines: u32,
+}
 
 pub struct OverlayApplication {
     window: Option<Arc<Window>>,
     renderer: Option<Renderer>,
     media_sequence: Option<MediaSequence>,
     media_source: Option<MediaSource>,
+    load_options: LoadOptions,
+    range: Option<FrameRange>,
+    watch_dir: bool,
+    directory_watcher: Option<DirectoryWatcher>,
+    stream_window: Option<usize>,
+    /// The idle animation when it's streamed instead of held in `media_sequence`
+    frame_stream: Option<FrameStream>,
     last_frame_time: Instant,
     frame_interval: Duration,
+    use_frame_delays: bool,
+    speed: f64,
+    /// Frames only change when stepped with the arrow keys
+    paused: bool,
     current_frame_index: usize,
+    /// Position within the current loop, see [`OverlayApplication::next_position`]
+    play_position: usize,
     frame_count: usize,
+    shuffle_rng: Option<StdRng>,
+    shuffled_order: Vec<usize>,
     use_compression: bool,
+    blend_mode: BlendMode,
+    background: [u8; 4],
+    present_mode: PresentMode,
+    adapter: AdapterOptions,
+    mipmaps: bool,
+    posterize: Option<u32>,
+    tile: Option<(u32, u32)>,
+    nearest: bool,
+    fill: bool,
+    transform: ImageTransform,
+    opacity: f32,
+    position: Option<WindowPosition>,
+    monitor: Option<MonitorSelector>,
+    easing: Easing,
+    playback: PlaybackMode,
+    /// When the eased loop timeline started
+    easing_started: Option<Instant>,
+    no_clear: bool,
+    opaque: bool,
+    stats_json: Option<PathBuf>,
+    trace_timing: Option<PathBuf>,
+    timing_trace: Option<TimingTrace>,
+    ambient: bool,
+    click_through: bool,
+    always_on_top: bool,
+    debug: bool,
+    show_stats: bool,
+    config_watch: Option<ConfigWatch>,
+    save_position: Option<PositionSave>,
+    /// The left button went down on the window and the window manager is moving it
+    dragging: bool,
+    /// Latest position reported during a drag, and when
+    dragged_to: Option<(PhysicalPosition<i32>, Instant)>,
+    /// Rate frames actually changed at, for the stats overlay
+    fps_counter: FpsCounter,
+    /// Text last drawn into the stats overlay
+    stats_lines: Vec<String>,
+    cursor_position: Option<PhysicalPosition<f64>>,
+    reaction_sources: HashMap<String, Reaction>,
+    reactions: HashMap<String, LoadedReaction>,
+    active_reaction: Option<ActiveReaction>,
+    play_duration: Option<Duration>,
+    playback_started: Option<Instant>,
+    loops: u32,
+    /// Loops of the idle animation finished so far
+    loops_played: u32,
+    min_compression_ratio: Option<f32>,
+    compression_options: CompressionOptions,
+    cache_frames: usize,
+    verify: bool,
+    max_missed_deadlines: u32,
+    missed_deadlines: u32,
+    /// Time spent switching frames since playback or the last mode toggle
+    frame_update_time: Duration,
+    frame_updates: u32,
     frame_update_in_progress: bool,
     is_shutting_down: bool,
 }
 
 impl OverlayApplication {
-    pub fn new(source: MediaSource, frame_interval: Duration, use_compression: bool) -> Self {
+    pub fn new(source: MediaSource, options: OverlayOptions) -> Self {
         Self {
             window: None,
             renderer: None,
             media_sequence: None,
             media_source: Some(source),
+            load_options: options.load_options,
+            range: options.range,
+            watch_dir: options.watch_dir,
+            directory_watcher: None,
+            stream_window: options.stream_window,
+            frame_stream: None,
             last_frame_time: Instant::now(),
-            frame_interval,
+            frame_interval: options.frame_interval,
+            use_frame_delays: options.use_frame_delays,
+            speed: options.speed,
+            paused: false,
             current_frame_index: 0,
+            play_position: 0,
             frame_count: 0,
-            use_compression,
+            shuffle_rng: options.shuffle_seed.map(StdRng::seed_from_u64),
+            shuffled_order: Vec::new(),
+            use_compression: options.use_compression,
+            blend_mode: options.blend_mode,
+            background: options.background,
+            present_mode: options.present_mode,
+            adapter: options.adapter,
+            mipmaps: options.mipmaps,
+            posterize: options.posterize,
+            tile: options.tile,
+            nearest: options.nearest,
+            fill: options.fill,
+            transform: options.transform,
+            opacity: options.opacity,
+            position: options.position,
+            monitor: options.monitor,
+            easing: options.easing,
+            playback: options.playback,
+            easing_started: None,
+            no_clear: options.no_clear,
+            opaque: options.opaque,
+            stats_json: options.stats_json,
+            trace_timing: options.trace_timing,
+            timing_trace: None,
+            ambient: options.ambient,
+            click_through: options.click_through,
+            always_on_top: options.always_on_top,
+            debug: options.debug,
+            show_stats: options.stats,
+            config_watch: options.config_watch,
+            save_position: options.save_position,
+            dragging: false,
+            dragged_to: None,
+            fps_counter: FpsCounter::default(),
+            stats_lines: Vec::new(),
+            cursor_position: None,
+            reaction_sources: options.reactions,
+            reactions: HashMap::new(),
+            active_reaction: None,
+            play_duration: options.play_duration,
+            playback_started: None,
+            loops: options.loops,
+            loops_played: 0,
+            min_compression_ratio: options.min_compression_ratio,
+            compression_options: options.compression_options,
+            verify: options.verify,
+            cache_frames: options.cache_frames,
+            max_missed_deadlines: options.max_missed_deadlines,
+            missed_deadlines: 0,
+            frame_update_time: Duration::ZERO,
+            frame_updates: 0,
             frame_update_in_progress: false,
             is_shutting_down: false,
         }
@@ -43,20 +606,93 @@ impl OverlayApplication {
     pub fn run(&mut self) -> Result<()> {
         let event_loop = EventLoop::new()?;
 
+        if let Some(path) = self.trace_timing.take() {
+            self.timing_trace = Some(TimingTrace::create(&path)?);
+        }
+
         // Load the media sequence
         if let Some(source) = self.media_source.take() {
-            self.media_sequence = Some(MediaSequence::load(source)?);
+            if self.watch_dir {
+                match &source {
+                    // Start watching before loading so frames written meanwhile aren't missed
+                    MediaSource::Directory(path) => {
+                        self.directory_watcher =
+                            Some(DirectoryWatcher::new(path, &self.load_options.formats)?);
+                        if self.use_compression {
+                            log::warn!("Delta compression is disabled while watching a directory");
+                            self.use_compression = false;
+                        }
+                    }
+                    _ => log::warn!("--watch-dir only applies to directory sources, ignoring"),
+                }
+            }
+
+            if self.shuffle_rng.is_some() && !matches!(source, MediaSource::Directory(_)) {
+                log::warn!(
+                    "--shuffle plays animation frames out of order, it's meant for directories of independent images"
+                );
+            }
+
+            match (&source, self.stream_window) {
+                (MediaSource::Directory(path), Some(window)) => {
+                    let mut paths = MediaSequence::find_frame_paths(path, &self.load_options)?;
+                    if let Some(range) = self.range {
+                        let (start, end) = range.bounds(paths.len())?;
+                        paths.truncate(end);
+                        paths.drain(..start);
+                    }
+                    if self.use_compression {
+                        log::warn!("Delta compression is disabled while streaming frames");
+                        self.use_compression = false;
+                    }
+                    self.frame_stream = Some(FrameStream::new(
+                        paths,
+                        window,
+                        self.load_options.chroma_key,
+                    )?);
+                }
+                _ => {
+                    if self.stream_window.is_some() {
+                        log::warn!(
+                            "--stream only applies to directory sources, loading every frame"
+                        );
+                    }
+                    let mut sequence = MediaSequence::load(source, &self.load_options)?;
+                    if let Some(range) = self.range {
+                        sequence.trim(range)?;
+                    }
+                    if let Some(watcher) = &mut self.directory_watcher {
+                        watcher.mark_known(sequence.paths());
+                    }
+                    self.media_sequence = Some(sequence);
+                }
+            }
         } else {
             return Err(anyhow::format_err!("No media source specified"));
         };
 
-        if let Some(sequence) = &self.media_sequence {
-            self.frame_count = sequence.count();
-            log::info!("Loaded {} frames in sequence", self.frame_count);
-        } else {
+        for (key, reaction) in self.reaction_sources.drain() {
+            let sequence = MediaSequence::load(reaction.source, &self.load_options)?;
+            log::info!(
+                "Loaded {} frame reaction for key '{}'",
+                sequence.count(),
+                key
+            );
+            self.reactions.insert(
+                key,
+                LoadedReaction {
+                    sequence,
+                    frame_interval: reaction.frame_interval,
+                },
+            );
+        }
+
+        self.frame_count = self.idle_frame_count();
+        if self.frame_count == 0 {
             log::error!("Failed to load media sequence");
             return Ok(());
         }
+        log::info!("Loaded {} frames in sequence", self.frame_count);
 
         event_loop.run_app(self)?;
 
@@ -76,48 +712,134 @@ impl OverlayApplication {
             renderer.cleanup();
         }
 
+        if let Some(mut trace) = self.timing_trace.take()
+            && let Err(e) = trace.flush()
+        {
+            log::error!("Failed to write timing trace: {}", e);
+        }
+
         self.media_sequence = None;
+        self.frame_stream = None;
         self.window = None;
 
         log::info!("Application cleanup complete");
     }
 
+    /// Pull in frames that appeared in the watched directory
+    fn append_watched_frames(&mut self) {
+        // New frames belong to the idle sequence, so leave them queued until it's back
+        if self.active_reaction.is_some() {
+            return;
+        }
+
+        let Some(watcher) = &mut self.directory_watcher else {
+            return;
+        };
+
+        for (path, mut image) in watcher.poll_new_frames() {
+            if let Some(key) = self.load_options.chroma_key {
+                key.apply(&mut image);
+            }
+            if let Some(renderer) = &mut self.renderer {
+                if let Err(e) = renderer.append_image(&image) {
+                    log::warn!("Skipping {}: {}", path.display(), e);
+                    continue;
+                }
+
+                if self.no_clear && !is_opaque(&image) {
+                    log::warn!(
+                        "{} has transparent pixels, clearing every frame again",
+                        path.display()
+                    );
+                    renderer.set_clear_each_frame(true);
+                    self.no_clear = false;
+                }
+            }
+
+            if let Some(sequence) = &mut self.media_sequence {
+                sequence.push_image(image, path);
+                self.frame_count = sequence.count();
+                log::info!("Sequence now has {} frames", self.frame_count);
+            }
+        }
+    }
+
     fn update(&mut self) {
         if self.is_shutting_down {
             return;
         }
 
+        self.append_watched_frames();
+        if self.paused {
+            return;
+        }
+
         let now = Instant::now();
-        if now.duration_since(self.last_frame_time) >= self.frame_interval
+        if now.duration_since(self.last_frame_time) >= self.current_frame_duration()
             && !self.frame_update_in_progress
         {
             self.last_frame_time = now;
 
             if self.frame_count > 0 {
-                let new_frame_index = (self.current_frame_index + 1) % self.frame_count;
+                let new_position = self.next_position(now);
+                let loop_length = self.loop_length();
+                let wrapped = new_position < self.play_position || loop_length == 1;
+                if self.active_reaction.is_some() && wrapped {
+                    self.finish_reaction();
+                    return;
+                }
+
+                if wrapped {
+                    self.loops_played += 1;
+                    if self.loops_finished() {
+                        // Leave the last frame up, it has had its full time on screen
+                        return;
+                    }
+                }
+
+                if new_position == self.play_position && loop_length > 1 {
+                    // Eased playback can linger on a frame for several intervals
+                    return;
+                }
+
+                if wrapped || self.shuffled_order.len() != self.frame_count {
+                    self.reshuffle();
+                }
+                self.play_position = new_position;
+                self.fps_counter.record(now);
+                let new_frame_index = self.frame_at_position(new_position);
 
                 if let Some(renderer) = &mut self.renderer {
                     self.frame_update_in_progress = true;
+                    let update_start = Instant::now();
 
                     // For compressed sequences, we need to handle async frame reconstruction
-                    if self.use_compression {
-                        // Create a future to update the frame
-                        let renderer_ptr = renderer as *mut Renderer;
-                        let frame_index = new_frame_index;
-
-                        // This could probably be done better
-                        // For now, we'll use pollster to block on the async operation
-                        match pollster::block_on(async {
-                            let renderer = unsafe { &mut *renderer_ptr };
-                            renderer.set_current_texture_index(frame_index).await
-                        }) {
+                    if self.use_compression && self.active_reaction.is_none() {
+                        let reconstruct_start = Instant::now();
+                        let result =
+                            pollster::block_on(renderer.set_current_texture_index(new_frame_index));
+                        let reconstruct_time = reconstruct_start.elapsed();
+                        match result {
                             Ok(_) => {
                                 self.current_frame_index = new_frame_index;
+                                self.trace_frame(Some(reconstruct_time));
                             }
                             Err(e) => {
                                 log::error!("Failed to update compressed frame: {}", e);
                             }
                         }
+
+                        if reconstruct_time > self.current_frame_duration() {
+                            self.missed_deadlines += 1;
+                        } else {
+                            self.missed_deadlines = 0;
+                        }
+
+                        if self.max_missed_deadlines > 0
+                            && self.missed_deadlines >= self.max_missed_deadlines
+                        {
+                            self.switch_to_uncompressed();
+                        }
                     } else {
                         // For uncompressed sequences, this is synchronous
                         match pollster::block_on(
@@ -125,6 +847,7 @@ impl OverlayApplication {
                         ) {
                             Ok(_) => {
                                 self.current_frame_index = new_frame_index;
+                                self.trace_frame(None);
                             }
                             Err(e) => {
                                 log::error!("Failed to update frame: {}", e);
@@ -133,163 +856,1585 @@ impl OverlayApplication {
                     }
 
                     self.frame_update_in_progress = false;
+                    self.frame_update_time += update_start.elapsed();
+                    self.frame_updates += 1;
                 }
             }
         }
     }
 
-    fn render(&mut self) -> Result<()> {
-        if self.is_shutting_down {
-            return Ok(());
-        }
+    /// How long the current frame stays up: its own delay when the source has
+    /// one and per-frame timing is enabled, otherwise the uniform interval,
+    /// scaled by the playback speed
+    fn current_frame_duration(&self) -> Duration {
+        let interval = self
+            .media_sequence
+            .as_ref()
+            .filter(|_| self.use_frame_delays)
+            .and_then(|sequence| sequence.delay(self.current_frame_index))
+            .unwrap_or(self.frame_interval);
+        at_speed(interval, self.speed)
+    }
 
-        if let Some(renderer) = &mut self.renderer {
-            renderer.render()?;
+    /// Switch the window between staying above other windows and normal stacking
+    fn toggle_always_on_top(&mut self) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        if self.ambient {
+            log::info!("Ambient mode keeps the window below others, not toggling");
+            return;
         }
 
-        Ok(())
+        self.always_on_top = !self.always_on_top;
+        // winit can't report failure here: Wayland has no protocol for clients to
+        // set their stacking level, and some X11 window managers ignore the hint
+        window.set_window_level(if self.always_on_top {
+            WindowLevel::AlwaysOnTop
+        } else {
+            WindowLevel::Normal
+        });
+        log::info!(
+            "Always on top {}",
+            if self.always_on_top { "on" } else { "off" }
+        );
     }
-}
 
-impl ApplicationHandler for OverlayApplication {
-    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let (width, height) = if let Some(sequence) = &self.media_sequence {
-            if let Some(image) = sequence.current_image() {
-                let dimensions = image.dimensions();
-                log::info!(
-                    "Using image dimensions for window: {}x{}",
-                    dimensions.0,
-                    dimensions.1
-                );
-                (dimensions.0, dimensions.1)
-            } else {
-                log::info!("No image found, using default dimensions");
-                (800, 600)
-            }
+    /// Multiply the playback speed by `factor`, within [`SPEED_RANGE`]
+    fn adjust_speed(&mut self, factor: f64) {
+        self.speed = (self.speed * factor).clamp(*SPEED_RANGE.start(), *SPEED_RANGE.end());
+        // Restart the eased timeline so the new speed doesn't jump to another phase
+        self.easing_started = None;
+        log::info!("Playback speed {:.2}x", self.speed);
+    }
+
+    /// Stop or resume advancing frames
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        if self.paused {
+            log::info!(
+                "Paused on frame {} of {}, arrow keys step frames",
+                self.current_frame_index,
+                self.frame_count
+            );
         } else {
-            log::info!("No media sequence found, using default dimensions");
-            (800, 600)
-        };
+            // Give the current frame its full time and restart the eased timeline
+            self.last_frame_time = Instant::now();
+            self.easing_started = None;
+            log::info!("Resumed");
+        }
+    }
 
-        let window_attributes = WindowAttributes::default()
-            .with_title(if self.use_compression {
-                "PNG Overlay (Delta Compressed)"
-            } else {
-                "PNG Overlay"
-            })
-            .with_transparent(true)
-            .with_decorations(false)
-            .with_resizable(false)
-            .with_inner_size(PhysicalSize::new(width, height));
+    /// Show the next or previous frame while paused, wrapping around the
+    /// sequence. Playback resumes from the stepped-to frame.
+    fn step_frame(&mut self, forward: bool) {
+        if !self.paused || self.frame_count == 0 {
+            return;
+        }
 
-        match event_loop.create_window(window_attributes) {
-            Ok(window) => {
-                let window_arc = Arc::new(window);
-                self.window = Some(window_arc.clone());
+        let index = step_index(self.current_frame_index, self.frame_count, forward);
+        if let Some(renderer) = &mut self.renderer {
+            // Compressed sequences replay backward steps from the nearest keyframe
+            if let Err(e) = pollster::block_on(renderer.set_current_texture_index(index)) {
+                log::error!("Failed to show frame {}: {}", index, e);
+                return;
+            }
+        }
 
-                pollster::block_on(async {
-                    match Renderer::new(window_arc).await {
-                        Ok(mut renderer) => {
-                            if let Some(sequence) = &self.media_sequence {
-                                let all_images = sequence.get_all_images();
-
-                                if self.use_compression {
-                                    log::info!(
-                                        "Loading {} images with delta compression",
-                                        all_images.len()
-                                    );
-                                    match renderer.preload_images_compressed(&all_images).await {
-                                        Ok(_) => {
-                                            log::info!("Successfully loaded compressed sequence");
-                                        }
-                                        Err(e) => {
-                                            log::error!(
-                                                "Failed to load compressed sequence: {}, falling back to uncompressed",
-                                                e
-                                            );
-                                            renderer.preload_images(&all_images);
-                                        }
-                                    }
-                                } else {
-                                    log::info!(
-                                        "Loading {} images without compression",
-                                        all_images.len()
-                                    );
-                                    renderer.preload_images(&all_images);
-                                }
-                            }
+        self.current_frame_index = index;
+        // The order is drawn on the first advance and redrawn when frames are appended
+        if self.shuffled_order.len() != self.frame_count {
+            self.reshuffle();
+        }
+        if let Some(position) =
+            (0..self.loop_length()).find(|&position| self.frame_at_position(position) == index)
+        {
+            self.play_position = position;
+        }
+        log::info!("Frame {} of {}", index, self.frame_count);
 
-                            self.renderer = Some(renderer);
-                        }
-                        Err(err) => {
-                            log::error!("Failed to create renderer: {}", err);
-                            event_loop.exit();
-                        }
-                    }
-                });
-            }
-            Err(err) => {
-                log::error!("Failed to create window: {}", err);
-                event_loop.exit();
-            }
+        if let Some(window) = &self.window {
+            window.request_redraw();
         }
     }
 
-    fn window_event(
-        &mut self,
-        event_loop: &ActiveEventLoop,
-        _window_id: WindowId,
-        event: winit::event::WindowEvent,
-    ) {
-        match event {
-            winit::event::WindowEvent::CloseRequested => {
-                log::info!("Window close requested");
+    /// Add a row to the timing trace, if one is being recorded
+    fn trace_frame(&mut self, reconstruct: Option<Duration>) {
+        let target = self.current_frame_duration();
+        if let Some(trace) = &mut self.timing_trace
+            && let Err(e) = trace.record(self.current_frame_index, target, reconstruct)
+        {
+            log::error!("Stopping timing trace: {}", e);
+            self.timing_trace = None;
+        }
+    }
 
-                self.cleanup();
+    /// Whether `key` is bound to a reaction, including the one playing, which
+    /// is taken out of `reactions` until it finishes
+    fn is_reaction_key(&self, key: &str) -> bool {
+        self.reactions.contains_key(key)
+            || self
+                .active_reaction
+                .as_ref()
+                .is_some_and(|active| active.key == key)
+    }
 
-                event_loop.exit();
-            }
-            winit::event::WindowEvent::Resized(size) => {
-                log::info!("Window resized to {}x{}", size.width, size.height);
-                if let Some(renderer) = &mut self.renderer {
-                    renderer.resize(size.width, size.height);
-                }
-            }
-            winit::event::WindowEvent::RedrawRequested => {
-                if !self.is_shutting_down {
-                    self.update();
+    /// Play the reaction bound to `key` once, then return to the current animation.
+    /// Presses while a reaction is already playing are ignored.
+    fn trigger_reaction(&mut self, key: &str) {
+        if self.renderer.is_none() || !self.is_reaction_key(key) {
+            return;
+        }
 
-                    if let Err(err) = self.render() {
-                        log::error!("Render error: {}", err);
-                    }
+        if let Some(active) = &self.active_reaction {
+            log::debug!(
+                "Ignoring '{}' while the '{}' reaction is playing",
+                key,
+                active.key
+            );
+            return;
+        }
 
-                    if let Some(window) = &self.window {
-                        window.request_redraw();
-                    }
-                }
-            }
-            _ => {}
+        let Some(reaction) = self.reactions.remove(key) else {
+            return;
+        };
+        log::info!("Playing reaction for key '{}'", key);
+
+        if self.no_clear
+            && !reaction.sequence.is_opaque()
+            && let Some(renderer) = &mut self.renderer
+        {
+            log::warn!(
+                "Reaction '{}' has transparent pixels, clearing every frame again",
+                key
+            );
+            renderer.set_clear_each_frame(true);
+            self.no_clear = false;
         }
+
+        self.active_reaction = Some(ActiveReaction {
+            key: key.to_string(),
+            idle_sequence: self.media_sequence.replace(reaction.sequence),
+            idle_frame_interval: std::mem::replace(
+                &mut self.frame_interval,
+                reaction.frame_interval,
+            ),
+            idle_frame_index: self.current_frame_index,
+            idle_play_position: self.play_position,
+        });
+
+        // Reactions are short one-shots, so they always play uncompressed
+        self.start_sequence(0, false);
     }
 
-    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
-        if self.is_shutting_down {
+    /// Put the idle animation back after a reaction played through. With
+    /// compression enabled the idle frames are compressed again.
+    fn finish_reaction(&mut self) {
+        let Some(active) = self.active_reaction.take() else {
             return;
+        };
+        log::info!("Reaction '{}' finished", active.key);
+
+        let frame_interval =
+            std::mem::replace(&mut self.frame_interval, active.idle_frame_interval);
+        if let Some(sequence) = std::mem::replace(&mut self.media_sequence, active.idle_sequence) {
+            self.reactions.insert(
+                active.key,
+                LoadedReaction {
+                    sequence,
+                    frame_interval,
+                },
+            );
         }
 
-        let now = Instant::now();
-        if now.duration_since(self.last_frame_time) >= self.frame_interval {
-            if let Some(window) = &self.window {
-                window.request_redraw();
-                event_loop.set_control_flow(ControlFlow::WaitUntil(now + self.frame_interval));
-            }
+        self.start_sequence(active.idle_frame_index, self.use_compression);
+        self.play_position = active.idle_play_position;
+    }
+
+    /// Frames in the playing sequence, whether it's held in memory or streamed
+    fn idle_frame_count(&self) -> usize {
+        match (&self.frame_stream, &self.active_reaction) {
+            (Some(stream), None) => stream.count(),
+            _ => self.media_sequence.as_ref().map_or(0, MediaSequence::count),
         }
     }
-}
 
-impl Drop for OverlayApplication {
-    fn drop(&mut self) {
-        log::debug!("Dropping OverlayApplication");
-        self.cleanup();
+    /// Upload the current sequence and show `index` from it
+    fn start_sequence(&mut self, index: usize, compress: bool) {
+        self.frame_count = self.idle_frame_count();
+        self.current_frame_index = index;
+        self.play_position = index;
+        self.easing_started = None;
+        self.missed_deadlines = 0;
+
+        self.upload_sequence(compress);
+        if let Some(renderer) = &mut self.renderer
+            && let Err(e) = pollster::block_on(renderer.set_current_texture_index(index))
+        {
+            log::error!("Failed to show frame {}: {}", index, e);
+        }
+
+        self.last_frame_time = Instant::now();
+    }
+
+    /// Position within the loop to show next. Equal to the frame index unless
+    /// shuffling, where it indexes into the shuffled order instead, or playing
+    /// in reverse or ping-pong (see [`PlaybackMode::frame_at`]).
+    fn next_position(&mut self, now: Instant) -> usize {
+        let loop_length = self.loop_length();
+        if self.easing == Easing::Linear {
+            return (self.play_position + 1) % loop_length;
+        }
+
+        let started = *self.easing_started.get_or_insert(now);
+        self.easing.frame_at(
+            now - started,
+            at_speed(self.frame_interval, self.speed),
+            loop_length,
+        )
+    }
+
+    /// Positions in one loop. Reactions and shuffled loops always play every
+    /// frame once.
+    fn loop_length(&self) -> usize {
+        if self.active_reaction.is_some() || self.shuffle_rng.is_some() {
+            self.frame_count
+        } else {
+            self.playback.loop_length(self.frame_count)
+        }
+    }
+
+    fn frame_at_position(&self, position: usize) -> usize {
+        match (&self.shuffle_rng, &self.active_reaction) {
+            (Some(_), None) => self.shuffled_order[position],
+            (None, None) => self.playback.frame_at(position, self.frame_count),
+            _ => position,
+        }
+    }
+
+    /// Draw a fresh frame order for the next loop when shuffling
+    fn reshuffle(&mut self) {
+        if let Some(rng) = &mut self.shuffle_rng
+            && self.active_reaction.is_none()
+        {
+            self.shuffled_order = shuffled_order(rng, self.frame_count);
+        }
+    }
+
+    /// Replace the delta-compressed sequence with plain textures when frame
+    /// reconstruction keeps missing its deadline. Trades memory for smooth playback.
+    fn switch_to_uncompressed(&mut self) {
+        let (Some(renderer), Some(sequence)) = (&mut self.renderer, &self.media_sequence) else {
+            return;
+        };
+
+        log::warn!(
+            "Delta reconstruction missed {} consecutive frame deadlines ({:?} per frame), \
+             switching to uncompressed playback",
+            self.missed_deadlines,
+            self.frame_interval
+        );
+
+        renderer.preload_images(sequence.get_all_images());
+        if let Err(e) =
+            pollster::block_on(renderer.set_current_texture_index(self.current_frame_index))
+        {
+            log::error!("Failed to restore frame after switching: {}", e);
+        }
+
+        self.use_compression = false;
+        self.missed_deadlines = 0;
+    }
+
+    /// Upload the current media sequence to the renderer, replacing whatever it showed
+    fn upload_sequence(&mut self, compress: bool) {
+        if let (Some(renderer), Some(stream), None) = (
+            &mut self.renderer,
+            &self.frame_stream,
+            &self.active_reaction,
+        ) {
+            renderer.load_stream(stream.clone());
+            return;
+        }
+
+        let (Some(renderer), Some(sequence)) = (&mut self.renderer, &self.media_sequence) else {
+            return;
+        };
+        let all_images = sequence.get_all_images();
+
+        if compress {
+            log::info!("Loading {} images with delta compression", all_images.len());
+            match pollster::block_on(renderer.preload_images_compressed(all_images)) {
+                Ok(_) => {
+                    log::info!("Successfully loaded compressed sequence");
+                }
+                Err(e) => {
+                    log::error!(
+                        "Failed to load compressed sequence: {}, falling back to uncompressed",
+                        e
+                    );
+                    renderer.preload_images(all_images);
+                }
+            }
+        } else {
+            log::info!("Loading {} images without compression", all_images.len());
+            renderer.preload_images(all_images);
+        }
+
+        // Compression may have been declined or failed, play back whatever was loaded
+        if compress && !renderer.is_compressed() {
+            self.use_compression = false;
+        }
+    }
+
+    /// Rebuild the current sequence in the other storage mode, for comparing
+    /// delta-compressed and plain playback on the same frames
+    fn toggle_compression(&mut self) {
+        if self.active_reaction.is_some() || self.renderer.is_none() {
+            return;
+        }
+        if self.frame_stream.is_some() {
+            log::info!("Streamed frames always play uncompressed, not toggling");
+            return;
+        }
+
+        self.log_mode_summary();
+
+        let compress = !self.use_compression;
+        self.use_compression = compress;
+        self.upload_sequence(compress);
+        self.missed_deadlines = 0;
+
+        if let Some(renderer) = &mut self.renderer {
+            if let Err(e) =
+                pollster::block_on(renderer.set_current_texture_index(self.current_frame_index))
+            {
+                log::error!("Failed to restore frame after switching: {}", e);
+            }
+            log::info!(
+                "Switched to {} playback",
+                if renderer.is_compressed() {
+                    "compressed"
+                } else {
+                    "uncompressed"
+                }
+            );
+        }
+    }
+
+    /// Reload the config after it changed on disk and apply the playing
+    /// preset's fps, opacity and position, reloading the frames when its path
+    /// changed. Settings given on the command line are left alone.
+    fn apply_config_changes(&mut self, event_loop: &ActiveEventLoop) {
+        // Reactions swap out the idle sequence and interval, so wait until it's back
+        if self.active_reaction.is_some() {
+            return;
+        }
+        let Some(watch) = &mut self.config_watch else {
+            return;
+        };
+        if !watch.watcher.poll(Instant::now()) {
+            return;
+        }
+
+        let preset = match Config::load(Some(watch.watcher.path())) {
+            Ok(Some(config)) => config.get_preset(&watch.preset).cloned(),
+            Ok(None) => None,
+            Err(e) => {
+                log::error!("Keeping the current settings: {}", e);
+                return;
+            }
+        };
+        let Some(preset) = preset else {
+            log::error!(
+                "Preset '{}' is no longer in the config, keeping the current settings",
+                watch.preset
+            );
+            return;
+        };
+        log::info!("Config changed, re-applying preset '{}'", watch.preset);
+
+        let new_path = (preset.path != watch.path).then(|| preset.path.clone());
+        let (fps_overridden, opacity_overridden, position_overridden) = (
+            watch.fps_overridden,
+            watch.opacity_overridden,
+            watch.position_overridden,
+        );
+
+        if !fps_overridden {
+            self.frame_interval = create_frame_interval(preset.fps.unwrap_or(30.0));
+            self.easing_started = None;
+        }
+
+        if !opacity_overridden {
+            self.opacity = preset
+                .opacity
+                .map_or(1.0, |opacity| opacity.clamp(0.0, 1.0));
+            if let Some(renderer) = &mut self.renderer {
+                renderer.set_opacity(self.opacity);
+            }
+        }
+
+        if !position_overridden && !self.ambient {
+            match preset.position.as_deref().map(WindowPosition::from_str) {
+                Some(Ok(position)) if Some(position) != self.position => {
+                    self.position = Some(position);
+                    let monitor = self
+                        .monitor
+                        .as_ref()
+                        .and_then(|selector| Self::select_monitor(event_loop, selector));
+                    if let Some(window) = &self.window {
+                        Self::move_window(event_loop, window, position, monitor.as_ref());
+                    }
+                }
+                Some(Err(e)) => log::error!("Invalid preset position: {}", e),
+                _ => {}
+            }
+        }
+
+        if let Some(path) = new_path
+            && self.reload_media(Path::new(&path))
+            && let Some(watch) = &mut self.config_watch
+        {
+            watch.path = path;
+        }
+    }
+
+    /// Replace the playing frames with those at `path`, keeping the current
+    /// ones when it can't be loaded
+    fn reload_media(&mut self, path: &Path) -> bool {
+        let sequence = detect_media_type(path)
+            .and_then(|source| MediaSequence::load(source, &self.load_options))
+            .and_then(|mut sequence| {
+                if let Some(range) = self.range {
+                    sequence.trim(range)?;
+                }
+                Ok(sequence)
+            });
+        let sequence = match sequence {
+            Ok(sequence) => sequence,
+            Err(e) => {
+                log::error!("Failed to load {}: {}", path.display(), e);
+                return false;
+            }
+        };
+        log::info!("Loaded {} frames from {}", sequence.count(), path.display());
+
+        if self.directory_watcher.take().is_some() {
+            log::warn!("Stopped watching the previous directory for new frames");
+        }
+        if self.no_clear
+            && !sequence.is_opaque()
+            && let Some(renderer) = &mut self.renderer
+        {
+            log::warn!("New frames have transparent pixels, clearing every frame again");
+            renderer.set_clear_each_frame(true);
+            self.no_clear = false;
+        }
+
+        if self.frame_stream.take().is_some() {
+            log::info!("Loading every frame of the new source instead of streaming");
+        }
+        self.media_sequence = Some(sequence);
+        self.shuffled_order.clear();
+        self.start_sequence(0, self.use_compression);
+        true
+    }
+
+    /// Hand the borderless window to the window manager to move while the
+    /// left button is held. Click-through and ambient windows never get the
+    /// press in the first place.
+    fn start_drag(&mut self) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        match window.drag_window() {
+            Ok(()) => self.dragging = true,
+            Err(e) => log::warn!("Could not start moving the window: {}", e),
+        }
+    }
+
+    /// Once a dragged window has settled, log where it ended up and save it
+    /// to the preset with `--save-position`
+    fn finish_drag(&mut self) {
+        let Some((position, moved_at)) = self.dragged_to else {
+            return;
+        };
+        if moved_at.elapsed() < DRAG_SETTLE {
+            return;
+        }
+        self.dragged_to = None;
+        self.dragging = false;
+
+        let Some(save) = &self.save_position else {
+            log::info!(
+                "Window moved to {},{} (set position = \"{},{}\" in a preset to keep it there)",
+                position.x,
+                position.y,
+                position.x,
+                position.y
+            );
+            return;
+        };
+
+        let origin = self
+            .window
+            .as_ref()
+            .and_then(|window| window.current_monitor())
+            .filter(|_| save.relative_to_monitor)
+            .map_or(PhysicalPosition::new(0, 0), |monitor| monitor.position());
+        let value = format!("{},{}", position.x - origin.x, position.y - origin.y);
+        match save_preset_value(&save.config_path, &save.preset, "position", &value) {
+            Ok(()) => log::info!("Saved position {} to preset '{}'", value, save.preset),
+            Err(e) => log::warn!("Could not save the window position: {}", e),
+        }
+    }
+
+    /// Show or hide the stats overlay
+    fn toggle_stats(&mut self) {
+        self.show_stats = !self.show_stats;
+        if !self.show_stats {
+            self.stats_lines.clear();
+            if let Some(renderer) = &mut self.renderer {
+                renderer.set_stats_image(None);
+            }
+        }
+    }
+
+    /// Redraw the stats overlay text when any of its values changed
+    fn update_stats_overlay(&mut self) {
+        let target_fps = 1.0 / self.current_frame_duration().as_secs_f64();
+        let Some(renderer) = &mut self.renderer else {
+            return;
+        };
+        if !self.show_stats {
+            return;
+        }
+
+        let mode = if self.active_reaction.is_some() {
+            "reaction"
+        } else if renderer.is_compressed() {
+            "compressed"
+        } else if renderer.is_streamed() {
+            "streamed"
+        } else {
+            "uncompressed"
+        };
+        let lines = vec![
+            format!("frame {}/{}", self.current_frame_index, self.frame_count),
+            format!(
+                "fps {:.1}/{:.1}",
+                self.fps_counter.fps(Instant::now()),
+                target_fps
+            ),
+            format!("{}{}", mode, if self.paused { " paused" } else { "" }),
+            format!(
+                "vram {:.1} mb",
+                renderer.frame_memory_usage() as f64 / (1024.0 * 1024.0)
+            ),
+        ];
+
+        if lines != self.stats_lines {
+            renderer.set_stats_image(Some(&render_text(&lines)));
+            self.stats_lines = lines;
+        }
+    }
+
+    /// Log memory use and average frame switch time of the current mode, then
+    /// start measuring afresh
+    fn log_mode_summary(&mut self) {
+        let Some(renderer) = &self.renderer else {
+            return;
+        };
+
+        let mode = if renderer.is_compressed() {
+            "Compressed"
+        } else if renderer.is_streamed() {
+            "Streamed"
+        } else {
+            "Uncompressed"
+        };
+        let average = self
+            .frame_update_time
+            .checked_div(self.frame_updates)
+            .unwrap_or_default();
+        log::info!(
+            "{} playback: {:.2} MB of frame data, {:?} average frame switch over {} frames",
+            mode,
+            renderer.frame_memory_usage() as f64 / (1024.0 * 1024.0),
+            average,
+            self.frame_updates
+        );
+
+        self.frame_update_time = Duration::ZERO;
+        self.frame_updates = 0;
+    }
+
+    /// Move `window` to `position`, keeping it on a monitor. Anchors are relative
+    /// to `monitor`, or the primary monitor without one; with a monitor, `x,y`
+    /// coordinates are relative to its top-left corner.
+    fn move_window(
+        event_loop: &ActiveEventLoop,
+        window: &Window,
+        position: WindowPosition,
+        monitor: Option<&MonitorHandle>,
+    ) {
+        let position = match (position, monitor) {
+            (WindowPosition::At(x, y), Some(monitor)) => {
+                let origin = monitor.position();
+                WindowPosition::At(origin.x + x, origin.y + y)
+            }
+            _ => position,
+        };
+
+        let first = monitor.cloned().or_else(|| event_loop.primary_monitor());
+        let monitors: Vec<MonitorRect> = first
+            .iter()
+            .cloned()
+            .chain(
+                event_loop
+                    .available_monitors()
+                    .filter(|monitor| Some(monitor) != first.as_ref()),
+            )
+            .map(|monitor| MonitorRect {
+                position: monitor.position(),
+                size: monitor.size(),
+            })
+            .collect();
+
+        let Some((target, clamped)) = place_window(position, window.outer_size(), &monitors) else {
+            log::warn!(
+                "No monitor information available, can't place the window at {:?}",
+                position
+            );
+            return;
+        };
+        if clamped {
+            log::warn!(
+                "{:?} is off every monitor, moving the window to {},{}",
+                position,
+                target.x,
+                target.y
+            );
+        }
+
+        log::info!("Moving window to {},{}", target.x, target.y);
+        window.set_outer_position(target);
+    }
+
+    /// The monitor `selector` picks, or the primary one with a warning listing
+    /// every monitor when it matches none
+    fn select_monitor(
+        event_loop: &ActiveEventLoop,
+        selector: &MonitorSelector,
+    ) -> Option<MonitorHandle> {
+        let monitors: Vec<MonitorHandle> = event_loop.available_monitors().collect();
+        let names: Vec<Option<String>> = monitors.iter().map(MonitorHandle::name).collect();
+        if let Some(index) = selector.find(&names) {
+            log::info!(
+                "Showing the window on monitor {} ({:?})",
+                index,
+                names[index]
+            );
+            return Some(monitors[index].clone());
+        }
+
+        let available: Vec<String> = monitors
+            .iter()
+            .enumerate()
+            .map(|(index, monitor)| {
+                let size = monitor.size();
+                format!(
+                    "{}: {} {}x{}",
+                    index,
+                    monitor.name().unwrap_or_else(|| "unnamed".to_string()),
+                    size.width,
+                    size.height
+                )
+            })
+            .collect();
+        log::warn!(
+            "No monitor matches {:?}, using the primary one. Available monitors: {}",
+            selector,
+            available.join(", ")
+        );
+        event_loop.primary_monitor()
+    }
+
+    /// Log how the loaded frames are stored: the base frame and delta sizes
+    /// against the plain RGBA size when compressed, the texture memory otherwise
+    fn log_compression_info(&self) {
+        let Some(renderer) = &self.renderer else {
+            return;
+        };
+        let megabytes = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+
+        match renderer.compression_stats() {
+            Some(stats) => {
+                let base_size = stats.width as usize * stats.height as usize * 4;
+                let delta_size: usize = stats.delta_sizes.iter().sum();
+                log::info!(
+                    "Compressed {} frames: {:.2} MB base frame + {:.2} MB deltas = {:.2} MB, \
+                     {:.2} MB uncompressed ({:.2}x), {:.1}% of delta tiles changed on average",
+                    stats.frame_count,
+                    megabytes(base_size),
+                    megabytes(delta_size),
+                    megabytes(stats.compressed_size),
+                    megabytes(stats.original_size),
+                    stats.compression_ratio,
+                    stats.average_dirty_tile_percent
+                );
+                if let Some(quality) = stats.quality {
+                    log::info!(
+                        "Quality: {:.2} dB average PSNR, worst frame {} at {:.2} dB, \
+                         max channel error {}",
+                        quality.average_psnr,
+                        quality.worst_frame,
+                        quality.worst_frame_psnr,
+                        quality.max_channel_error
+                    );
+                }
+            }
+            None => log::info!(
+                "Uncompressed {} frames: {:.2} MB of textures",
+                self.frame_count,
+                megabytes(renderer.frame_memory_usage())
+            ),
+        }
+    }
+
+    /// Log the RGBA value of the current frame under the cursor. Compressed
+    /// sequences report the reconstructed frame, so reconstruction errors show up.
+    fn inspect_pixel(&self) {
+        let (Some(position), Some(window)) = (self.cursor_position, &self.window) else {
+            log::info!("Pixel inspect: cursor is not over the window");
+            return;
+        };
+
+        let reconstructed = self
+            .renderer
+            .as_ref()
+            .and_then(Renderer::reconstructed_frame);
+        let (frame, source) = match reconstructed {
+            Some(frame) => (Some(frame), "reconstructed"),
+            None => (
+                self.media_sequence
+                    .as_ref()
+                    .and_then(|sequence| sequence.get_all_images().get(self.current_frame_index)),
+                "source",
+            ),
+        };
+        let Some(frame) = frame else {
+            return;
+        };
+
+        let Some((x, y)) = window_to_image(
+            position,
+            window.inner_size(),
+            frame.dimensions(),
+            self.tile.unwrap_or((1, 1)),
+            self.fill,
+            self.transform,
+        ) else {
+            log::info!("Pixel inspect: cursor is outside the frame");
+            return;
+        };
+
+        let [r, g, b, a] = frame.get_pixel(x, y).0;
+        log::info!(
+            "Pixel inspect: frame {} ({}) at {},{} = rgba({}, {}, {}, {}) #{:02x}{:02x}{:02x}{:02x}",
+            self.current_frame_index,
+            source,
+            x,
+            y,
+            r,
+            g,
+            b,
+            a,
+            r,
+            g,
+            b,
+            a
+        );
+    }
+
+    /// Write the requested JSON statistics report for the compressed sequence
+    fn write_compression_stats(&mut self) {
+        let Some(path) = self.stats_json.take() else {
+            return;
+        };
+        let Some(stats) = self.renderer.as_ref().and_then(Renderer::compression_stats) else {
+            log::warn!("--stats-json needs a compressed sequence, nothing written");
+            return;
+        };
+
+        let result = if path == Path::new("-") {
+            serde_json::to_writer_pretty(std::io::stdout().lock(), &stats)
+                .map_err(anyhow::Error::from)
+        } else {
+            std::fs::File::create(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|file| {
+                    serde_json::to_writer_pretty(std::io::BufWriter::new(file), &stats)
+                        .map_err(anyhow::Error::from)
+                })
+        };
+
+        match result {
+            Ok(()) => log::info!("Wrote compression statistics to {}", path.display()),
+            Err(e) => log::error!("Failed to write {}: {}", path.display(), e),
+        }
+    }
+
+    /// Whether skipping the clear leaves no stale pixels behind: the quad covers
+    /// the whole surface, so that holds when every frame is opaque and drawn "over"
+    fn can_skip_clear(&self) -> bool {
+        if self.blend_mode != BlendMode::Over {
+            log::warn!("--no-clear needs --blend over, clearing every frame");
+            return false;
+        }
+
+        if self.opacity < 1.0 {
+            log::warn!("--no-clear needs full --opacity, clearing every frame");
+            return false;
+        }
+
+        match &self.media_sequence {
+            Some(sequence) if !sequence.is_opaque() => {
+                log::warn!("--no-clear needs fully opaque frames, clearing every frame");
+                false
+            }
+            Some(_) if !self.fills_window() => {
+                log::warn!("--no-clear needs the animation to cover the window, use --fill");
+                false
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    /// Whether the drawn frame covers the whole window, leaving no letterbox bars
+    fn fills_window(&self) -> bool {
+        if self.fill {
+            return true;
+        }
+
+        let (Some(window), Some(frame)) = (
+            &self.window,
+            self.media_sequence
+                .as_ref()
+                .and_then(MediaSequence::current_image),
+        ) else {
+            return false;
+        };
+
+        let size = window.inner_size();
+        let (columns, rows) = self.tile.unwrap_or((1, 1));
+        let (width, height) = self.transform.displayed_size(frame.dimensions());
+        let content_width = (width * columns) as f64;
+        let content_height = (height * rows) as f64;
+        let scale = (size.width as f64 / content_width).min(size.height as f64 / content_height);
+        size.width as f64 - content_width * scale < 1.0
+            && size.height as f64 - content_height * scale < 1.0
+    }
+
+    /// Whether the requested number of loops has played through
+    fn loops_finished(&self) -> bool {
+        self.loops > 0 && self.loops_played >= self.loops
+    }
+
+    /// Whether the requested wall-clock play duration has run out
+    fn play_duration_elapsed(&self) -> bool {
+        match (self.play_duration, self.playback_started) {
+            (Some(duration), Some(started)) => started.elapsed() >= duration,
+            _ => false,
+        }
+    }
+
+    fn render(&mut self) -> Result<()> {
+        if self.is_shutting_down {
+            return Ok(());
+        }
+
+        if let Some(renderer) = &mut self.renderer {
+            renderer.render()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ApplicationHandler for OverlayApplication {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let (width, height) = if let Some(stream) = &self.frame_stream {
+            stream.dimensions()
+        } else if let Some(sequence) = &self.media_sequence {
+            if let Some(image) = sequence.current_image() {
+                let dimensions = image.dimensions();
+                log::info!(
+                    "Using image dimensions for window: {}x{}",
+                    dimensions.0,
+                    dimensions.1
+                );
+                (dimensions.0, dimensions.1)
+            } else {
+                log::info!("No image found, using default dimensions");
+                (800, 600)
+            }
+        } else {
+            log::info!("No media sequence found, using default dimensions");
+            (800, 600)
+        };
+        let (width, height) = self.transform.displayed_size((width, height));
+
+        let mut window_attributes = WindowAttributes::default()
+            .with_title(if self.use_compression {
+                "PNG Overlay (Delta Compressed)"
+            } else {
+                "PNG Overlay"
+            })
+            .with_transparent(!self.opaque)
+            .with_decorations(false)
+            .with_resizable(false)
+            .with_inner_size(PhysicalSize::new(width, height));
+
+        let monitor = self
+            .monitor
+            .as_ref()
+            .and_then(|selector| Self::select_monitor(event_loop, selector));
+
+        if self.ambient {
+            // The frame is scaled to the whole surface, so it fills the screen
+            window_attributes = window_attributes
+                .with_fullscreen(Some(Fullscreen::Borderless(monitor.clone())))
+                .with_window_level(WindowLevel::AlwaysOnBottom);
+        } else if self.always_on_top {
+            window_attributes = window_attributes.with_window_level(WindowLevel::AlwaysOnTop);
+        }
+
+        match event_loop.create_window(window_attributes) {
+            Ok(window) => {
+                // The window manager can still close the window, only pointer input passes through
+                if self.ambient || self.click_through {
+                    if let Err(e) = window.set_cursor_hittest(false) {
+                        log::warn!("Could not make the window click-through: {}", e);
+                    } else if !self.reactions.is_empty() || self.debug {
+                        log::info!("Window is click-through, keys won't reach it");
+                    }
+                }
+
+                if !self.ambient && (self.position.is_some() || monitor.is_some()) {
+                    let position = self
+                        .position
+                        .unwrap_or(WindowPosition::Anchor(Anchor::Center));
+                    Self::move_window(event_loop, &window, position, monitor.as_ref());
+                }
+
+                let window_arc = Arc::new(window);
+                self.window = Some(window_arc.clone());
+
+                match pollster::block_on(Renderer::new(
+                    window_arc,
+                    self.blend_mode,
+                    self.nearest,
+                    self.adapter,
+                )) {
+                    Ok(mut renderer) => {
+                        renderer.set_posterize_levels(self.posterize);
+                        if let Some((columns, rows)) = self.tile {
+                            renderer.set_tile(columns, rows);
+                        }
+                        renderer.set_fill(self.fill);
+                        renderer.set_transform(self.transform);
+                        renderer.set_opacity(self.opacity);
+                        renderer.set_background(self.background);
+                        renderer.set_present_mode(self.present_mode);
+                        renderer.set_opaque(self.opaque);
+                        if !self.opaque && !renderer.is_transparent() {
+                            log::warn!(
+                                "This display can't show transparent windows, usually because no compositor is running, so transparent areas will be black. Start a compositor, or pass --opaque with --background to pick the color behind the animation"
+                            );
+                        }
+                        renderer.set_mipmaps(self.mipmaps);
+                        if self.mipmaps && self.use_compression {
+                            log::warn!(
+                                "--mipmaps only applies to uncompressed playback, compressed frames have none"
+                            );
+                        }
+                        renderer.set_min_compression_ratio(self.min_compression_ratio);
+                        renderer.set_compression_options(self.compression_options);
+                        renderer.set_cache_frames(self.cache_frames);
+                        renderer.set_measure_quality(self.verify);
+
+                        if self.no_clear {
+                            self.no_clear = self.can_skip_clear();
+                            renderer.set_clear_each_frame(!self.no_clear);
+                        }
+
+                        self.renderer = Some(renderer);
+                        self.upload_sequence(self.use_compression);
+                        self.write_compression_stats();
+                    }
+                    Err(err) => {
+                        log::error!("Failed to create renderer: {}", err);
+                        event_loop.exit();
+                    }
+                }
+            }
+            Err(err) => {
+                log::error!("Failed to create window: {}", err);
+                event_loop.exit();
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _window_id: WindowId,
+        event: winit::event::WindowEvent,
+    ) {
+        match event {
+            winit::event::WindowEvent::CloseRequested => {
+                log::info!("Window close requested");
+
+                self.cleanup();
+
+                event_loop.exit();
+            }
+            winit::event::WindowEvent::Resized(size) => {
+                log::info!("Window resized to {}x{}", size.width, size.height);
+                if let Some(renderer) = &mut self.renderer {
+                    renderer.resize(size.width, size.height);
+                }
+
+                // A new aspect ratio opens letterbox bars that must be cleared
+                if self.no_clear
+                    && !self.fills_window()
+                    && let Some(renderer) = &mut self.renderer
+                {
+                    log::warn!("Window no longer matches the animation, clearing every frame");
+                    renderer.set_clear_each_frame(true);
+                    self.no_clear = false;
+                }
+            }
+            winit::event::WindowEvent::KeyboardInput { event, .. }
+                if event.state == ElementState::Pressed && !event.repeat =>
+            {
+                match key_name(&event.logical_key) {
+                    Some(name) if self.debug && name == "p" => self.inspect_pixel(),
+                    Some(name) if self.debug && name == "m" => self.toggle_compression(),
+                    Some(name) if self.debug && name == "c" => self.log_compression_info(),
+                    Some(name) if self.is_reaction_key(&name) => self.trigger_reaction(&name),
+                    Some(name) if name == "+" || name == "=" => self.adjust_speed(SPEED_STEP),
+                    Some(name) if name == "-" => self.adjust_speed(1.0 / SPEED_STEP),
+                    Some(name) if name == "t" => self.toggle_always_on_top(),
+                    Some(name) if name == "space" => self.toggle_pause(),
+                    Some(name) if name == "s" => self.toggle_stats(),
+                    Some(name) if name == "arrowright" => self.step_frame(true),
+                    Some(name) if name == "arrowleft" => self.step_frame(false),
+                    Some(_) => {}
+                    None => {}
+                }
+            }
+            winit::event::WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => match state {
+                ElementState::Pressed => self.start_drag(),
+                // Some platforms report the release even though the window
+                // manager took the pointer; a click without a move isn't a drag
+                ElementState::Released if self.dragged_to.is_none() => self.dragging = false,
+                ElementState::Released => {}
+            },
+            winit::event::WindowEvent::Moved(position) if self.dragging => {
+                self.dragged_to = Some((position, Instant::now()));
+            }
+            winit::event::WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_position = Some(position);
+            }
+            winit::event::WindowEvent::CursorLeft { .. } => {
+                self.cursor_position = None;
+            }
+            winit::event::WindowEvent::RedrawRequested if !self.is_shutting_down => {
+                if self.play_duration_elapsed() {
+                    log::info!("Play duration elapsed, exiting");
+                    self.cleanup();
+                    event_loop.exit();
+                    return;
+                }
+
+                self.update();
+                self.update_stats_overlay();
+
+                if self.loops_finished() {
+                    log::info!("Played {} loops, exiting", self.loops_played);
+                    self.cleanup();
+                    event_loop.exit();
+                    return;
+                }
+
+                if let Err(err) = self.render() {
+                    log::error!("Render error: {}", err);
+                }
+
+                // Time the duration from the first frame actually shown
+                if self.playback_started.is_none() {
+                    self.playback_started = Some(Instant::now());
+                }
+
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if self.is_shutting_down {
+            return;
+        }
+
+        self.apply_config_changes(event_loop);
+        self.finish_drag();
+
+        let now = Instant::now();
+        let frame_duration = self.current_frame_duration();
+        if now.duration_since(self.last_frame_time) >= frame_duration
+            && let Some(window) = &self.window
+        {
+            window.request_redraw();
+            event_loop.set_control_flow(ControlFlow::WaitUntil(now + frame_duration));
+        }
+    }
+}
+
+/// Map a window position to the image pixel drawn there, matching the fragment
+/// shader: the grid of `tile` copies is centered and scaled to fit the window,
+/// or stretched over all of it with `fill`, and each copy is rotated and
+/// mirrored by `transform`. Positions in the bars around a fitted image map to
+/// nothing.
+pub fn window_to_image(
+    position: PhysicalPosition<f64>,
+    window_size: PhysicalSize<u32>,
+    image_size: (u32, u32),
+    tile: (u32, u32),
+    fill: bool,
+    transform: ImageTransform,
+) -> Option<(u32, u32)> {
+    if window_size.width == 0 || window_size.height == 0 {
+        return None;
+    }
+
+    let window_width = window_size.width as f64;
+    let window_height = window_size.height as f64;
+    let (left, top, drawn_width, drawn_height) = if fill {
+        (0.0, 0.0, window_width, window_height)
+    } else {
+        let (width, height) = transform.displayed_size(image_size);
+        let content_width = width as f64 * tile.0 as f64;
+        let content_height = height as f64 * tile.1 as f64;
+        let scale = (window_width / content_width).min(window_height / content_height);
+        let (width, height) = (content_width * scale, content_height * scale);
+        (
+            (window_width - width) / 2.0,
+            (window_height - height) / 2.0,
+            width,
+            height,
+        )
+    };
+
+    let u = (position.x - left) / drawn_width;
+    let v = (position.y - top) / drawn_height;
+    if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+        return None;
+    }
+
+    // Each copy is transformed about its own center
+    let (x, y) = transform.untransform(
+        (u * tile.0 as f64).fract() - 0.5,
+        (v * tile.1 as f64).fract() - 0.5,
+    );
+    let x = (x + 0.5) * image_size.0 as f64;
+    let y = (y + 0.5) * image_size.1 as f64;
+    Some((
+        (x as u32).min(image_size.0 - 1),
+        (y as u32).min(image_size.1 - 1),
+    ))
+}
+
+/// Time each frame is shown at `fps`, falling back to about 30 FPS for 0 or
+/// less and for rates too small to time
+pub fn create_frame_interval(fps: f64) -> Duration {
+    const DEFAULT: Duration = Duration::from_millis(33);
+    if fps > 0.0 {
+        Duration::try_from_secs_f64(1.0 / fps).unwrap_or(DEFAULT)
+    } else {
+        DEFAULT
+    }
+}
+
+/// Accept a frame rate of 0, which picks the default, or at least [`MIN_FPS`]
+pub fn check_fps(fps: f64) -> std::result::Result<f64, String> {
+    if fps == 0.0 || (fps.is_finite() && fps >= MIN_FPS) {
+        Ok(fps)
+    } else {
+        Err(format!(
+            "{} is not 0 or a frame rate of at least {}",
+            fps, MIN_FPS
+        ))
+    }
+}
+
+/// `interval` played back at `speed`, left as is if the result can't be timed
+fn at_speed(interval: Duration, speed: f64) -> Duration {
+    Duration::try_from_secs_f64(interval.as_secs_f64() / speed).unwrap_or(interval)
+}
+
+/// A random permutation of `0..count`
+pub fn shuffled_order(rng: &mut StdRng, count: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..count).collect();
+    order.shuffle(rng);
+    order
+}
+
+/// Frame after (or before) `index` in a sequence of `count` frames, wrapping
+/// around at either end
+pub fn step_index(index: usize, count: usize, forward: bool) -> usize {
+    if forward {
+        (index + 1) % count
+    } else {
+        (index + count - 1) % count
+    }
+}
+
+/// Name a key the way the `[reactions]` config refers to it: the lowercase
+/// character it types (`"w"`), or the lowercase winit name for other keys
+/// (`"space"`, `"f1"`, `"arrowup"`)
+pub fn key_name(key: &Key) -> Option<String> {
+    match key {
+        Key::Character(text) => Some(text.to_lowercase()),
+        Key::Named(named) => Some(format!("{:?}", named).to_lowercase()),
+        _ => None,
+    }
+}
+
+impl Drop for OverlayApplication {
+    fn drop(&mut self) {
+        log::debug!("Dropping OverlayApplication");
+        self.cleanup();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::keyboard::NamedKey;
+
+    #[test]
+    fn test_shuffled_order_is_seeded_permutation() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let first = shuffled_order(&mut rng, 20);
+        let second = shuffled_order(&mut rng, 20);
+
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+        assert_ne!(first, second, "each loop should get a new order");
+
+        let mut replay = StdRng::seed_from_u64(7);
+        assert_eq!(shuffled_order(&mut replay, 20), first);
+        assert_eq!(shuffled_order(&mut replay, 20), second);
+    }
+
+    #[test]
+    fn test_playing_reaction_keeps_its_key() {
+        let mut app = OverlayApplication::new(MediaSource::Embedded, OverlayOptions::default());
+        assert!(!app.is_reaction_key("space"));
+
+        // The playing reaction is out of `reactions`, but its key mustn't
+        // fall through to the built-in pause
+        app.active_reaction = Some(ActiveReaction {
+            key: "space".to_string(),
+            idle_sequence: None,
+            idle_frame_interval: Duration::ZERO,
+            idle_frame_index: 0,
+            idle_play_position: 0,
+        });
+        assert!(app.is_reaction_key("space"));
+        assert!(!app.is_reaction_key("t"));
+    }
+
+    #[test]
+    fn test_step_while_shuffled_before_first_advance() {
+        let mut app = OverlayApplication::new(
+            MediaSource::Embedded,
+            OverlayOptions {
+                shuffle_seed: Some(7),
+                ..OverlayOptions::default()
+            },
+        );
+        app.frame_count = 5;
+        app.paused = true;
+
+        app.step_frame(true);
+        assert_eq!(app.current_frame_index, 1);
+        assert_eq!(app.shuffled_order.len(), 5);
+        assert_eq!(app.frame_at_position(app.play_position), 1);
+
+        // Frames appended while paused extend the order before the next step
+        app.frame_count = 6;
+        app.step_frame(false);
+        assert_eq!(app.current_frame_index, 0);
+        assert_eq!(app.shuffled_order.len(), 6);
+    }
+
+    #[test]
+    fn test_create_frame_interval() {
+        assert_eq!(create_frame_interval(0.5), Duration::from_secs(2));
+        assert_eq!(
+            create_frame_interval(7.5),
+            Duration::from_secs_f64(1.0 / 7.5)
+        );
+        assert_eq!(create_frame_interval(0.0), Duration::from_millis(33));
+        assert_eq!(create_frame_interval(-5.0), Duration::from_millis(33));
+        assert_eq!(create_frame_interval(1e-20), Duration::from_millis(33));
+        assert_eq!(at_speed(Duration::MAX, *SPEED_RANGE.start()), Duration::MAX);
+    }
+
+    #[test]
+    fn test_window_to_image() {
+        let window = PhysicalSize::new(200, 100);
+        let at = |x, y| {
+            window_to_image(
+                PhysicalPosition::new(x, y),
+                window,
+                (100, 50),
+                (1, 1),
+                true,
+                ImageTransform::default(),
+            )
+        };
+
+        assert_eq!(at(0.0, 0.0), Some((0, 0)));
+        assert_eq!(at(199.9, 99.9), Some((99, 49)));
+        assert_eq!(at(51.0, 20.0), Some((25, 10)));
+        assert_eq!(at(200.0, 10.0), None);
+        assert_eq!(at(-1.0, 10.0), None);
+    }
+
+    #[test]
+    fn test_window_to_image_letterboxed() {
+        // A square image in a wide window is drawn 100x100 with 50px bars either side
+        let window = PhysicalSize::new(200, 100);
+        let at = |x, y, tile| {
+            window_to_image(
+                PhysicalPosition::new(x, y),
+                window,
+                (10, 10),
+                tile,
+                false,
+                ImageTransform::default(),
+            )
+        };
+
+        assert_eq!(at(49.0, 50.0, (1, 1)), None);
+        assert_eq!(at(50.0, 0.0, (1, 1)), Some((0, 0)));
+        assert_eq!(at(149.9, 99.9, (1, 1)), Some((9, 9)));
+        assert_eq!(at(150.0, 50.0, (1, 1)), None);
+
+        // Two copies side by side fill the whole window at 100x100 each
+        assert_eq!(at(0.0, 0.0, (2, 1)), Some((0, 0)));
+        assert_eq!(at(105.0, 50.0, (2, 1)), Some((0, 5)));
+    }
+
+    #[test]
+    fn test_window_to_image_transformed() {
+        // A 20x10 image turned a quarter clockwise is drawn 100x200 in a square window
+        let window = PhysicalSize::new(200, 200);
+        let at = |x, y, transform| {
+            window_to_image(
+                PhysicalPosition::new(x, y),
+                window,
+                (20, 10),
+                (1, 1),
+                false,
+                transform,
+            )
+        };
+        let rotated = ImageTransform {
+            quarter_turns: 1,
+            ..ImageTransform::default()
+        };
+
+        assert_eq!(at(49.0, 0.0, rotated), None);
+        // The image's top-left corner is now at the top right, and its
+        // bottom-left corner at the top left
+        assert_eq!(at(149.9, 0.0, rotated), Some((0, 0)));
+        assert_eq!(at(50.0, 0.0, rotated), Some((0, 9)));
+        assert_eq!(at(50.0, 199.9, rotated), Some((19, 9)));
+
+        let mirrored = ImageTransform {
+            flip_horizontal: true,
+            ..ImageTransform::default()
+        };
+        assert_eq!(at(0.0, 50.0, mirrored), Some((19, 0)));
+        assert_eq!(at(199.9, 149.9, mirrored), Some((0, 9)));
+
+        // Flips mirror what's shown, after rotating
+        let both = ImageTransform {
+            flip_vertical: true,
+            ..rotated
+        };
+        assert_eq!(at(149.9, 199.9, both), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_playback_modes() {
+        let frames = |mode: PlaybackMode, count: usize| -> Vec<usize> {
+            (0..mode.loop_length(count))
+                .map(|position| mode.frame_at(position, count))
+                .collect()
+        };
+
+        assert_eq!(frames(PlaybackMode::Forward, 4), vec![0, 1, 2, 3]);
+        assert_eq!(frames(PlaybackMode::Reverse, 4), vec![0, 3, 2, 1]);
+        assert_eq!(frames(PlaybackMode::PingPong, 4), vec![0, 1, 2, 3, 2, 1]);
+        assert_eq!(frames(PlaybackMode::PingPong, 2), vec![0, 1]);
+        assert_eq!(frames(PlaybackMode::PingPong, 1), vec![0]);
+        assert_eq!(frames(PlaybackMode::Reverse, 1), vec![0]);
+    }
+
+    #[test]
+    fn test_parse_window_position() {
+        assert_eq!("20,-40".parse(), Ok(WindowPosition::At(20, -40)));
+        assert_eq!(
+            "bottom-right".parse(),
+            Ok(WindowPosition::Anchor(Anchor::BottomRight))
+        );
+        assert!("20".parse::<WindowPosition>().is_err());
+        assert!("middle".parse::<WindowPosition>().is_err());
+    }
+
+    #[test]
+    fn test_monitor_selector() {
+        let names = [Some("DP-1".to_string()), None, Some("HDMI-A-1".to_string())];
+
+        let index: MonitorSelector = "2".parse().unwrap();
+        assert_eq!(index, MonitorSelector::Index(2));
+        assert_eq!(index.find(&names), Some(2));
+        assert_eq!(MonitorSelector::Index(3).find(&names), None);
+
+        let name: MonitorSelector = "HDMI-A-1".parse().unwrap();
+        assert_eq!(name.find(&names), Some(2));
+        assert_eq!(
+            MonitorSelector::Name("eDP-1".to_string()).find(&names),
+            None
+        );
+
+        assert!("  ".parse::<MonitorSelector>().is_err());
+    }
+
+    #[test]
+    fn test_place_window() {
+        let monitor = |x, y| MonitorRect {
+            position: PhysicalPosition::new(x, y),
+            size: PhysicalSize::new(1920, 1080),
+        };
+        // Primary first, with a second monitor to its left
+        let monitors = [monitor(0, 0), monitor(-1920, 0)];
+        let window = PhysicalSize::new(200, 100);
+        let place = |position| place_window(position, window, &monitors).unwrap();
+
+        assert_eq!(
+            place(WindowPosition::Anchor(Anchor::TopRight)),
+            (PhysicalPosition::new(1720, 0), false)
+        );
+        assert_eq!(
+            place(WindowPosition::Anchor(Anchor::Center)),
+            (PhysicalPosition::new(860, 490), false)
+        );
+        assert_eq!(
+            place(WindowPosition::At(-500, 300)),
+            (PhysicalPosition::new(-500, 300), false)
+        );
+        // Below the secondary monitor: pulled up onto it
+        assert_eq!(
+            place(WindowPosition::At(-1000, 5000)),
+            (PhysicalPosition::new(-1000, 980), true)
+        );
+        // Far off to the right: onto the primary's right edge
+        assert_eq!(
+            place(WindowPosition::At(9000, 50)),
+            (PhysicalPosition::new(1720, 50), true)
+        );
+
+        assert!(place_window(WindowPosition::Anchor(Anchor::TopLeft), window, &[]).is_none());
+    }
+
+    #[test]
+    fn test_key_name() {
+        assert_eq!(key_name(&Key::Character("W".into())), Some("w".to_string()));
+        assert_eq!(
+            key_name(&Key::Named(NamedKey::Space)),
+            Some("space".to_string())
+        );
+        assert_eq!(key_name(&Key::Named(NamedKey::F1)), Some("f1".to_string()));
+    }
+
+    #[test]
+    fn test_step_index_wraps() {
+        assert_eq!(step_index(0, 3, true), 1);
+        assert_eq!(step_index(2, 3, true), 0);
+        assert_eq!(step_index(0, 3, false), 2);
+        assert_eq!(step_index(1, 3, false), 0);
+        assert_eq!(step_index(0, 1, false), 0);
+    }
+
+    #[test]
+    fn test_easing_endpoints_and_monotonicity() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-9);
+
+            let samples: Vec<f64> = (0..=100).map(|i| easing.apply(i as f64 / 100.0)).collect();
+            assert!(samples.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+    }
+
+    #[test]
+    fn test_frame_at_wraps_and_visits_every_end() {
+        let interval = Duration::from_millis(100);
+
+        // Linear gives each frame one interval
+        assert_eq!(
+            Easing::Linear.frame_at(Duration::from_millis(250), interval, 10),
+            2
+        );
+        // The next loop starts over at frame 0
+        assert_eq!(
+            Easing::EaseInOut.frame_at(Duration::from_millis(1000), interval, 10),
+            0
+        );
+        // Ease-in-out lingers near the start and is halfway through at mid-loop
+        assert_eq!(
+            Easing::EaseInOut.frame_at(Duration::from_millis(200), interval, 10),
+            0
+        );
+        assert_eq!(
+            Easing::EaseInOut.frame_at(Duration::from_millis(500), interval, 10),
+            5
+        );
+        assert_eq!(
+            Easing::EaseInOut.frame_at(Duration::from_millis(999), interval, 10),
+            9
+        );
     }
 }
@@ -5,51 +5,27 @@ use std::sync::Arc;
 use wgpu::util::DeviceExt;
 use winit::window::Window;
 
-use crate::delta_compression::{CompressedSequence, DeltaCompressor};
-
-const VERTEX_SHADER: &str = r#"
-@vertex
-fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
-    // Quad vertices (triangle strip): full screen
-    var positions = array<vec2<f32>, 4>(
-        vec2<f32>(-1.0, -1.0),
-        vec2<f32>(1.0, -1.0),
-        vec2<f32>(-1.0, 1.0),
-        vec2<f32>(1.0, 1.0)
-    );
-    
-    var texcoords = array<vec2<f32>, 4>(
-        vec2<f32>(0.0, 1.0),
-        vec2<f32>(1.0, 1.0),
-        vec2<f32>(0.0, 0.0),
-        vec2<f32>(1.0, 0.0)
-    );
-    
-    return vec4<f32>(positions[vertex_index], 0.0, 1.0);
-}
-"#;
-
-const FRAGMENT_SHADER: &str = r#"
-@group(0) @binding(0)
-var t_diffuse: texture_2d<f32>;
-@group(0) @binding(1)
-var s_diffuse: sampler;
-@group(0) @binding(2)
-var<uniform> dimensions: vec4<f32>; // window_width, window_height, image_width, image_height
-
-@fragment
-fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
-    // Calculate texture coordinates based on actual dimensions
-    let tex_coords = vec2<f32>(
-        pos.x / dimensions.x,
-        pos.y / dimensions.y
-    );
-    
-    // Sample the texture
-    return textureSample(t_diffuse, s_diffuse, tex_coords);
-}
-"#;
+use crate::delta_compression::{
+    CompressedSequence, CompressionOptions, CompressionStats, DeltaCompressor, QualityMetrics,
+    apply_delta, compress_sequence_cpu,
+};
+use crate::media_loader::FrameStream;
+#[cfg(feature = "shader-hot-reload")]
+use crate::shader_reload::ShaderWatcher;
+use crate::shader_reload::shader_source;
+
+const VERTEX_SHADER: &str = include_str!("shaders/overlay_vertex.wgsl");
 
+const FRAGMENT_SHADER: &str = include_str!("shaders/overlay_fragment.wgsl");
+
+const STATS_SHADER: &str = include_str!("shaders/stats_overlay.wgsl");
+
+/// Window pixels per pixel of the stats text, and its gap from the window corner
+const STATS_SCALE: u32 = 2;
+const STATS_MARGIN: u32 = 8;
+
+/// Fragment shader uniforms. Display effects share the dimensions buffer so
+/// every frame's bind group picks them up without being rebuilt.
 #[repr(C)]
 #[derive(Copy, Clone, Pod, Zeroable)]
 struct Dimensions {
@@ -57,17 +33,250 @@ struct Dimensions {
     window_height: f32,
     image_width: f32,
     image_height: f32,
+    /// Color levels per channel, 0 disables posterization
+    posterize_levels: f32,
+    /// 1 stretches the image over the window, 0 fits it preserving aspect ratio
+    fill: f32,
+    /// Copies of the image across and down the window
+    tile: [f32; 2],
+    /// Multiplies every pixel's alpha, fading the whole overlay
+    opacity: f32,
+    /// Layer of the bound frame array texture to sample
+    layer: u32,
+    /// Rotation and flips, packed by [`ImageTransform::bits`]
+    transform: u32,
+    _padding: f32,
+}
+
+/// Placement of the stats text, matching `StatsUniforms` in the stats shader
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct StatsUniforms {
+    /// Left, top, width and height in window pixels
+    rect: [f32; 4],
+    window_size: [f32; 2],
+    scale: f32,
+    _padding: f32,
+}
+
+/// Statistics text drawn over the top-left corner of the animation
+struct StatsLayer {
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+}
+
+/// How the animation is blended with what's behind the window.
+///
+/// The surface uses premultiplied alpha, so the compositor computes
+/// `out.rgb + desktop * (1 - out.a)` for every pixel we present.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum BlendMode {
+    /// Regular alpha blending (`src * a + dst * (1 - a)`)
+    #[default]
+    Over,
+    /// Adds the image on top of the desktop. Output alpha stays at zero so the
+    /// compositor keeps the desktop at full strength underneath, making it glow
+    Additive,
+    /// Darkens the desktop by the image's luminance; white pixels leave it untouched
+    Multiply,
+    /// Lightens the desktop, covering it only in proportion to the image's luminance
+    Screen,
+}
+
+impl BlendMode {
+    fn blend_state(self) -> wgpu::BlendState {
+        let over_alpha = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+            operation: wgpu::BlendOperation::Add,
+        };
+
+        match self {
+            BlendMode::Over => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: over_alpha,
+            },
+            BlendMode::Additive => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Zero,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            },
+            // The fragment shader already outputs premultiplied color for these
+            BlendMode::Multiply | BlendMode::Screen => wgpu::BlendState {
+                color: over_alpha,
+                alpha: over_alpha,
+            },
+        }
+    }
+
+    /// Value of the `BLEND_MODE` override constant in the fragment shader
+    fn shader_constant(self) -> f64 {
+        match self {
+            BlendMode::Over => 0.0,
+            BlendMode::Additive => 1.0,
+            BlendMode::Multiply => 2.0,
+            BlendMode::Screen => 3.0,
+        }
+    }
+}
+
+/// When finished frames reach the screen, trading latency against tearing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum PresentMode {
+    /// Wait for vertical blank; never tears. Supported everywhere
+    #[default]
+    Fifo,
+    /// Replace the queued frame with the newest one; low latency without tearing
+    Mailbox,
+    /// Present right away; lowest latency, may tear
+    Immediate,
+}
+
+impl PresentMode {
+    fn to_wgpu(self) -> wgpu::PresentMode {
+        match self {
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+        }
+    }
+}
+
+/// Graphics API to render with, to work around drivers that misbehave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Backend {
+    Vulkan,
+    /// OpenGL or OpenGL ES
+    Gl,
+    Dx12,
+    Metal,
+}
+
+impl Backend {
+    fn to_wgpu(self) -> wgpu::Backends {
+        match self {
+            Backend::Vulkan => wgpu::Backends::VULKAN,
+            Backend::Gl => wgpu::Backends::GL,
+            Backend::Dx12 => wgpu::Backends::DX12,
+            Backend::Metal => wgpu::Backends::METAL,
+        }
+    }
+}
+
+/// Which GPU to prefer on systems with more than one, such as hybrid laptops
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PowerPreference {
+    /// The integrated GPU
+    Low,
+    /// The discrete GPU
+    High,
+}
+
+impl PowerPreference {
+    fn to_wgpu(self) -> wgpu::PowerPreference {
+        match self {
+            PowerPreference::Low => wgpu::PowerPreference::LowPower,
+            PowerPreference::High => wgpu::PowerPreference::HighPerformance,
+        }
+    }
+}
+
+/// How the renderer picks its adapter. The defaults try every backend and let
+/// wgpu choose the GPU.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AdapterOptions {
+    pub backend: Option<Backend>,
+    pub power_preference: Option<PowerPreference>,
+}
+
+/// Rotation and mirroring of the displayed frames. Flips apply after the
+/// rotation, so they mirror along the window's axes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImageTransform {
+    /// Clockwise quarter turns, 0 to 3
+    pub quarter_turns: u32,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+}
+
+impl ImageTransform {
+    /// Whether the frames are turned on their side, swapping width and height
+    pub fn swaps_axes(self) -> bool {
+        self.quarter_turns % 2 == 1
+    }
+
+    /// Size of a `width` x `height` frame as displayed
+    pub fn displayed_size(self, (width, height): (u32, u32)) -> (u32, u32) {
+        if self.swaps_axes() {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Map an offset from the center of the displayed frame back onto the
+    /// untransformed frame, as the fragment shader does
+    pub fn untransform(self, x: f64, y: f64) -> (f64, f64) {
+        let x = if self.flip_horizontal { -x } else { x };
+        let y = if self.flip_vertical { -y } else { y };
+        match self.quarter_turns % 4 {
+            1 => (y, -x),
+            2 => (-x, -y),
+            3 => (-y, x),
+            _ => (x, y),
+        }
+    }
+
+    /// Quarter turns in the low two bits, then the horizontal and vertical flips
+    fn bits(self) -> u32 {
+        (self.quarter_turns % 4)
+            | (u32::from(self.flip_horizontal) << 2)
+            | (u32::from(self.flip_vertical) << 3)
+    }
+}
+
+/// Frames uploaded as the layers of one texture, bound once
+pub struct FrameArray {
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
 }
 
 pub enum SequenceType {
     Uncompressed {
-        texture_bind_groups: Vec<wgpu::BindGroup>,
+        /// Every array but the last is full, holding the device's maximum
+        /// number of layers
+        frame_arrays: Vec<FrameArray>,
+        frame_count: usize,
     },
     Compressed {
-        compressed_sequence: CompressedSequence,
+        compressed_sequence: Box<CompressedSequence>,
         current_frame_texture: wgpu::Texture,
         current_frame_bind_group: wgpu::BindGroup,
         reconstructed_frame: Option<RgbaImage>,
+        /// Sequence index that `reconstructed_frame` holds
+        reconstructed_index: usize,
+        /// The first frames, reconstructed once at load into their own textures
+        cached_bind_groups: Vec<wgpu::BindGroup>,
+        /// Last cached frame, so playback leaving the cache only applies one delta
+        cache_tail: Option<RgbaImage>,
+    },
+    /// Frames decoded from disk as they're needed, shown from a single texture
+    Streamed {
+        stream: FrameStream,
+        current_frame: FrameArray,
+        /// Sequence index that `current_frame` holds
+        uploaded_index: usize,
     },
 }
 
@@ -77,20 +286,74 @@ pub struct Renderer {
     surface: Option<wgpu::Surface<'static>>,
     pipeline: wgpu::RenderPipeline,
     bind_group_layout: wgpu::BindGroupLayout,
+    /// Most frames a single array texture can hold on this device
+    max_array_layers: u32,
     sequence_type: Option<SequenceType>,
     current_texture_index: usize,
     config: wgpu::SurfaceConfiguration,
+    /// Present modes the surface supports on this adapter
+    present_modes: Vec<wgpu::PresentMode>,
+    /// Compositing modes the surface supports, telling whether it can be transparent
+    alpha_modes: Vec<wgpu::CompositeAlphaMode>,
     dimensions_buffer: wgpu::Buffer,
     current_dimensions: Dimensions,
 
     delta_compressor: Option<DeltaCompressor>,
     sampler: wgpu::Sampler,
+    /// Linear, or nearest for crisp pixel art
+    filter_mode: wgpu::FilterMode,
+
+    /// Clear to the background before drawing. Only safe to turn off when every
+    /// frame is opaque and blended "over", since the quad then overwrites the
+    /// whole surface anyway.
+    clear_each_frame: bool,
+    /// Premultiplied clear color, in the surface's color space
+    background: wgpu::Color,
+
+    /// Compressed sequences that don't shrink at least this much are kept
+    /// uncompressed, since reconstruction isn't free
+    min_compression_ratio: Option<f32>,
+
+    /// Keyframe spacing and delta quantization used when compressing
+    compression_options: CompressionOptions,
+
+    /// Compressed frames to reconstruct at load and keep as textures
+    cache_frames: usize,
+
+    /// Give frame array textures a full mip chain, so frames shown smaller
+    /// than their size don't alias
+    mipmaps: bool,
+
+    /// Compare every reconstructed frame to its original after compressing
+    measure_quality: bool,
+    compression_quality: Option<QualityMetrics>,
+
+    stats_pipeline: wgpu::RenderPipeline,
+    stats_bind_group_layout: wgpu::BindGroupLayout,
+    stats_buffer: wgpu::Buffer,
+    stats: Option<StatsLayer>,
+
+    #[cfg(feature = "shader-hot-reload")]
+    blend_mode: BlendMode,
+
+    #[cfg(feature = "shader-hot-reload")]
+    shader_watcher: Option<ShaderWatcher>,
 }
 
 impl Renderer {
-    pub async fn new(window: Arc<Window>) -> Result<Self> {
+    /// Create a renderer for `window`. With `nearest` frames are scaled with
+    /// nearest-neighbor sampling, keeping pixel art crisp.
+    pub async fn new(
+        window: Arc<Window>,
+        blend_mode: BlendMode,
+        nearest: bool,
+        adapter_options: AdapterOptions,
+    ) -> Result<Self> {
+        let backends = adapter_options
+            .backend
+            .map_or(wgpu::Backends::all(), Backend::to_wgpu);
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
+            backends,
             ..Default::default()
         });
 
@@ -98,18 +361,29 @@ impl Renderer {
 
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
+                power_preference: adapter_options
+                    .power_preference
+                    .map_or(wgpu::PowerPreference::default(), PowerPreference::to_wgpu),
                 force_fallback_adapter: false,
                 compatible_surface: Some(&surface),
             })
             .await
-            .map_err(|_| anyhow::anyhow!("Failed to find an appropriate adapter"))?;
+            .map_err(|_| {
+                Self::log_adapters(&instance, backends, &surface);
+                anyhow::anyhow!("Failed to find an appropriate adapter")
+            })?;
+        let info = adapter.get_info();
+        log::info!("Rendering on {} ({:?})", info.name, info.backend);
 
         let (device, queue) = adapter
             .request_device(&wgpu::DeviceDescriptor {
                 label: Some("Overlay Device"),
                 required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default(),
+                // Longer sequences fit in fewer frame arrays where the adapter allows it
+                required_limits: wgpu::Limits {
+                    max_texture_array_layers: adapter.limits().max_texture_array_layers,
+                    ..Default::default()
+                },
                 memory_hints: wgpu::MemoryHints::default(),
                 trace: wgpu::Trace::Off,
             })
@@ -135,7 +409,8 @@ impl Renderer {
             height: size.height,
             present_mode: wgpu::PresentMode::Fifo,
             desired_maximum_frame_latency: 2,
-            alpha_mode: wgpu::CompositeAlphaMode::PreMultiplied,
+            // Configuring an unsupported mode is a validation error, so check first
+            alpha_mode: select_alpha_mode(false, &surface_caps.alpha_modes),
             view_formats: vec![],
         };
 
@@ -147,6 +422,13 @@ impl Renderer {
             window_height: size.height as f32,
             image_width: size.width as f32,
             image_height: size.height as f32,
+            posterize_levels: 0.0,
+            fill: 0.0,
+            tile: [1.0, 1.0],
+            opacity: 1.0,
+            layer: 0,
+            transform: 0,
+            _padding: 0.0,
         };
 
         // Create dimensions buffer
@@ -156,6 +438,20 @@ impl Renderer {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
+        let (filter_mode, sample_type, sampler_type) = if nearest {
+            (
+                wgpu::FilterMode::Nearest,
+                wgpu::TextureSampleType::Float { filterable: false },
+                wgpu::SamplerBindingType::NonFiltering,
+            )
+        } else {
+            (
+                wgpu::FilterMode::Linear,
+                wgpu::TextureSampleType::Float { filterable: true },
+                wgpu::SamplerBindingType::Filtering,
+            )
+        };
+
         let bind_group_layout =
             device_arc.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("Texture Bind Group Layout"),
@@ -164,8 +460,8 @@ impl Renderer {
                         binding: 0,
                         visibility: wgpu::ShaderStages::FRAGMENT,
                         ty: wgpu::BindingType::Texture {
-                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type,
+                            view_dimension: wgpu::TextureViewDimension::D2Array,
                             multisampled: false,
                         },
                         count: None,
@@ -173,7 +469,7 @@ impl Renderer {
                     wgpu::BindGroupLayoutEntry {
                         binding: 1,
                         visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        ty: wgpu::BindingType::Sampler(sampler_type),
                         count: None,
                     },
                     wgpu::BindGroupLayoutEntry {
@@ -189,23 +485,177 @@ impl Renderer {
                 ],
             });
 
-        let pipeline_layout = device_arc.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        let pipeline = Self::create_render_pipeline(
+            &device_arc,
+            &bind_group_layout,
+            config.format,
+            blend_mode,
+        );
+
+        let stats_bind_group_layout =
+            device_arc.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Stats Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+        let stats_pipeline =
+            Self::create_stats_pipeline(&device_arc, &stats_bind_group_layout, config.format);
+        let stats_buffer = device_arc.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Stats Buffer"),
+            size: std::mem::size_of::<StatsUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Create reusable sampler
+        let sampler =
+            Self::create_sampler(&device_arc, wgpu::AddressMode::ClampToEdge, filter_mode);
+
+        // Initialize delta compressor, compressing on the CPU where the adapter can't run it
+        let delta_compressor = match DeltaCompressor::check_support(&adapter)
+            .and_then(|()| DeltaCompressor::new(device_arc.clone(), queue_arc.clone()))
+        {
+            Ok(compressor) => Some(compressor),
+            Err(e) => {
+                log::warn!("GPU delta compression unavailable, using the CPU: {}", e);
+                None
+            }
+        };
+
+        #[cfg(feature = "shader-hot-reload")]
+        let shader_watcher = match ShaderWatcher::new() {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                log::warn!("Shader hot-reload disabled: {}", e);
+                None
+            }
+        };
+
+        let max_array_layers = device_arc.limits().max_texture_array_layers;
+
+        Ok(Self {
+            device: device_arc,
+            queue: queue_arc,
+            surface: Some(surface), // Wrap in Option
+            pipeline,
+            bind_group_layout,
+            max_array_layers,
+            sequence_type: None,
+            current_texture_index: 0,
+            config,
+            present_modes: surface_caps.present_modes,
+            alpha_modes: surface_caps.alpha_modes,
+            dimensions_buffer,
+            current_dimensions,
+            delta_compressor,
+            sampler,
+            filter_mode,
+            clear_each_frame: true,
+            background: wgpu::Color::TRANSPARENT,
+            min_compression_ratio: None,
+            compression_options: CompressionOptions::default(),
+            cache_frames: 0,
+            mipmaps: false,
+            measure_quality: false,
+            compression_quality: None,
+            stats_pipeline,
+            stats_bind_group_layout,
+            stats_buffer,
+            stats: None,
+            #[cfg(feature = "shader-hot-reload")]
+            blend_mode,
+            #[cfg(feature = "shader-hot-reload")]
+            shader_watcher,
+        })
+    }
+
+    /// List every adapter the instance can see, to tell why none was suitable
+    fn log_adapters(instance: &wgpu::Instance, backends: wgpu::Backends, surface: &wgpu::Surface) {
+        let adapters = instance.enumerate_adapters(backends);
+        if adapters.is_empty() {
+            log::error!("No graphics adapters found for {:?}", backends);
+            return;
+        }
+
+        log::error!("Available graphics adapters:");
+        for adapter in adapters {
+            let info = adapter.get_info();
+            log::error!(
+                "  {} ({:?}, {:?}){}",
+                info.name,
+                info.backend,
+                info.device_type,
+                if adapter.is_surface_supported(surface) {
+                    ""
+                } else {
+                    ", can't present to the window"
+                }
+            );
+        }
+    }
+
+    fn create_sampler(
+        device: &wgpu::Device,
+        address_mode: wgpu::AddressMode,
+        filter_mode: wgpu::FilterMode,
+    ) -> wgpu::Sampler {
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            mag_filter: filter_mode,
+            min_filter: filter_mode,
+            mipmap_filter: filter_mode,
+            ..Default::default()
+        })
+    }
+
+    fn create_render_pipeline(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        format: wgpu::TextureFormat,
+        blend_mode: BlendMode,
+    ) -> wgpu::RenderPipeline {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Render Pipeline Layout"),
-            bind_group_layouts: &[&bind_group_layout],
+            bind_group_layouts: &[bind_group_layout],
             push_constant_ranges: &[],
         });
 
-        let vertex_shader = device_arc.create_shader_module(wgpu::ShaderModuleDescriptor {
+        let vertex_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Vertex Shader"),
-            source: wgpu::ShaderSource::Wgsl(VERTEX_SHADER.into()),
+            source: wgpu::ShaderSource::Wgsl(shader_source("overlay_vertex.wgsl", VERTEX_SHADER)),
         });
 
-        let fragment_shader = device_arc.create_shader_module(wgpu::ShaderModuleDescriptor {
+        let fragment_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Fragment Shader"),
-            source: wgpu::ShaderSource::Wgsl(FRAGMENT_SHADER.into()),
+            source: wgpu::ShaderSource::Wgsl(shader_source(
+                "overlay_fragment.wgsl",
+                FRAGMENT_SHADER,
+            )),
         });
 
-        let pipeline = device_arc.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
             layout: Some(&pipeline_layout),
             vertex: wgpu::VertexState {
@@ -233,100 +683,449 @@ impl Renderer {
                 module: &fragment_shader,
                 entry_point: Some("fs_main"),
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState {
-                        color: wgpu::BlendComponent {
-                            src_factor: wgpu::BlendFactor::SrcAlpha,
-                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                            operation: wgpu::BlendOperation::Add,
-                        },
-                        alpha: wgpu::BlendComponent {
-                            src_factor: wgpu::BlendFactor::One,
-                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                            operation: wgpu::BlendOperation::Add,
-                        },
-                    }),
+                    format,
+                    blend: Some(blend_mode.blend_state()),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &[("BLEND_MODE", blend_mode.shader_constant())],
+                    ..Default::default()
+                },
             }),
             multiview: None,
             cache: None,
-        });
+        })
+    }
 
-        // Create reusable sampler
-        let sampler = device_arc.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Linear,
-            ..Default::default()
+    /// Pipeline drawing the stats text over the animation, blended "over"
+    /// whatever blend mode the animation itself uses
+    fn create_stats_pipeline(
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Stats Pipeline Layout"),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
         });
 
-        // Initialize delta compressor
-        let delta_compressor = Some(DeltaCompressor::new(device_arc.clone(), queue_arc.clone())?);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Stats Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_source("stats_overlay.wgsl", STATS_SHADER)),
+        });
 
-        Ok(Self {
-            device: device_arc,
-            queue: queue_arc,
-            surface: Some(surface), // Wrap in Option
-            pipeline,
-            bind_group_layout,
-            sequence_type: None,
-            current_texture_index: 0,
-            config,
-            dimensions_buffer,
-            current_dimensions,
-            delta_compressor,
-            sampler,
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Stats Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(BlendMode::Over.blend_state()),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            multiview: None,
+            cache: None,
         })
     }
 
-    /// Properly cleanup the renderer resources
+    /// Show `image` in the top-left corner of the window, scaled up for
+    /// legibility, or remove it with `None`
+    pub fn set_stats_image(&mut self, image: Option<&RgbaImage>) {
+        let Some(image) = image else {
+            self.stats = None;
+            return;
+        };
+
+        let size = wgpu::Extent3d {
+            width: image.width(),
+            height: image.height(),
+            depth_or_array_layers: 1,
+        };
+        if self
+            .stats
+            .as_ref()
+            .is_none_or(|stats| stats.texture.size() != size)
+        {
+            let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Stats Texture"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Stats Bind Group"),
+                layout: &self.stats_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: self.stats_buffer.as_entire_binding(),
+                    },
+                ],
+            });
+            self.stats = Some(StatsLayer {
+                texture,
+                bind_group,
+            });
+            self.write_stats_uniforms();
+        }
+
+        if let Some(stats) = &self.stats {
+            self.queue.write_texture(
+                stats.texture.as_image_copy(),
+                image,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * image.width()),
+                    rows_per_image: Some(image.height()),
+                },
+                size,
+            );
+        }
+    }
+
+    /// Place the stats text for the current window size
+    fn write_stats_uniforms(&self) {
+        let Some(stats) = &self.stats else {
+            return;
+        };
+
+        let uniforms = StatsUniforms {
+            rect: [
+                STATS_MARGIN as f32,
+                STATS_MARGIN as f32,
+                (stats.texture.width() * STATS_SCALE) as f32,
+                (stats.texture.height() * STATS_SCALE) as f32,
+            ],
+            window_size: [self.config.width as f32, self.config.height as f32],
+            scale: STATS_SCALE as f32,
+            _padding: 0.0,
+        };
+        self.queue
+            .write_buffer(&self.stats_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+    }
+
+    /// Rebuild pipelines whose WGSL files changed on disk since the last frame
+    #[cfg(feature = "shader-hot-reload")]
+    fn reload_changed_shaders(&mut self) {
+        let changed = match &self.shader_watcher {
+            Some(watcher) => watcher.changed_shaders(),
+            None => return,
+        };
+
+        if changed.is_empty() {
+            return;
+        }
+
+        if changed.iter().any(|name| name.starts_with("overlay_")) {
+            // Catch WGSL errors instead of letting the device panic, so a typo
+            // keeps the previous pipeline running
+            self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+            let pipeline = Self::create_render_pipeline(
+                &self.device,
+                &self.bind_group_layout,
+                self.config.format,
+                self.blend_mode,
+            );
+            match pollster::block_on(self.device.pop_error_scope()) {
+                None => {
+                    self.pipeline = pipeline;
+                    log::info!("Reloaded overlay shaders");
+                }
+                Some(e) => log::error!("Failed to reload overlay shaders: {}", e),
+            }
+        }
+
+        if changed
+            .iter()
+            .any(|name| name == "delta_calculate.wgsl" || name == "frame_reconstruct.wgsl")
+            && let Some(ref mut compressor) = self.delta_compressor
+        {
+            match compressor.reload_shaders() {
+                Ok(()) => log::info!("Reloaded delta compression shaders"),
+                Err(e) => log::error!("Failed to reload delta compression shaders: {}", e),
+            }
+        }
+    }
+
+    /// Release GPU resources ahead of the window being destroyed.
+    ///
+    /// Frame textures and bind groups go first, then the delta compressor and its
+    /// working textures. The device is then polled until all submitted work has
+    /// finished, so nothing still references those resources when the surface
+    /// is dropped last. Safe to call more than once; `Drop` calls it too.
     pub fn cleanup(&mut self) {
         log::info!("Cleaning up renderer resources");
 
-        // Clear sequence type which contains GPU resources
-        self.sequence_type = None;
+        // Clear sequence type which contains GPU resources
+        self.sequence_type = None;
+
+        // Clear delta compressor
+        self.delta_compressor = None;
+
+        // Some drivers complain when the surface goes while work is in flight
+        if let Err(e) = self.device.poll(wgpu::MaintainBase::Wait) {
+            log::warn!("Failed to wait for the GPU to finish: {}", e);
+        }
+
+        // Drop the surface before the window is destroyed
+        if let Some(surface) = self.surface.take() {
+            drop(surface);
+            log::debug!("Surface dropped");
+        }
+
+        log::info!("Renderer cleanup complete");
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        self.config.width = width;
+        self.config.height = height;
+
+        if let Some(ref surface) = self.surface {
+            surface.configure(&self.device, &self.config);
+        }
+
+        // Update dimensions
+        self.current_dimensions.window_width = width as f32;
+        self.current_dimensions.window_height = height as f32;
+
+        // Update the buffer
+        self.queue.write_buffer(
+            &self.dimensions_buffer,
+            0,
+            bytemuck::cast_slice(&[self.current_dimensions]),
+        );
+        self.write_stats_uniforms();
+
+        log::info!("Resized to {}x{}", width, height);
+    }
+
+    /// The frame last reconstructed for display, when the sequence is delta compressed
+    pub fn reconstructed_frame(&self) -> Option<&RgbaImage> {
+        match &self.sequence_type {
+            Some(SequenceType::Compressed {
+                reconstructed_frame,
+                ..
+            }) => reconstructed_frame.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Bytes of frame data held for the loaded sequence: every texture when
+    /// uncompressed, the deltas plus the displayed and cached frames when
+    /// compressed, or the decode window plus the displayed frame when streamed
+    pub fn frame_memory_usage(&self) -> usize {
+        let width = self.current_dimensions.image_width as u32;
+        let height = self.current_dimensions.image_height as u32;
+        let frame_size = mip_chain_bytes(width, height, 1);
+        match &self.sequence_type {
+            Some(SequenceType::Uncompressed {
+                frame_arrays,
+                frame_count,
+            }) => {
+                let levels = frame_arrays
+                    .first()
+                    .map_or(1, |array| array.texture.mip_level_count());
+                frame_count * mip_chain_bytes(width, height, levels)
+            }
+            Some(SequenceType::Compressed {
+                compressed_sequence,
+                cached_bind_groups,
+                ..
+            }) => compressed_sequence.memory_usage() + frame_size * (1 + cached_bind_groups.len()),
+            Some(SequenceType::Streamed {
+                stream,
+                current_frame,
+                ..
+            }) => {
+                frame_size * stream.window()
+                    + mip_chain_bytes(width, height, current_frame.texture.mip_level_count())
+            }
+            None => 0,
+        }
+    }
+
+    /// Statistics for the loaded sequence, if it's delta compressed
+    pub fn compression_stats(&self) -> Option<CompressionStats> {
+        match &self.sequence_type {
+            Some(SequenceType::Compressed {
+                compressed_sequence,
+                ..
+            }) => Some(CompressionStats {
+                quality: self.compression_quality,
+                ..compressed_sequence.stats()
+            }),
+            _ => None,
+        }
+    }
+
+    /// Quantize displayed colors to `levels` per channel, or show full color with `None`
+    pub fn set_posterize_levels(&mut self, levels: Option<u32>) {
+        self.current_dimensions.posterize_levels = levels.map_or(0.0, |levels| levels as f32);
+        self.queue.write_buffer(
+            &self.dimensions_buffer,
+            0,
+            bytemuck::cast_slice(&[self.current_dimensions]),
+        );
+    }
+
+    /// Repeat the image in a `columns` x `rows` grid across the window. The
+    /// sampler wraps when tiling, so call this before loading a sequence for
+    /// its bind groups to pick that up.
+    pub fn set_tile(&mut self, columns: u32, rows: u32) {
+        self.current_dimensions.tile = [columns as f32, rows as f32];
+        self.queue.write_buffer(
+            &self.dimensions_buffer,
+            0,
+            bytemuck::cast_slice(&[self.current_dimensions]),
+        );
+
+        let address_mode = if (columns, rows) == (1, 1) {
+            wgpu::AddressMode::ClampToEdge
+        } else {
+            wgpu::AddressMode::Repeat
+        };
+        self.sampler = Self::create_sampler(&self.device, address_mode, self.filter_mode);
+    }
+
+    /// Stretch the image over the whole window instead of fitting it centered
+    /// with its aspect ratio intact
+    pub fn set_fill(&mut self, fill: bool) {
+        self.current_dimensions.fill = if fill { 1.0 } else { 0.0 };
+        self.queue.write_buffer(
+            &self.dimensions_buffer,
+            0,
+            bytemuck::cast_slice(&[self.current_dimensions]),
+        );
+    }
+
+    /// Fade the whole overlay, from 0.0 (invisible) to 1.0 (as authored)
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.current_dimensions.opacity = opacity.clamp(0.0, 1.0);
+        self.queue.write_buffer(
+            &self.dimensions_buffer,
+            0,
+            bytemuck::cast_slice(&[self.current_dimensions]),
+        );
+    }
+
+    /// Rotate and mirror the displayed frames
+    pub fn set_transform(&mut self, transform: ImageTransform) {
+        self.current_dimensions.transform = transform.bits();
+        self.queue.write_buffer(
+            &self.dimensions_buffer,
+            0,
+            bytemuck::cast_slice(&[self.current_dimensions]),
+        );
+    }
+
+    pub fn set_min_compression_ratio(&mut self, ratio: Option<f32>) {
+        self.min_compression_ratio = ratio;
+    }
+
+    pub fn set_compression_options(&mut self, options: CompressionOptions) {
+        self.compression_options = options;
+    }
+
+    /// Reconstruct the first `frames` frames of compressed sequences once at load,
+    /// so they play without a compute dispatch and readback each time
+    pub fn set_cache_frames(&mut self, frames: usize) {
+        self.cache_frames = frames;
+    }
+
+    /// Generate mipmaps for uncompressed and streamed frames uploaded from now
+    /// on. Costs a third more texture memory and some time at upload.
+    pub fn set_mipmaps(&mut self, mipmaps: bool) {
+        self.mipmaps = mipmaps;
+    }
 
-        // Clear delta compressor
-        self.delta_compressor = None;
+    /// Measure how closely compressed sequences reproduce their original frames
+    /// once compressed. This plays the whole sequence through, roughly doubling
+    /// the time compression takes.
+    pub fn set_measure_quality(&mut self, measure: bool) {
+        self.measure_quality = measure;
+    }
 
-        // Drop the surface before the window is destroyed
-        if let Some(surface) = self.surface.take() {
-            drop(surface);
-            log::debug!("Surface dropped");
-        }
+    /// Whether the loaded sequence is held as deltas
+    pub fn is_compressed(&self) -> bool {
+        matches!(self.sequence_type, Some(SequenceType::Compressed { .. }))
+    }
 
-        log::info!("Renderer cleanup complete");
+    /// Whether frames are decoded from disk as they're shown
+    pub fn is_streamed(&self) -> bool {
+        matches!(self.sequence_type, Some(SequenceType::Streamed { .. }))
     }
 
-    pub fn resize(&mut self, width: u32, height: u32) {
-        if width == 0 || height == 0 {
-            return;
-        }
+    /// Present frames with `mode`, falling back to vsync'd FIFO with a
+    /// warning when the surface doesn't support it
+    pub fn set_present_mode(&mut self, mode: PresentMode) {
+        let selected = select_present_mode(mode, &self.present_modes);
+        log::info!("Using present mode {:?}", selected);
 
-        self.config.width = width;
-        self.config.height = height;
+        if selected != self.config.present_mode {
+            self.config.present_mode = selected;
+            if let Some(surface) = &self.surface {
+                surface.configure(&self.device, &self.config);
+            }
+        }
+    }
 
-        if let Some(ref surface) = self.surface {
-            surface.configure(&self.device, &self.config);
+    /// Present opaque frames, showing the background color (black unless set)
+    /// where the animation is transparent
+    pub fn set_opaque(&mut self, opaque: bool) {
+        let selected = select_alpha_mode(opaque, &self.alpha_modes);
+        if selected != self.config.alpha_mode {
+            self.config.alpha_mode = selected;
+            if let Some(surface) = &self.surface {
+                surface.configure(&self.device, &self.config);
+            }
         }
+    }
 
-        // Update dimensions
-        self.current_dimensions.window_width = width as f32;
-        self.current_dimensions.window_height = height as f32;
+    /// Whether the compositor can show the desktop through transparent pixels
+    pub fn is_transparent(&self) -> bool {
+        self.config.alpha_mode != wgpu::CompositeAlphaMode::Opaque
+    }
 
-        // Update the buffer
-        self.queue.write_buffer(
-            &self.dimensions_buffer,
-            0,
-            bytemuck::cast_slice(&[self.current_dimensions]),
-        );
+    /// Fill the window with this straight-alpha sRGB color behind the animation
+    /// instead of leaving it transparent
+    pub fn set_background(&mut self, rgba: [u8; 4]) {
+        self.background = clear_color(rgba, self.config.format.is_srgb());
+    }
 
-        log::info!("Resized to {}x{}", width, height);
+    /// Choose whether each frame starts from a clear to the background or from
+    /// the previous surface contents
+    pub fn set_clear_each_frame(&mut self, clear: bool) {
+        self.clear_each_frame = clear;
     }
 
     // New method to preload all images at once
@@ -356,77 +1155,241 @@ impl Renderer {
             images.len()
         );
 
-        let mut texture_bind_groups = Vec::new();
+        // One texture per array instead of one per frame keeps a long sequence
+        // down to a handful of bind groups
+        let layers = self.max_array_layers as usize;
+        let frame_arrays: Vec<FrameArray> = images
+            .chunks(layers)
+            .enumerate()
+            .map(|(i, frames)| {
+                let frame_array = self.create_frame_array(i, frames.len() as u32);
+                for (layer, image) in frames.iter().enumerate() {
+                    Self::write_frame_layer(&self.queue, &frame_array.texture, layer as u32, image);
+                }
+                frame_array
+            })
+            .collect();
+
+        self.sequence_type = Some(SequenceType::Uncompressed {
+            frame_arrays,
+            frame_count: images.len(),
+        });
+
+        self.current_texture_index = 0;
+        log::info!(
+            "Preloaded {} images to GPU memory (uncompressed)",
+            images.len()
+        );
+    }
+
+    /// Play frames from `stream`, uploading each one as it's shown instead of
+    /// keeping the whole sequence on the GPU
+    pub fn load_stream(&mut self, stream: FrameStream) {
+        self.sequence_type = None;
+
+        let (width, height) = stream.dimensions();
+        self.current_dimensions.image_width = width as f32;
+        self.current_dimensions.image_height = height as f32;
+        self.queue.write_buffer(
+            &self.dimensions_buffer,
+            0,
+            bytemuck::cast_slice(&[self.current_dimensions]),
+        );
+
+        let current_frame = self.create_frame_array(0, 1);
+        Self::write_frame_layer(&self.queue, &current_frame.texture, 0, &stream.frame(0));
+        log::info!("Streaming {} frames from disk", stream.count());
+
+        self.sequence_type = Some(SequenceType::Streamed {
+            stream,
+            current_frame,
+            uploaded_index: 0,
+        });
+        self.current_texture_index = 0;
+    }
+
+    /// Create an array texture with room for `layers` frames of the current
+    /// size, with a full mip chain when mipmaps are on
+    fn create_frame_array(&self, i: usize, layers: u32) -> FrameArray {
+        let width = self.current_dimensions.image_width as u32;
+        let height = self.current_dimensions.image_height as u32;
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&format!("Frame Array Texture {}", i)),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: layers,
+            },
+            mip_level_count: if self.mipmaps {
+                mip_level_count(width, height)
+            } else {
+                1
+            },
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let bind_group = self.create_texture_bind_group(&format!("Frame Array {}", i), &texture);
+
+        FrameArray {
+            texture,
+            bind_group,
+        }
+    }
 
-        for (i, image) in images.iter().enumerate() {
-            let dimensions = image.dimensions();
+    /// Upload one frame into `layer` of an array texture, filling in every mip
+    /// level the texture has by repeatedly halving the frame
+    fn write_frame_layer(
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        layer: u32,
+        image: &RgbaImage,
+    ) {
+        Self::write_mip_level(queue, texture, layer, 0, image);
+
+        let mut level_image = None;
+        for level in 1..texture.mip_level_count() {
+            let smaller = downsample(level_image.as_ref().unwrap_or(image));
+            Self::write_mip_level(queue, texture, layer, level, &smaller);
+            level_image = Some(smaller);
+        }
+    }
 
-            let texture_size = wgpu::Extent3d {
-                width: dimensions.0,
-                height: dimensions.1,
+    fn write_mip_level(
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        layer: u32,
+        level: u32,
+        image: &RgbaImage,
+    ) {
+        let (width, height) = image.dimensions();
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: level,
+                origin: wgpu::Origin3d {
+                    x: 0,
+                    y: 0,
+                    z: layer,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            image,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            wgpu::Extent3d {
+                width,
+                height,
                 depth_or_array_layers: 1,
-            };
+            },
+        );
+    }
 
-            let texture = self.device.create_texture(&wgpu::TextureDescriptor {
-                label: Some(&format!("Image Texture {}", i)),
-                size: texture_size,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Rgba8UnormSrgb,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-                view_formats: &[],
-            });
+    /// Bind every layer of `texture` for the fragment shader, which picks one
+    /// through the `layer` uniform
+    fn create_texture_bind_group(&self, label: &str, texture: &wgpu::Texture) -> wgpu::BindGroup {
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
 
-            self.queue.write_texture(
-                wgpu::TexelCopyTextureInfo {
-                    texture: &texture,
-                    mip_level: 0,
-                    origin: wgpu::Origin3d::ZERO,
-                    aspect: wgpu::TextureAspect::All,
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(label),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture_view),
                 },
-                image,
-                wgpu::TexelCopyBufferLayout {
-                    offset: 0,
-                    bytes_per_row: Some(4 * dimensions.0),
-                    rows_per_image: Some(dimensions.1),
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
                 },
-                texture_size,
-            );
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.dimensions_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
 
-            let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    /// Add a frame to the end of an uncompressed sequence. The last frame
+    /// array doubles in size when it runs out of layers, and a new one is
+    /// started once it reaches the device limit.
+    pub fn append_image(&mut self, image: &RgbaImage) -> Result<()> {
+        let expected = (
+            self.current_dimensions.image_width as u32,
+            self.current_dimensions.image_height as u32,
+        );
+        if image.dimensions() != expected {
+            return Err(anyhow::anyhow!(
+                "Frame is {}x{}, but the sequence is {}x{}",
+                image.width(),
+                image.height(),
+                expected.0,
+                expected.1
+            ));
+        }
 
-            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some(&format!("Texture Bind Group {}", i)),
-                layout: &self.bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&texture_view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+        let (mut frame_arrays, frame_count) = match self.sequence_type.take() {
+            Some(SequenceType::Uncompressed {
+                frame_arrays,
+                frame_count,
+            }) => (frame_arrays, frame_count),
+            other @ Some(SequenceType::Compressed { .. } | SequenceType::Streamed { .. }) => {
+                self.sequence_type = other;
+                return Err(anyhow::anyhow!(
+                    "Frames can only be appended to an uncompressed, fully loaded sequence"
+                ));
+            }
+            None => return Err(anyhow::anyhow!("No sequence loaded")),
+        };
+
+        let (array, layer) = array_slot(frame_count, self.max_array_layers);
+        if array == frame_arrays.len() {
+            frame_arrays.push(self.create_frame_array(array, 1));
+        } else if layer >= frame_arrays[array].texture.depth_or_array_layers() {
+            let old = &frame_arrays[array].texture;
+            let grown = self.create_frame_array(
+                array,
+                (old.depth_or_array_layers() * 2).min(self.max_array_layers),
+            );
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Grow Frame Array Encoder"),
+                });
+            for level in 0..old.mip_level_count() {
+                encoder.copy_texture_to_texture(
+                    wgpu::TexelCopyTextureInfo {
+                        mip_level: level,
+                        ..old.as_image_copy()
                     },
-                    wgpu::BindGroupEntry {
-                        binding: 2,
-                        resource: self.dimensions_buffer.as_entire_binding(),
+                    wgpu::TexelCopyTextureInfo {
+                        mip_level: level,
+                        ..grown.texture.as_image_copy()
                     },
-                ],
-            });
-
-            texture_bind_groups.push(bind_group);
+                    old.size().mip_level_size(level, old.dimension()),
+                );
+            }
+            self.queue.submit(Some(encoder.finish()));
+            frame_arrays[array] = grown;
         }
+        Self::write_frame_layer(&self.queue, &frame_arrays[array].texture, layer, image);
 
         self.sequence_type = Some(SequenceType::Uncompressed {
-            texture_bind_groups,
+            frame_arrays,
+            frame_count: frame_count + 1,
         });
 
-        self.current_texture_index = 0;
-        log::info!(
-            "Preloaded {} images to GPU memory (uncompressed)",
-            images.len()
-        );
+        Ok(())
     }
 
     pub async fn preload_images_compressed(&mut self, images: &[RgbaImage]) -> Result<()> {
@@ -460,10 +1423,13 @@ impl Renderer {
         log::info!("Compressing {} images with delta compression", images.len());
 
         // Compress the sequence
-        let compressed_sequence = if let Some(ref mut compressor) = self.delta_compressor {
-            compressor.compress_sequence(images).await?
-        } else {
-            return Err(anyhow::anyhow!("Delta compressor not initialized"));
+        let compressed_sequence = match &mut self.delta_compressor {
+            Some(compressor) => {
+                compressor
+                    .compress_sequence(images, self.compression_options)
+                    .await?
+            }
+            None => compress_sequence_cpu(images, self.compression_options)?,
         };
 
         // Log compression statistics
@@ -475,6 +1441,34 @@ impl Renderer {
         );
         log::info!("Compression ratio: {:.2}x", compression_ratio);
 
+        self.compression_quality = None;
+        if self.measure_quality {
+            let quality = compressed_sequence
+                .measure_quality(self.delta_compressor.as_mut(), images)
+                .await?;
+            log::info!(
+                "Compression quality: {:.2} dB average PSNR, worst frame {} at {:.2} dB, \
+                 max channel error {}",
+                quality.average_psnr,
+                quality.worst_frame,
+                quality.worst_frame_psnr,
+                quality.max_channel_error
+            );
+            self.compression_quality = Some(quality);
+        }
+
+        if let Some(min_ratio) = self.min_compression_ratio
+            && compression_ratio < min_ratio
+        {
+            log::info!(
+                "Compression ratio {:.2}x is below the minimum of {:.2}x, using uncompressed frames",
+                compression_ratio,
+                min_ratio
+            );
+            self.preload_images(images);
+            return Ok(());
+        }
+
         // Create texture for current frame
         let texture_size = wgpu::Extent3d {
             width: first_dims.0,
@@ -501,7 +1495,7 @@ impl Renderer {
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            &compressed_sequence.base_frame,
+            compressed_sequence.base_frame(),
             wgpu::TexelCopyBufferLayout {
                 offset: 0,
                 bytes_per_row: Some(4 * first_dims.0),
@@ -510,33 +1504,19 @@ impl Renderer {
             texture_size,
         );
 
-        let texture_view =
-            current_frame_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let current_frame_bind_group =
+            self.create_texture_bind_group("Current Frame Bind Group", &current_frame_texture);
 
-        let current_frame_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Current Frame Bind Group"),
-            layout: &self.bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&self.sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: self.dimensions_buffer.as_entire_binding(),
-                },
-            ],
-        });
+        let (cached_bind_groups, cache_tail) = self.reconstruct_cache(&compressed_sequence).await?;
 
         self.sequence_type = Some(SequenceType::Compressed {
-            compressed_sequence,
+            compressed_sequence: Box::new(compressed_sequence),
             current_frame_texture,
             current_frame_bind_group,
             reconstructed_frame: Some(images[0].clone()),
+            reconstructed_index: 0,
+            cached_bind_groups,
+            cache_tail,
         });
 
         self.current_texture_index = 0;
@@ -545,19 +1525,58 @@ impl Renderer {
         Ok(())
     }
 
+    /// Reconstruct the first `cache_frames` frames into their own textures,
+    /// returning their bind groups and the last of them
+    async fn reconstruct_cache(
+        &mut self,
+        sequence: &CompressedSequence,
+    ) -> Result<(Vec<wgpu::BindGroup>, Option<RgbaImage>)> {
+        let count = self.cache_frames.min(sequence.frame_count);
+        if count == 0 {
+            return Ok((Vec::new(), None));
+        }
+
+        let started = std::time::Instant::now();
+        let mut bind_groups = Vec::with_capacity(count);
+        let mut frame = sequence.base_frame().clone();
+        for index in 0..count {
+            if let Some(keyframe) = sequence.keyframes.get(&index) {
+                frame = keyframe.clone();
+            } else {
+                frame = apply_delta(
+                    self.delta_compressor.as_mut(),
+                    &frame,
+                    &sequence.deltas[&index],
+                )
+                .await?;
+            }
+            let frame_array = self.create_frame_array(index, 1);
+            Self::write_frame_layer(&self.queue, &frame_array.texture, 0, &frame);
+            bind_groups.push(frame_array.bind_group);
+        }
+
+        log::info!(
+            "Reconstructed {} of {} frames into textures in {:?}",
+            count,
+            sequence.frame_count,
+            started.elapsed()
+        );
+        Ok((bind_groups, Some(frame)))
+    }
+
     pub async fn set_current_texture_index(&mut self, index: usize) -> Result<()> {
         match &mut self.sequence_type {
-            Some(SequenceType::Uncompressed {
-                texture_bind_groups,
-            }) => {
-                if !texture_bind_groups.is_empty() {
-                    self.current_texture_index = index % texture_bind_groups.len();
-                }
+            Some(SequenceType::Uncompressed { frame_count, .. }) if *frame_count > 0 => {
+                self.current_texture_index = index % *frame_count;
             }
+            Some(SequenceType::Uncompressed { .. }) => {}
             Some(SequenceType::Compressed {
                 compressed_sequence,
                 current_frame_texture,
                 reconstructed_frame,
+                reconstructed_index,
+                cached_bind_groups,
+                cache_tail,
                 ..
             }) => {
                 if index >= compressed_sequence.frame_count {
@@ -566,33 +1585,37 @@ impl Renderer {
 
                 self.current_texture_index = index;
 
-                // Reconstruct the frame if it's not the base frame
-                let new_frame = if index == 0 {
-                    compressed_sequence.base_frame.clone()
+                // Cached frames are drawn from their own textures as they are
+                if index < cached_bind_groups.len()
+                    || (*reconstructed_index == index && reconstructed_frame.is_some())
+                {
+                    return Ok(());
+                }
+
+                let compressor = self.delta_compressor.as_mut();
+                let previous = if index > 0 && index == cached_bind_groups.len() {
+                    cache_tail.as_ref()
                 } else {
-                    let delta_index = index - 1;
-                    if delta_index < compressed_sequence.deltas.len() {
-                        let base_frame = reconstructed_frame
-                            .as_ref()
-                            .ok_or_else(|| anyhow::anyhow!("No reconstructed frame available"))?;
-
-                        if let Some(ref mut compressor) = self.delta_compressor {
-                            compressor
-                                .reconstruct_frame(
-                                    base_frame,
-                                    &compressed_sequence.deltas[delta_index],
-                                )
-                                .await?
-                        } else {
-                            return Err(anyhow::anyhow!("Delta compressor not available"));
-                        }
-                    } else {
-                        return Ok(());
+                    reconstructed_frame
+                        .as_ref()
+                        .filter(|_| *reconstructed_index + 1 == index)
+                };
+                let new_frame = match (previous, compressed_sequence.deltas.get(&index)) {
+                    // Sequential playback only needs to apply the next delta
+                    (Some(previous), Some(delta)) => {
+                        apply_delta(compressor, previous, delta).await?
+                    }
+                    // Keyframes and any other jump replay from the nearest keyframe
+                    _ => {
+                        compressed_sequence
+                            .reconstruct_at(compressor, index)
+                            .await?
                     }
                 };
 
                 // Update the reconstructed frame for next iteration
                 *reconstructed_frame = Some(new_frame.clone());
+                *reconstructed_index = index;
 
                 // Upload the new frame to the current frame texture
                 let (width, height) = new_frame.dimensions();
@@ -616,6 +1639,19 @@ impl Renderer {
                     },
                 );
             }
+            Some(SequenceType::Streamed {
+                stream,
+                current_frame,
+                uploaded_index,
+            }) => {
+                let index = index % stream.count();
+                self.current_texture_index = index;
+                if *uploaded_index != index {
+                    let frame = stream.frame(index);
+                    Self::write_frame_layer(&self.queue, &current_frame.texture, 0, &frame);
+                    *uploaded_index = index;
+                }
+            }
             None => {}
         }
 
@@ -623,6 +1659,9 @@ impl Renderer {
     }
 
     pub fn render(&mut self) -> Result<()> {
+        #[cfg(feature = "shader-hot-reload")]
+        self.reload_changed_shaders();
+
         let surface = match &self.surface {
             Some(surface) => surface,
             None => {
@@ -642,31 +1681,56 @@ impl Renderer {
                 label: Some("Render Encoder"),
             });
 
-        let bind_group = match &self.sequence_type {
+        let (bind_group, layer) = match &self.sequence_type {
             Some(SequenceType::Uncompressed {
-                texture_bind_groups,
-            }) => {
-                if !texture_bind_groups.is_empty() {
-                    Some(&texture_bind_groups[self.current_texture_index])
-                } else {
-                    None
-                }
+                frame_arrays,
+                frame_count,
+            }) if *frame_count > 0 => {
+                let (array, layer) = array_slot(self.current_texture_index, self.max_array_layers);
+                (Some(&frame_arrays[array].bind_group), layer)
             }
+            Some(SequenceType::Uncompressed { .. }) => (None, 0),
             Some(SequenceType::Compressed {
                 current_frame_bind_group,
+                cached_bind_groups,
                 ..
-            }) => Some(current_frame_bind_group),
-            None => None,
+            }) => (
+                Some(
+                    cached_bind_groups
+                        .get(self.current_texture_index)
+                        .unwrap_or(current_frame_bind_group),
+                ),
+                0,
+            ),
+            Some(SequenceType::Streamed { current_frame, .. }) => {
+                (Some(&current_frame.bind_group), 0)
+            }
+            None => (None, 0),
         };
 
+        if layer != self.current_dimensions.layer {
+            self.current_dimensions.layer = layer;
+            self.queue.write_buffer(
+                &self.dimensions_buffer,
+                0,
+                bytemuck::cast_slice(&[self.current_dimensions]),
+            );
+        }
+
         if let Some(bind_group) = bind_group {
+            let load = if self.clear_each_frame {
+                wgpu::LoadOp::Clear(self.background)
+            } else {
+                wgpu::LoadOp::Load
+            };
+
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                     view: &view,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        load,
                         store: wgpu::StoreOp::Store,
                     },
                 })],
@@ -678,6 +1742,12 @@ impl Renderer {
             render_pass.set_pipeline(&self.pipeline);
             render_pass.set_bind_group(0, bind_group, &[]);
             render_pass.draw(0..4, 0..1);
+
+            if let Some(stats) = &self.stats {
+                render_pass.set_pipeline(&self.stats_pipeline);
+                render_pass.set_bind_group(0, &stats.bind_group, &[]);
+                render_pass.draw(0..4, 0..1);
+            }
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
@@ -694,3 +1764,235 @@ impl Drop for Renderer {
         self.cleanup();
     }
 }
+
+/// Levels in a full mip chain for a `width` x `height` texture, down to 1x1
+fn mip_level_count(width: u32, height: u32) -> u32 {
+    u32::BITS - width.max(height).max(1).leading_zeros()
+}
+
+/// Bytes of RGBA8 data in the first `levels` mip levels of a `width` x `height` texture
+fn mip_chain_bytes(width: u32, height: u32, levels: u32) -> usize {
+    (0..levels)
+        .map(|level| ((width >> level).max(1) * (height >> level).max(1)) as usize * 4)
+        .sum()
+}
+
+/// Halve `image` in each dimension (down to 1 pixel) for the next mip level.
+/// Each pixel averages a 2x2 block in linear light, weighting color by alpha
+/// so transparent pixels don't darken the edges of what's drawn.
+fn downsample(image: &RgbaImage) -> RgbaImage {
+    let to_linear = |c: u8| (c as f32 / 255.0).powf(2.2);
+    let to_srgb = |c: f32| (c.powf(1.0 / 2.2) * 255.0).round() as u8;
+    let (width, height) = image.dimensions();
+
+    RgbaImage::from_fn((width / 2).max(1), (height / 2).max(1), |x, y| {
+        let mut color = [0.0f32; 3];
+        let mut alpha = 0.0;
+        for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            let pixel = image.get_pixel((x * 2 + dx).min(width - 1), (y * 2 + dy).min(height - 1));
+            let weight = pixel[3] as f32 / 255.0;
+            for (sum, &channel) in color.iter_mut().zip(&pixel.0[..3]) {
+                *sum += to_linear(channel) * weight;
+            }
+            alpha += weight;
+        }
+        if alpha == 0.0 {
+            return image::Rgba([0, 0, 0, 0]);
+        }
+        image::Rgba([
+            to_srgb(color[0] / alpha),
+            to_srgb(color[1] / alpha),
+            to_srgb(color[2] / alpha),
+            (alpha / 4.0 * 255.0).round() as u8,
+        ])
+    })
+}
+
+fn select_present_mode(mode: PresentMode, supported: &[wgpu::PresentMode]) -> wgpu::PresentMode {
+    let requested = mode.to_wgpu();
+    if supported.contains(&requested) {
+        return requested;
+    }
+    log::warn!(
+        "Present mode {:?} is not supported here (supported: {:?}), using Fifo",
+        requested,
+        supported
+    );
+    wgpu::PresentMode::Fifo
+}
+
+/// How the compositor treats the alpha we present: premultiplied as the
+/// shaders output it, else left to the window system, else opaque
+fn select_alpha_mode(
+    opaque: bool,
+    supported: &[wgpu::CompositeAlphaMode],
+) -> wgpu::CompositeAlphaMode {
+    let preferred: &[wgpu::CompositeAlphaMode] = if opaque {
+        &[wgpu::CompositeAlphaMode::Opaque]
+    } else {
+        &[
+            wgpu::CompositeAlphaMode::PreMultiplied,
+            wgpu::CompositeAlphaMode::Inherit,
+            wgpu::CompositeAlphaMode::Opaque,
+        ]
+    };
+    preferred
+        .iter()
+        .find(|mode| supported.contains(mode))
+        .or(supported.first())
+        .copied()
+        .unwrap_or(wgpu::CompositeAlphaMode::Auto)
+}
+
+/// Frame array and layer holding frame `index`, when every array but the
+/// last holds `max_layers` frames
+fn array_slot(index: usize, max_layers: u32) -> (usize, u32) {
+    let max_layers = max_layers as usize;
+    (index / max_layers, (index % max_layers) as u32)
+}
+
+/// Clear value for a straight-alpha sRGB color on the premultiplied surface.
+/// sRGB surfaces take linear values and encode them on write.
+fn clear_color(rgba: [u8; 4], srgb_surface: bool) -> wgpu::Color {
+    let channel = |value: u8| {
+        let value = f64::from(value) / 255.0;
+        if !srgb_surface {
+            value
+        } else if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let alpha = f64::from(rgba[3]) / 255.0;
+    wgpu::Color {
+        r: channel(rgba[0]) * alpha,
+        g: channel(rgba[1]) * alpha,
+        b: channel(rgba[2]) * alpha,
+        a: alpha,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate_wgsl(source: &str) {
+        let module = naga::front::wgsl::parse_str(source).expect("shader should parse");
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .expect("shader should validate");
+    }
+
+    #[test]
+    fn test_array_slot() {
+        assert_eq!(array_slot(0, 256), (0, 0));
+        assert_eq!(array_slot(255, 256), (0, 255));
+        assert_eq!(array_slot(256, 256), (1, 0));
+        assert_eq!(array_slot(600, 256), (2, 88));
+    }
+
+    #[test]
+    fn test_mip_chain() {
+        assert_eq!(mip_level_count(1, 1), 1);
+        assert_eq!(mip_level_count(256, 64), 9);
+        assert_eq!(mip_level_count(300, 7), 9);
+        assert_eq!(mip_chain_bytes(4, 2, 3), (8 + 2 + 1) * 4);
+
+        // One transparent pixel in four leaves the color alone and lowers coverage
+        let red = image::Rgba([200, 0, 0, 255]);
+        let mut image = RgbaImage::from_pixel(3, 2, red);
+        image.put_pixel(1, 1, image::Rgba([0, 0, 0, 0]));
+        let smaller = downsample(&image);
+        assert_eq!(smaller.dimensions(), (1, 1));
+        assert_eq!(*smaller.get_pixel(0, 0), image::Rgba([200, 0, 0, 191]));
+    }
+
+    #[test]
+    fn test_select_present_mode() {
+        let supported = [wgpu::PresentMode::Fifo, wgpu::PresentMode::Mailbox];
+        assert_eq!(
+            select_present_mode(PresentMode::Mailbox, &supported),
+            wgpu::PresentMode::Mailbox
+        );
+        assert_eq!(
+            select_present_mode(PresentMode::Immediate, &supported),
+            wgpu::PresentMode::Fifo
+        );
+    }
+
+    #[test]
+    fn test_select_alpha_mode() {
+        use wgpu::CompositeAlphaMode::{Inherit, Opaque, PostMultiplied, PreMultiplied};
+
+        assert_eq!(
+            select_alpha_mode(false, &[Opaque, PreMultiplied]),
+            PreMultiplied
+        );
+        assert_eq!(select_alpha_mode(false, &[Opaque, Inherit]), Inherit);
+        // Without a compositor only opaque windows may be on offer
+        assert_eq!(select_alpha_mode(false, &[Opaque]), Opaque);
+        assert_eq!(select_alpha_mode(true, &[PreMultiplied, Opaque]), Opaque);
+        assert_eq!(select_alpha_mode(true, &[PostMultiplied]), PostMultiplied);
+    }
+
+    #[test]
+    fn test_clear_color() {
+        assert_eq!(clear_color([0, 0, 0, 0], true), wgpu::Color::TRANSPARENT);
+        assert_eq!(clear_color([255, 255, 255, 255], true), wgpu::Color::WHITE);
+
+        // Premultiplied by alpha, and linearized only for sRGB surfaces
+        let color = clear_color([255, 128, 0, 128], false);
+        assert!((color.a - 128.0 / 255.0).abs() < 1e-9);
+        assert!((color.r - color.a).abs() < 1e-9);
+        assert!((color.g - 128.0 / 255.0 * color.a).abs() < 1e-9);
+        assert_eq!(color.b, 0.0);
+        let linear = clear_color([128, 128, 128, 255], true);
+        assert!((linear.r - 0.2158).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_overlay_shaders_validate() {
+        validate_wgsl(VERTEX_SHADER);
+        validate_wgsl(FRAGMENT_SHADER);
+    }
+
+    #[test]
+    fn test_stats_uniforms_match_shader() {
+        validate_wgsl(STATS_SHADER);
+
+        let module = naga::front::wgsl::parse_str(STATS_SHADER).unwrap();
+        let mut layouter = naga::proc::Layouter::default();
+        layouter.update(module.to_ctx()).unwrap();
+
+        let (handle, _) = module
+            .types
+            .iter()
+            .find(|(_, ty)| ty.name.as_deref() == Some("StatsUniforms"))
+            .expect("stats shader should declare StatsUniforms");
+        assert_eq!(
+            layouter[handle].size as usize,
+            std::mem::size_of::<StatsUniforms>()
+        );
+    }
+
+    #[test]
+    fn test_dimensions_match_shader_uniforms() {
+        let module = naga::front::wgsl::parse_str(FRAGMENT_SHADER).unwrap();
+        let mut layouter = naga::proc::Layouter::default();
+        layouter.update(module.to_ctx()).unwrap();
+
+        let (handle, _) = module
+            .types
+            .iter()
+            .find(|(_, ty)| ty.name.as_deref() == Some("Uniforms"))
+            .expect("fragment shader should declare Uniforms");
+        assert_eq!(
+            layouter[handle].size as usize,
+            std::mem::size_of::<Dimensions>()
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use std::borrow::Cow;
+
+#[cfg(feature = "shader-hot-reload")]
+use anyhow::Result;
+#[cfg(feature = "shader-hot-reload")]
+use std::collections::HashSet;
+#[cfg(feature = "shader-hot-reload")]
+use std::path::PathBuf;
+#[cfg(feature = "shader-hot-reload")]
+use std::sync::mpsc::{Receiver, channel};
+
+/// Directory the hot-reload feature reads WGSL files from.
+///
+/// Defaults to the `src/shaders` directory of the source checkout and can be
+/// overridden with `ANIBUDDY_SHADER_DIR`.
+#[cfg(feature = "shader-hot-reload")]
+pub fn shader_dir() -> PathBuf {
+    std::env::var_os("ANIBUDDY_SHADER_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders")))
+}
+
+/// Get the WGSL source for a shader.
+///
+/// With the `shader-hot-reload` feature the file is read from [`shader_dir`],
+/// falling back to the embedded copy when it can't be read. Without the
+/// feature the embedded copy is always used.
+pub fn shader_source(name: &str, embedded: &'static str) -> Cow<'static, str> {
+    #[cfg(feature = "shader-hot-reload")]
+    {
+        let path = shader_dir().join(name);
+        match std::fs::read_to_string(&path) {
+            Ok(source) => return Cow::Owned(source),
+            Err(e) => log::debug!(
+                "Using embedded {} ({} unavailable: {})",
+                name,
+                path.display(),
+                e
+            ),
+        }
+    }
+
+    #[cfg(not(feature = "shader-hot-reload"))]
+    let _ = name;
+
+    Cow::Borrowed(embedded)
+}
+
+/// Watches the shader directory and reports which WGSL files changed
+#[cfg(feature = "shader-hot-reload")]
+pub struct ShaderWatcher {
+    _watcher: notify::RecommendedWatcher,
+    receiver: Receiver<PathBuf>,
+}
+
+#[cfg(feature = "shader-hot-reload")]
+impl ShaderWatcher {
+    pub fn new() -> Result<Self> {
+        use notify::{EventKind, RecursiveMode, Watcher};
+
+        let (sender, receiver) = channel();
+        let mut watcher =
+            notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+                if let Ok(event) = result
+                    && matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                {
+                    for path in event.paths {
+                        let _ = sender.send(path);
+                    }
+                }
+            })?;
+
+        let dir = shader_dir();
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        log::info!("Watching {} for shader changes", dir.display());
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver,
+        })
+    }
+
+    /// Drain pending events, returning the file names of changed shaders
+    pub fn changed_shaders(&self) -> HashSet<String> {
+        self.receiver
+            .try_iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "wgsl"))
+            .filter_map(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .map(str::to_string)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,39 @@
+struct DeltaParams {
+    // Stored value for a full-range channel difference, at most 32767
+    scale: f32,
+    // Side of the square tiles tile_mask has one texel for
+    tile_size: u32,
+    _padding0: u32,
+    _padding1: u32,
+}
+
+@group(0) @binding(0)
+var current_frame: texture_2d<f32>;
+@group(0) @binding(1)
+var previous_frame: texture_2d<f32>;
+@group(0) @binding(2)
+var delta_output: texture_storage_2d<rgba16sint, write>;
+@group(0) @binding(3)
+var<uniform> params: DeltaParams;
+
+@compute @workgroup_size(8, 8)
+fn cs_main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let dims = textureDimensions(current_frame);
+    let coords = vec2<i32>(i32(global_id.x), i32(global_id.y));
+    
+    if (coords.x >= i32(dims.x) || coords.y >= i32(dims.y)) {
+        return;
+    }
+    
+    let current_pixel = textureLoad(current_frame, coords, 0);
+    let previous_pixel = textureLoad(previous_frame, coords, 0);
+    
+    // Calculate delta as signed difference of straight-alpha values, every channel independently
+    let delta = current_pixel - previous_pixel;
+    
+    // Convert to signed 16-bit integers in the [-scale, scale] range
+    let scale = params.scale;
+    let delta_int = vec4<i32>(clamp(delta * scale, vec4<f32>(-scale), vec4<f32>(scale)));
+    
+    textureStore(delta_output, coords, delta_int);
+}
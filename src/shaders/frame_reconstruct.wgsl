@@ -0,0 +1,45 @@
+struct DeltaParams {
+    // Stored value for a full-range channel difference, at most 32767
+    scale: f32,
+    // Side of the square tiles tile_mask has one texel for
+    tile_size: u32,
+    _padding0: u32,
+    _padding1: u32,
+}
+
+@group(0) @binding(0)
+var base_frame: texture_2d<f32>;
+@group(0) @binding(1)
+var delta_frame: texture_2d<i32>;
+@group(0) @binding(2)
+var output_frame: texture_storage_2d<rgba8unorm, write>;
+@group(0) @binding(3)
+var<uniform> params: DeltaParams;
+@group(0) @binding(4)
+var tile_mask: texture_2d<u32>;
+
+@compute @workgroup_size(8, 8)
+fn cs_main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let dims = textureDimensions(base_frame);
+    let coords = vec2<i32>(i32(global_id.x), i32(global_id.y));
+    
+    if (coords.x >= i32(dims.x) || coords.y >= i32(dims.y)) {
+        return;
+    }
+    
+    let base_pixel = textureLoad(base_frame, coords, 0);
+
+    // Tiles a sparse delta left out hold stale data and leave the base unchanged
+    if (textureLoad(tile_mask, coords / i32(params.tile_size), 0).r == 0u) {
+        textureStore(output_frame, coords, base_pixel);
+        return;
+    }
+
+    let delta_pixel = textureLoad(delta_frame, coords, 0);
+    
+    // Convert delta back to a normalized difference
+    let delta_float = vec4<f32>(delta_pixel) / params.scale;
+    
+    let reconstructed = clamp(base_pixel + delta_float, vec4<f32>(0.0), vec4<f32>(1.0));
+    textureStore(output_frame, coords, reconstructed);
+}
@@ -0,0 +1,104 @@
+@group(0) @binding(0)
+var t_diffuse: texture_2d_array<f32>;
+@group(0) @binding(1)
+var s_diffuse: sampler;
+struct Uniforms {
+    dimensions: vec4<f32>, // window_width, window_height, image_width, image_height
+    posterize_levels: f32, // levels per color channel, 0 when off
+    fill: f32, // 1 stretches over the window, 0 fits preserving aspect ratio
+    tile: vec2<f32>, // copies of the image across and down, wrapped by the sampler
+    opacity: f32, // multiplies alpha to fade the whole overlay
+    layer: u32, // layer of t_diffuse holding the current frame
+    transform: u32, // clockwise quarter turns in bits 0-1, horizontal flip bit 2, vertical flip bit 3
+}
+
+@group(0) @binding(2)
+var<uniform> uniforms: Uniforms;
+
+// Map an offset from the center of the displayed tile grid back onto the
+// untransformed grid: undo the flips, then the clockwise quarter turns
+fn untransform(offset: vec2<f32>) -> vec2<f32> {
+    var p = offset;
+    if (uniforms.transform & 4u) != 0u {
+        p.x = -p.x;
+    }
+    if (uniforms.transform & 8u) != 0u {
+        p.y = -p.y;
+    }
+    switch uniforms.transform & 3u {
+        case 1u: {
+            return vec2<f32>(p.y, -p.x);
+        }
+        case 2u: {
+            return -p;
+        }
+        case 3u: {
+            return vec2<f32>(-p.y, p.x);
+        }
+        default: {
+            return p;
+        }
+    }
+}
+
+// Selected per pipeline: 0 = over, 1 = additive, 2 = multiply, 3 = screen
+override BLEND_MODE: u32 = 0u;
+
+@fragment
+fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    let window_size = uniforms.dimensions.xy;
+    // Turned on its side, each frame shows as tall as it is wide and the grid
+    // of copies holds transposed in the texture's wrapped coordinates
+    let sideways = (uniforms.transform & 1u) != 0u;
+    var image_size = uniforms.dimensions.zw;
+    var texture_tile = uniforms.tile;
+    if sideways {
+        image_size = image_size.yx;
+        texture_tile = texture_tile.yx;
+    }
+
+    var uv = pos.xy / window_size;
+    var inside = true;
+    if uniforms.fill < 0.5 {
+        // Scale the whole tile grid to fit, centered, leaving transparent bars
+        let content_size = image_size * uniforms.tile;
+        let scale = min(window_size.x / content_size.x, window_size.y / content_size.y);
+        let fitted_size = content_size * scale;
+        uv = (pos.xy - (window_size - fitted_size) * 0.5) / fitted_size;
+        inside = all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0));
+    }
+    let tex_coords = untransform((uv - 0.5) * uniforms.tile) + texture_tile * 0.5;
+
+    // Sample the texture. This has to happen in uniform control flow, so
+    // pixels outside the fitted image are cleared afterwards
+    var color = textureSample(t_diffuse, s_diffuse, tex_coords, uniforms.layer);
+    if !inside {
+        color = vec4<f32>(0.0);
+    }
+    color.a *= uniforms.opacity;
+
+    // Quantize the straight (unpremultiplied) color, leaving alpha alone so
+    // anti-aliased edges keep their soft coverage
+    let levels = uniforms.posterize_levels;
+    if levels >= 2.0 {
+        color = vec4<f32>(round(color.rgb * (levels - 1.0)) / (levels - 1.0), color.a);
+    }
+
+    // The compositor blends our premultiplied output as `out + desktop * (1 - out.a)`,
+    // so multiply and screen are expressed through the alpha channel. They are exact
+    // for grayscale content and use luminance as the per-pixel factor otherwise.
+    let luminance = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+    switch BLEND_MODE {
+        case 2u: {
+            // Darken the desktop by the image, adding no color of our own
+            return vec4<f32>(0.0, 0.0, 0.0, color.a * (1.0 - luminance));
+        }
+        case 3u: {
+            // Add the image color while only partially covering the desktop
+            return vec4<f32>(color.rgb * color.a, color.a * luminance);
+        }
+        default: {
+            return color;
+        }
+    }
+}
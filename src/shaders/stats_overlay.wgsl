@@ -0,0 +1,30 @@
+// Draws the debug statistics text image in a corner of the window, with each
+// of its pixels covering a `scale` x `scale` block of window pixels
+
+struct StatsUniforms {
+    rect: vec4<f32>, // left, top, width, height in window pixels
+    window_size: vec2<f32>,
+    scale: f32,
+    _padding: f32,
+}
+
+@group(0) @binding(0)
+var t_text: texture_2d<f32>;
+@group(0) @binding(1)
+var<uniform> uniforms: StatsUniforms;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // Quad corners as a triangle strip
+    let corner = vec2<f32>(f32(vertex_index & 1u), f32(vertex_index >> 1u));
+    let pixel = uniforms.rect.xy + corner * uniforms.rect.zw;
+    let ndc = pixel / uniforms.window_size * 2.0 - 1.0;
+    return vec4<f32>(ndc.x, -ndc.y, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+    let texel = vec2<i32>((pos.xy - uniforms.rect.xy) / uniforms.scale);
+    let size = vec2<i32>(textureDimensions(t_text));
+    return textureLoad(t_text, clamp(texel, vec2<i32>(0), size - 1), 0);
+}
@@ -0,0 +1,174 @@
+use image::{Rgba, RgbaImage};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Glyph cell of the built-in font, in font pixels
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+/// Blank font pixels between characters and lines, and around the text
+const SPACING: u32 = 1;
+const PADDING: u32 = 2;
+
+const TEXT_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const BACKDROP_COLOR: Rgba<u8> = Rgba([0, 0, 0, 160]);
+
+/// Rows of a 5x7 glyph from top to bottom, the leftmost pixel in bit 4.
+/// Letters are drawn uppercase; characters without a glyph show as `?`.
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        '0' => [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e],
+        '1' => [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        '2' => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f],
+        '3' => [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e],
+        '4' => [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02],
+        '5' => [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e],
+        '6' => [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e],
+        '7' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e],
+        '9' => [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c],
+        'A' => [0x0e, 0x11, 0x11, 0x11, 0x1f, 0x11, 0x11],
+        'B' => [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e],
+        'C' => [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e],
+        'D' => [0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c],
+        'E' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f],
+        'F' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10],
+        'G' => [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f],
+        'H' => [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+        'I' => [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f],
+        'M' => [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'P' => [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10],
+        'Q' => [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d],
+        'R' => [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11],
+        'S' => [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e],
+        'T' => [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a],
+        'X' => [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04],
+        'Z' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f],
+        ' ' => [0x00; 7],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c],
+        ':' => [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        '-' => [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00],
+        _ => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
+
+/// Draw `lines` in the built-in font as white text on a translucent dark
+/// backdrop, one image pixel per font pixel
+pub fn render_text(lines: &[String]) -> RgbaImage {
+    let columns = lines
+        .iter()
+        .map(|line| line.chars().count() as u32)
+        .max()
+        .unwrap_or(0);
+    let rows = lines.len() as u32;
+    let width = 2 * PADDING + (columns * (GLYPH_WIDTH + SPACING)).saturating_sub(SPACING);
+    let height = 2 * PADDING + (rows * (GLYPH_HEIGHT + SPACING)).saturating_sub(SPACING);
+
+    let mut image = RgbaImage::from_pixel(width.max(1), height.max(1), BACKDROP_COLOR);
+    for (row, line) in lines.iter().enumerate() {
+        let top = PADDING + row as u32 * (GLYPH_HEIGHT + SPACING);
+        for (column, c) in line.chars().enumerate() {
+            let left = PADDING + column as u32 * (GLYPH_WIDTH + SPACING);
+            for (y, bits) in glyph(c).iter().enumerate() {
+                for x in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - x)) != 0 {
+                        image.put_pixel(left + x, top + y as u32, TEXT_COLOR);
+                    }
+                }
+            }
+        }
+    }
+    image
+}
+
+/// Frames per second averaged over the last second of frame changes
+#[derive(Default)]
+pub struct FpsCounter {
+    frames: VecDeque<Instant>,
+}
+
+impl FpsCounter {
+    const WINDOW: Duration = Duration::from_secs(1);
+
+    /// Note that a new frame was shown at `now`
+    pub fn record(&mut self, now: Instant) {
+        self.frames.push_back(now);
+        self.expire(now);
+    }
+
+    /// Average rate over the frames shown in the last second, or 0 until
+    /// there are two of them
+    pub fn fps(&mut self, now: Instant) -> f64 {
+        self.expire(now);
+        match (self.frames.front(), self.frames.back()) {
+            (Some(first), Some(last)) if last > first => {
+                (self.frames.len() - 1) as f64 / (*last - *first).as_secs_f64()
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some(&first) = self.frames.front()
+            && now.duration_since(first) > Self::WINDOW
+        {
+            self.frames.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_text() {
+        let image = render_text(&["FPS 1".to_string(), "ab".to_string()]);
+        // Five columns and two rows of glyphs, plus spacing and padding
+        assert_eq!(image.dimensions(), (33, 19));
+        assert_eq!(*image.get_pixel(0, 0), BACKDROP_COLOR);
+
+        // The top bar of the F, and the gap between it and the P
+        for x in 0..5 {
+            assert_eq!(*image.get_pixel(PADDING + x, PADDING), TEXT_COLOR);
+        }
+        assert_eq!(*image.get_pixel(PADDING + 5, PADDING), BACKDROP_COLOR);
+
+        // Lowercase letters use the uppercase glyphs
+        assert_eq!(
+            render_text(&["a".to_string()]),
+            render_text(&["A".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_fps_rolling_average() {
+        let start = Instant::now();
+        let mut counter = FpsCounter::default();
+        assert_eq!(counter.fps(start), 0.0);
+
+        for frame in 0..=20 {
+            counter.record(start + Duration::from_millis(50 * frame));
+        }
+        let now = start + Duration::from_millis(1000);
+        assert!((counter.fps(now) - 20.0).abs() < 1e-9);
+
+        // Frames older than a second drop out of the average
+        for frame in 0..=10 {
+            counter.record(now + Duration::from_millis(100 * frame));
+        }
+        let later = now + Duration::from_millis(1050);
+        assert!((counter.fps(later) - 10.0).abs() < 1e-9);
+        assert_eq!(counter.fps(later + Duration::from_secs(2)), 0.0);
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::Result;
+use std::fs::File as StdFile;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const HEADER: &str = "timestamp_s,frame,target_ms,delta_ms,reconstruct_ms";
+
+/// Per-frame timing log written as CSV while the overlay plays.
+///
+/// Rows are buffered and only reach the file when the buffer fills or on
+/// [`TimingTrace::flush`], so tracing stays cheap on the render path.
+pub struct TimingTrace {
+    writer: BufWriter<StdFile>,
+    last_frame: Option<Instant>,
+}
+
+impl TimingTrace {
+    pub fn create(path: &Path) -> Result<Self> {
+        let mut writer = BufWriter::new(StdFile::create(path)?);
+        writeln!(writer, "{}", HEADER)?;
+        log::info!("Tracing frame timing to {}", path.display());
+
+        Ok(Self {
+            writer,
+            last_frame: None,
+        })
+    }
+
+    /// Record that `frame` was just displayed. `reconstruct` is the time spent
+    /// rebuilding it from deltas, for compressed playback.
+    pub fn record(
+        &mut self,
+        frame: usize,
+        target: Duration,
+        reconstruct: Option<Duration>,
+    ) -> Result<()> {
+        let now = Instant::now();
+        let delta = self.last_frame.map(|last| now - last);
+        self.last_frame = Some(now);
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+
+        writeln!(
+            self.writer,
+            "{:.6},{},{:.3},{},{}",
+            timestamp,
+            frame,
+            millis(target),
+            delta
+                .map(|d| format!("{:.3}", millis(d)))
+                .unwrap_or_default(),
+            reconstruct
+                .map(|d| format!("{:.3}", millis(d)))
+                .unwrap_or_default()
+        )?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_rows() {
+        let path = std::env::temp_dir().join(format!("anibuddy-trace-{}.csv", std::process::id()));
+
+        let mut trace = TimingTrace::create(&path).unwrap();
+        trace.record(0, Duration::from_millis(40), None).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        trace
+            .record(
+                1,
+                Duration::from_millis(40),
+                Some(Duration::from_micros(1500)),
+            )
+            .unwrap();
+        trace.flush().unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let rows: Vec<Vec<&str>> = contents
+            .lines()
+            .map(|line| line.split(',').collect())
+            .collect();
+        assert_eq!(rows[0].join(","), HEADER);
+        assert_eq!(rows.len(), 3);
+
+        assert_eq!(rows[1][1..], ["0", "40.000", "", ""]);
+        assert_eq!(rows[2][1], "1");
+        assert!(rows[2][3].parse::<f64>().unwrap() >= 5.0);
+        assert_eq!(rows[2][4], "1.500");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}